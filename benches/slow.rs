@@ -1,6 +1,9 @@
 use bellman::{
     multicore::Worker,
-    multiexp::{multiexp, FullDensity},
+    multiexp::{
+        multiexp, multiexp_precomputed, multiexp_with_config, Digits, FullDensity, MultiexpConfig,
+        PrecomputedBases,
+    },
 };
 use bls12_381::{Bls12, Scalar};
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
@@ -31,7 +34,7 @@ fn bench_parts(c: &mut Criterion) {
 
     c.bench_with_input(
         BenchmarkId::new("multiexp", samples),
-        &(pool, g, v_bits),
+        &(pool.clone(), g.clone(), v_bits.clone()),
         |b, (pool, g, v_bits)| {
             b.iter(|| {
                 let _: <Bls12 as Engine>::G1 =
@@ -41,6 +44,48 @@ fn bench_parts(c: &mut Criterion) {
             })
         },
     );
+
+    let mut group = c.benchmark_group("multiexp_config");
+    for digits in [Digits::Unsigned, Digits::Signed] {
+        let config = MultiexpConfig {
+            digits,
+            window: None,
+        };
+        group.bench_with_input(
+            BenchmarkId::new(format!("{:?}", digits), samples),
+            &(&pool, &g, &v_bits),
+            |b, (pool, g, v_bits)| {
+                b.iter(|| {
+                    let _: <Bls12 as Engine>::G1 = multiexp_with_config(
+                        pool,
+                        ((*g).clone(), 0),
+                        FullDensity,
+                        (*v_bits).clone(),
+                        config,
+                    )
+                    .wait()
+                    .unwrap();
+                })
+            },
+        );
+    }
+
+    for window in [12, 16] {
+        let table = PrecomputedBases::<<Bls12 as Engine>::G1>::new(&pool, &g, window).unwrap();
+        group.bench_with_input(
+            BenchmarkId::new(format!("Precomputed({})", window), samples),
+            &(&pool, &table, &v_bits),
+            |b, (pool, table, v_bits)| {
+                b.iter(|| {
+                    let _: <Bls12 as Engine>::G1 =
+                        multiexp_precomputed(pool, table, FullDensity, (*v_bits).clone())
+                            .wait()
+                            .unwrap();
+                })
+            },
+        );
+    }
+    group.finish();
 }
 
 criterion_group!(benches, bench_parts);
@@ -0,0 +1,866 @@
+//! Interoperability with the [circom] circuit compiler.
+//!
+//! circom compiles circuits to the iden3 binary `.r1cs` format, and computes
+//! witnesses in the binary `.wtns` format. This module parses both, and
+//! provides [`CircomCircuit`], a [`Circuit`] that replays the parsed
+//! constraints onto any [`ConstraintSystem`].
+//!
+//! In both formats, wire 0 is the constant `1`, followed by the public outputs
+//! and public inputs of the circuit, followed by its private wires. Field
+//! elements are stored as little-endian integers, so this module assumes that
+//! the scalar field has a little-endian [`PrimeField::Repr`].
+//!
+//! In the other direction, [`R1csExporter`] records any [`Circuit`] so that it
+//! can be written out with [`R1cs::write`] and [`write_witness`], for auditing
+//! or cross-checking with other tooling.
+//!
+//! [circom]: https://github.com/iden3/circom
+
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use ff::PrimeField;
+
+use crate::lc::proc_lc;
+use crate::{Circuit, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
+
+const R1CS_MAGIC: &[u8; 4] = b"r1cs";
+const WTNS_MAGIC: &[u8; 4] = b"wtns";
+
+const R1CS_HEADER_SECTION: u32 = 1;
+const R1CS_CONSTRAINTS_SECTION: u32 = 2;
+const R1CS_WIRE_TO_LABEL_SECTION: u32 = 3;
+
+const WTNS_HEADER_SECTION: u32 = 1;
+const WTNS_WITNESS_SECTION: u32 = 2;
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// Reads an iden3 binary container, returning the `(type, contents)` of each
+/// section in file order.
+fn read_sections<R: Read>(mut reader: R, magic: &[u8; 4]) -> io::Result<Vec<(u32, Vec<u8>)>> {
+    let mut file_magic = [0u8; 4];
+    reader.read_exact(&mut file_magic)?;
+    if &file_magic != magic {
+        return Err(invalid_data("unexpected file magic"));
+    }
+
+    // The version does not change the layout of the sections we understand.
+    let _version = reader.read_u32::<LittleEndian>()?;
+    let num_sections = reader.read_u32::<LittleEndian>()?;
+
+    let mut sections = vec![];
+    for _ in 0..num_sections {
+        let ty = reader.read_u32::<LittleEndian>()?;
+        let len = reader.read_u64::<LittleEndian>()?;
+
+        let mut contents = vec![];
+        (&mut reader).take(len).read_to_end(&mut contents)?;
+        if contents.len() as u64 != len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "truncated section",
+            ));
+        }
+
+        sections.push((ty, contents));
+    }
+
+    Ok(sections)
+}
+
+/// Writes an iden3 binary container with the given `(type, contents)`
+/// sections.
+fn write_sections<W: Write>(
+    mut writer: W,
+    magic: &[u8; 4],
+    version: u32,
+    sections: &[(u32, Vec<u8>)],
+) -> io::Result<()> {
+    writer.write_all(magic)?;
+    writer.write_u32::<LittleEndian>(version)?;
+    writer.write_u32::<LittleEndian>(sections.len() as u32)?;
+
+    for (ty, contents) in sections {
+        writer.write_u32::<LittleEndian>(*ty)?;
+        writer.write_u64::<LittleEndian>(contents.len() as u64)?;
+        writer.write_all(contents)?;
+    }
+
+    Ok(())
+}
+
+fn find_section(sections: &[(u32, Vec<u8>)], ty: u32) -> io::Result<&[u8]> {
+    sections
+        .iter()
+        .find(|(t, _)| *t == ty)
+        .map(|(_, contents)| &contents[..])
+        .ok_or_else(|| invalid_data(format!("missing section {}", ty)))
+}
+
+/// Returns the modulus of `S` as a little-endian integer of `S::Repr` length.
+fn modulus_le<S: PrimeField>() -> Vec<u8> {
+    let mut modulus = (-S::one()).to_repr().as_ref().to_vec();
+    for b in modulus.iter_mut() {
+        let (sum, carry) = b.overflowing_add(1);
+        *b = sum;
+        if !carry {
+            break;
+        }
+    }
+
+    modulus
+}
+
+/// Reads the field element size and prime of a header, checking that the
+/// prime is the modulus of `S`.
+fn read_field_header<S: PrimeField, R: Read>(mut reader: R) -> io::Result<usize> {
+    let n8 = reader.read_u32::<LittleEndian>()? as usize;
+    if n8 != S::Repr::default().as_ref().len() {
+        return Err(invalid_data(
+            "field element size does not match the scalar field",
+        ));
+    }
+
+    let mut prime = vec![0u8; n8];
+    reader.read_exact(&mut prime)?;
+    if prime != modulus_le::<S>() {
+        return Err(invalid_data("prime does not match the scalar field"));
+    }
+
+    Ok(n8)
+}
+
+fn write_field_header<S: PrimeField>(out: &mut Vec<u8>) {
+    let modulus = modulus_le::<S>();
+    out.write_u32::<LittleEndian>(modulus.len() as u32).unwrap();
+    out.extend_from_slice(&modulus);
+}
+
+fn read_scalar<S: PrimeField, R: Read>(mut reader: R) -> io::Result<S> {
+    let mut repr = S::Repr::default();
+    reader.read_exact(repr.as_mut())?;
+
+    Option::from(S::from_repr(repr)).ok_or_else(|| invalid_data("field element is not canonical"))
+}
+
+/// A rank-1 constraint `a * b = c`, with each linear combination given as
+/// `(wire, coefficient)` pairs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Constraint<S: PrimeField> {
+    pub a: Vec<(usize, S)>,
+    pub b: Vec<(usize, S)>,
+    pub c: Vec<(usize, S)>,
+}
+
+/// A constraint system parsed from a circom `.r1cs` file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct R1cs<S: PrimeField> {
+    /// The total number of wires, including the constant `1` wire.
+    pub num_wires: usize,
+    pub num_pub_out: usize,
+    pub num_pub_in: usize,
+    pub num_prv_in: usize,
+    pub num_labels: u64,
+    pub constraints: Vec<Constraint<S>>,
+    /// The label of each wire in the original circuit. May be empty if the
+    /// file has no wire-to-label map.
+    pub wire_to_label: Vec<u64>,
+}
+
+impl<S: PrimeField> R1cs<S> {
+    /// The number of public wires, excluding the constant `1` wire.
+    pub fn num_public(&self) -> usize {
+        self.num_pub_out + self.num_pub_in
+    }
+
+    /// Reads a constraint system in the iden3 binary `.r1cs` format.
+    pub fn read<R: Read>(reader: R) -> io::Result<Self> {
+        let sections = read_sections(reader, R1CS_MAGIC)?;
+
+        let mut header = find_section(&sections, R1CS_HEADER_SECTION)?;
+        let n8 = read_field_header::<S, _>(&mut header)?;
+        let num_wires = header.read_u32::<LittleEndian>()? as usize;
+        let num_pub_out = header.read_u32::<LittleEndian>()? as usize;
+        let num_pub_in = header.read_u32::<LittleEndian>()? as usize;
+        let num_prv_in = header.read_u32::<LittleEndian>()? as usize;
+        let num_labels = header.read_u64::<LittleEndian>()?;
+        let num_constraints = header.read_u32::<LittleEndian>()? as usize;
+
+        if num_wires == 0 || 1 + num_pub_out + num_pub_in > num_wires {
+            return Err(invalid_data("inconsistent wire counts"));
+        }
+
+        let mut contents = find_section(&sections, R1CS_CONSTRAINTS_SECTION)?;
+        let read_lc = |contents: &mut &[u8]| -> io::Result<Vec<(usize, S)>> {
+            let num_terms = contents.read_u32::<LittleEndian>()? as usize;
+            // Each term occupies at least 4 + n8 bytes, which bounds the
+            // allocation for malformed files.
+            let mut lc = Vec::with_capacity(num_terms.min(contents.len() / (4 + n8)));
+            for _ in 0..num_terms {
+                let wire = contents.read_u32::<LittleEndian>()? as usize;
+                if wire >= num_wires {
+                    return Err(invalid_data("constraint refers to an unknown wire"));
+                }
+                lc.push((wire, read_scalar(&mut *contents)?));
+            }
+
+            Ok(lc)
+        };
+
+        let mut constraints = vec![];
+        for _ in 0..num_constraints {
+            let a = read_lc(&mut contents)?;
+            let b = read_lc(&mut contents)?;
+            let c = read_lc(&mut contents)?;
+            constraints.push(Constraint { a, b, c });
+        }
+
+        let wire_to_label = match find_section(&sections, R1CS_WIRE_TO_LABEL_SECTION) {
+            Ok(mut contents) => (0..num_wires)
+                .map(|_| contents.read_u64::<LittleEndian>())
+                .collect::<io::Result<_>>()?,
+            Err(_) => vec![],
+        };
+
+        Ok(R1cs {
+            num_wires,
+            num_pub_out,
+            num_pub_in,
+            num_prv_in,
+            num_labels,
+            constraints,
+            wire_to_label,
+        })
+    }
+
+    /// Writes this constraint system in the iden3 binary `.r1cs` format.
+    ///
+    /// If `wire_to_label` is empty, each wire is labelled with its index.
+    pub fn write<W: Write>(&self, writer: W) -> io::Result<()> {
+        // Writes to a Vec<u8> cannot fail.
+        let mut header = vec![];
+        write_field_header::<S>(&mut header);
+        for v in [
+            self.num_wires,
+            self.num_pub_out,
+            self.num_pub_in,
+            self.num_prv_in,
+        ] {
+            header.write_u32::<LittleEndian>(v as u32).unwrap();
+        }
+        header.write_u64::<LittleEndian>(self.num_labels).unwrap();
+        header
+            .write_u32::<LittleEndian>(self.constraints.len() as u32)
+            .unwrap();
+
+        let mut contents = vec![];
+        for constraint in &self.constraints {
+            for lc in [&constraint.a, &constraint.b, &constraint.c] {
+                contents.write_u32::<LittleEndian>(lc.len() as u32).unwrap();
+                for (wire, coeff) in lc {
+                    contents.write_u32::<LittleEndian>(*wire as u32).unwrap();
+                    contents.extend_from_slice(coeff.to_repr().as_ref());
+                }
+            }
+        }
+
+        let mut labels = vec![];
+        if self.wire_to_label.is_empty() {
+            for wire in 0..self.num_wires {
+                labels.write_u64::<LittleEndian>(wire as u64).unwrap();
+            }
+        } else {
+            for label in &self.wire_to_label {
+                labels.write_u64::<LittleEndian>(*label).unwrap();
+            }
+        }
+
+        write_sections(
+            writer,
+            R1CS_MAGIC,
+            1,
+            &[
+                (R1CS_HEADER_SECTION, header),
+                (R1CS_CONSTRAINTS_SECTION, contents),
+                (R1CS_WIRE_TO_LABEL_SECTION, labels),
+            ],
+        )
+    }
+}
+
+/// Reads a witness in the iden3 binary `.wtns` format.
+pub fn read_witness<S: PrimeField, R: Read>(reader: R) -> io::Result<Vec<S>> {
+    let sections = read_sections(reader, WTNS_MAGIC)?;
+
+    let mut header = find_section(&sections, WTNS_HEADER_SECTION)?;
+    read_field_header::<S, _>(&mut header)?;
+    let num_witness = header.read_u32::<LittleEndian>()? as usize;
+
+    let mut contents = find_section(&sections, WTNS_WITNESS_SECTION)?;
+    let witness = (0..num_witness)
+        .map(|_| read_scalar(&mut contents))
+        .collect::<io::Result<Vec<S>>>()?;
+
+    if witness.first() != Some(&S::one()) {
+        return Err(invalid_data("witness does not start with the constant 1"));
+    }
+
+    Ok(witness)
+}
+
+/// Writes a witness in the iden3 binary `.wtns` format.
+pub fn write_witness<S: PrimeField, W: Write>(witness: &[S], writer: W) -> io::Result<()> {
+    let mut header = vec![];
+    write_field_header::<S>(&mut header);
+    header
+        .write_u32::<LittleEndian>(witness.len() as u32)
+        .unwrap();
+
+    let mut contents = vec![];
+    for w in witness {
+        contents.extend_from_slice(w.to_repr().as_ref());
+    }
+
+    write_sections(
+        writer,
+        WTNS_MAGIC,
+        2,
+        &[
+            (WTNS_HEADER_SECTION, header),
+            (WTNS_WITNESS_SECTION, contents),
+        ],
+    )
+}
+
+/// A [`Circuit`] built from a circom constraint system and, when proving, a
+/// witness for it.
+///
+/// Public wires are allocated as inputs in wire order, so the public inputs
+/// for verification are the values returned by
+/// [`CircomCircuit::public_inputs`] (which match the snarkjs `public.json`).
+pub struct CircomCircuit<'a, S: PrimeField> {
+    pub r1cs: &'a R1cs<S>,
+    /// The full witness, including the constant `1` wire. Set to `None` when
+    /// generating parameters.
+    pub witness: Option<Vec<S>>,
+}
+
+impl<'a, S: PrimeField> CircomCircuit<'a, S> {
+    /// Returns the public inputs of this circuit's witness.
+    pub fn public_inputs(&self) -> Option<Vec<S>> {
+        self.witness
+            .as_ref()
+            .and_then(|w| w.get(1..=self.r1cs.num_public()))
+            .map(|w| w.to_vec())
+    }
+}
+
+impl<'a, S: PrimeField> Circuit<S> for CircomCircuit<'a, S> {
+    fn synthesize<CS: ConstraintSystem<S>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let witness = self.witness.as_ref();
+        let value = |i: usize| {
+            witness
+                .and_then(|w| w.get(i).cloned())
+                .ok_or(SynthesisError::AssignmentMissing)
+        };
+
+        let mut wires = Vec::with_capacity(self.r1cs.num_wires);
+        wires.push(CS::one());
+        for i in 1..self.r1cs.num_wires {
+            let var = if i <= self.r1cs.num_public() {
+                cs.alloc_input(|| format!("public wire {}", i), || value(i))?
+            } else {
+                cs.alloc(|| format!("private wire {}", i), || value(i))?
+            };
+            wires.push(var);
+        }
+
+        let to_lc = |terms: &[(usize, S)], mut lc: LinearCombination<S>| {
+            for &(wire, coeff) in terms {
+                lc = lc + (coeff, wires[wire]);
+            }
+            lc
+        };
+
+        for (i, constraint) in self.r1cs.constraints.iter().enumerate() {
+            cs.enforce(
+                || format!("constraint {}", i),
+                |lc| to_lc(&constraint.a, lc),
+                |lc| to_lc(&constraint.b, lc),
+                |lc| to_lc(&constraint.c, lc),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// A constraint system that records a synthesized circuit, so that it can be
+/// exported in the iden3 `.r1cs` and `.wtns` formats.
+///
+/// Inputs are laid out as the public wires (with input 0 as the constant `1`
+/// wire), followed by the auxiliary variables, each in allocation order. This
+/// matches the layout that [`CircomCircuit`] allocates, so an exported circuit
+/// can be re-imported with the same variable indices.
+///
+/// If the circuit is synthesized without a witness, variables are recorded
+/// without values and [`R1csExporter::witness`] returns `None`.
+pub struct R1csExporter<S: PrimeField> {
+    inputs: Vec<(Option<S>, String)>,
+    aux: Vec<(Option<S>, String)>,
+    constraints: Vec<(
+        LinearCombination<S>,
+        LinearCombination<S>,
+        LinearCombination<S>,
+    )>,
+    current_namespace: Vec<String>,
+}
+
+impl<S: PrimeField> Default for R1csExporter<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: PrimeField> R1csExporter<S> {
+    pub fn new() -> Self {
+        R1csExporter {
+            inputs: vec![(Some(S::one()), "ONE".into())],
+            aux: vec![],
+            constraints: vec![],
+            current_namespace: vec![],
+        }
+    }
+
+    /// Synthesizes `circuit` into a new exporter.
+    pub fn synthesize<C: Circuit<S>>(circuit: C) -> Result<Self, SynthesisError> {
+        let mut exporter = Self::new();
+        circuit.synthesize(&mut exporter)?;
+
+        Ok(exporter)
+    }
+
+    pub fn num_inputs(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn num_aux(&self) -> usize {
+        self.aux.len()
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        self.constraints.len()
+    }
+
+    fn wire(&self, var: Variable) -> usize {
+        match var.get_unchecked() {
+            Index::Input(i) => i,
+            Index::Aux(i) => self.inputs.len() + i,
+        }
+    }
+
+    /// Returns the recorded constraint system, with linear combinations
+    /// normalized and variables mapped to wires.
+    pub fn to_r1cs(&self) -> R1cs<S> {
+        let to_terms = |lc: &LinearCombination<S>| {
+            proc_lc(lc.as_ref())
+                .into_iter()
+                .map(|(var, coeff)| (self.wire(var.0), coeff))
+                .collect::<Vec<_>>()
+        };
+
+        let num_wires = self.inputs.len() + self.aux.len();
+
+        R1cs {
+            num_wires,
+            num_pub_out: 0,
+            num_pub_in: self.inputs.len() - 1,
+            num_prv_in: 0,
+            num_labels: num_wires as u64,
+            constraints: self
+                .constraints
+                .iter()
+                .map(|(a, b, c)| Constraint {
+                    a: to_terms(a),
+                    b: to_terms(b),
+                    c: to_terms(c),
+                })
+                .collect(),
+            wire_to_label: (0..num_wires as u64).collect(),
+        }
+    }
+
+    /// Returns the recorded witness in wire order, or `None` if any variable
+    /// was allocated without a value.
+    pub fn witness(&self) -> Option<Vec<S>> {
+        self.inputs
+            .iter()
+            .chain(self.aux.iter())
+            .map(|(value, _)| *value)
+            .collect()
+    }
+
+    /// Returns the namespaced annotation of each wire.
+    pub fn wire_names(&self) -> Vec<&str> {
+        self.inputs
+            .iter()
+            .chain(self.aux.iter())
+            .map(|(_, name)| &name[..])
+            .collect()
+    }
+
+    /// Writes a symbol file in the format of circom's `.sym` files, mapping
+    /// each wire to its namespaced annotation.
+    pub fn write_sym<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut s = String::new();
+        for (wire, name) in self.wire_names().into_iter().enumerate() {
+            writeln!(&mut s, "{},{},0,{}", wire, wire, name).unwrap();
+        }
+
+        writer.write_all(s.as_bytes())
+    }
+
+    fn path(&self, annotation: String) -> String {
+        self.current_namespace
+            .iter()
+            .cloned()
+            .chain(Some(annotation))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    fn value<F>(f: F) -> Result<Option<S>, SynthesisError>
+    where
+        F: FnOnce() -> Result<S, SynthesisError>,
+    {
+        match f() {
+            Ok(value) => Ok(Some(value)),
+            Err(SynthesisError::AssignmentMissing) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<S: PrimeField> ConstraintSystem<S> for R1csExporter<S> {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<S, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let path = self.path(annotation().into());
+        self.aux.push((Self::value(f)?, path));
+
+        Ok(Variable::new_unchecked(Index::Aux(self.aux.len() - 1)))
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<S, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let path = self.path(annotation().into());
+        self.inputs.push((Self::value(f)?, path));
+
+        Ok(Variable::new_unchecked(Index::Input(self.inputs.len() - 1)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, a: LA, b: LB, c: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<S>) -> LinearCombination<S>,
+        LB: FnOnce(LinearCombination<S>) -> LinearCombination<S>,
+        LC: FnOnce(LinearCombination<S>) -> LinearCombination<S>,
+    {
+        self.constraints.push((
+            a(LinearCombination::zero()),
+            b(LinearCombination::zero()),
+            c(LinearCombination::zero()),
+        ));
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.current_namespace.push(name_fn().into());
+    }
+
+    fn pop_namespace(&mut self) {
+        assert!(self.current_namespace.pop().is_some());
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::test::TestConstraintSystem;
+
+    use bls12_381::Scalar;
+    use byteorder::WriteBytesExt;
+
+    fn write_section(out: &mut Vec<u8>, ty: u32, contents: &[u8]) {
+        out.write_u32::<LittleEndian>(ty).unwrap();
+        out.write_u64::<LittleEndian>(contents.len() as u64)
+            .unwrap();
+        out.extend_from_slice(contents);
+    }
+
+    /// Encodes the circuit `out = x^3 + x + 5`, with public `out` and private
+    /// `x`, as circom would:
+    ///
+    /// - wire 0: one
+    /// - wire 1: out (public output)
+    /// - wire 2: x (private input)
+    /// - wire 3: x^2
+    fn cubic_r1cs() -> Vec<u8> {
+        let neg_one = -Scalar::one();
+        let constraints: [[&[(u32, Scalar)]; 3]; 2] = [
+            // x * x = x^2
+            [
+                &[(2, Scalar::one())],
+                &[(2, Scalar::one())],
+                &[(3, Scalar::one())],
+            ],
+            // x^2 * x = out - x - 5
+            [
+                &[(3, Scalar::one())],
+                &[(2, Scalar::one())],
+                &[(1, Scalar::one()), (2, neg_one), (0, -Scalar::from(5))],
+            ],
+        ];
+
+        let mut header = vec![];
+        write_field_header::<Scalar>(&mut header);
+        for v in [4u32, 1, 0, 1] {
+            header.write_u32::<LittleEndian>(v).unwrap();
+        }
+        header.write_u64::<LittleEndian>(4).unwrap();
+        header.write_u32::<LittleEndian>(2).unwrap();
+
+        let mut contents = vec![];
+        for constraint in constraints.iter() {
+            for lc in constraint.iter() {
+                contents.write_u32::<LittleEndian>(lc.len() as u32).unwrap();
+                for (wire, coeff) in lc.iter() {
+                    contents.write_u32::<LittleEndian>(*wire).unwrap();
+                    contents.extend_from_slice(coeff.to_repr().as_ref());
+                }
+            }
+        }
+
+        let mut labels = vec![];
+        for l in 0..4u64 {
+            labels.write_u64::<LittleEndian>(l).unwrap();
+        }
+
+        let mut out = R1CS_MAGIC.to_vec();
+        out.write_u32::<LittleEndian>(1).unwrap();
+        out.write_u32::<LittleEndian>(3).unwrap();
+        // Sections may appear in any order.
+        write_section(&mut out, R1CS_CONSTRAINTS_SECTION, &contents);
+        write_section(&mut out, R1CS_HEADER_SECTION, &header);
+        write_section(&mut out, R1CS_WIRE_TO_LABEL_SECTION, &labels);
+        out
+    }
+
+    fn cubic_wtns(x: u64) -> Vec<u8> {
+        let x = Scalar::from(x);
+        let witness = [Scalar::one(), x * x * x + x + Scalar::from(5), x, x * x];
+
+        let mut header = vec![];
+        write_field_header::<Scalar>(&mut header);
+        header
+            .write_u32::<LittleEndian>(witness.len() as u32)
+            .unwrap();
+
+        let mut contents = vec![];
+        for w in witness.iter() {
+            contents.extend_from_slice(w.to_repr().as_ref());
+        }
+
+        let mut out = WTNS_MAGIC.to_vec();
+        out.write_u32::<LittleEndian>(2).unwrap();
+        out.write_u32::<LittleEndian>(2).unwrap();
+        write_section(&mut out, WTNS_HEADER_SECTION, &header);
+        write_section(&mut out, WTNS_WITNESS_SECTION, &contents);
+        out
+    }
+
+    #[test]
+    fn parse_r1cs_and_witness() {
+        let r1cs = R1cs::<Scalar>::read(&cubic_r1cs()[..]).unwrap();
+        assert_eq!(r1cs.num_wires, 4);
+        assert_eq!(r1cs.num_public(), 1);
+        assert_eq!(r1cs.num_prv_in, 1);
+        assert_eq!(r1cs.constraints.len(), 2);
+        assert_eq!(r1cs.wire_to_label, vec![0, 1, 2, 3]);
+
+        let witness = read_witness::<Scalar, _>(&cubic_wtns(3)[..]).unwrap();
+        assert_eq!(witness[1], Scalar::from(35));
+
+        let mut cs = TestConstraintSystem::new();
+        let circuit = CircomCircuit {
+            r1cs: &r1cs,
+            witness: Some(witness),
+        };
+        assert_eq!(circuit.public_inputs(), Some(vec![Scalar::from(35)]));
+        circuit.synthesize(&mut cs).unwrap();
+
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_constraints(), 2);
+        assert_eq!(cs.num_inputs(), 2);
+        assert!(cs.verify(&[Scalar::from(35)]));
+        assert_eq!(cs.get("private wire 2"), Scalar::from(3));
+
+        cs.set("public wire 1", Scalar::from(36));
+        assert_eq!(cs.which_is_unsatisfied(), Some("constraint 1"));
+    }
+
+    #[test]
+    fn rejects_malformed_files() {
+        let mut bad_magic = cubic_r1cs();
+        bad_magic[0] = b'x';
+        assert!(R1cs::<Scalar>::read(&bad_magic[..]).is_err());
+
+        let truncated = cubic_r1cs();
+        assert!(R1cs::<Scalar>::read(&truncated[..truncated.len() - 1]).is_err());
+
+        // A witness for a different prime is rejected.
+        let mut wrong_prime = cubic_wtns(3);
+        wrong_prime[4 + 4 + 4 + 4 + 8 + 4] ^= 1;
+        assert!(read_witness::<Scalar, _>(&wrong_prime[..]).is_err());
+    }
+
+    /// Allocates `x`, exposes its bits as packed public inputs, and exposes
+    /// `x^2` as a further public input, interleaving input and auxiliary
+    /// allocation.
+    struct BitsAndSquare {
+        x: Option<u8>,
+    }
+
+    impl Circuit<Scalar> for BitsAndSquare {
+        fn synthesize<CS: ConstraintSystem<Scalar>>(
+            self,
+            cs: &mut CS,
+        ) -> Result<(), SynthesisError> {
+            use crate::gadgets::boolean::{AllocatedBit, Boolean};
+            use crate::gadgets::multipack::pack_into_inputs;
+            use crate::gadgets::num::AllocatedNum;
+
+            let bits = (0..8)
+                .map(|i| {
+                    AllocatedBit::alloc(
+                        cs.namespace(|| format!("bit {}", i)),
+                        self.x.map(|x| (x >> i) & 1 == 1),
+                    )
+                    .map(Boolean::from)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            pack_into_inputs(cs.namespace(|| "pack"), &bits)?;
+
+            let x = AllocatedNum::alloc(cs.namespace(|| "x"), || {
+                self.x
+                    .map(|x| Scalar::from(u64::from(x)))
+                    .ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            let sq = x.square(cs.namespace(|| "x^2"))?;
+            sq.inputize(cs.namespace(|| "x^2 input"))
+        }
+    }
+
+    #[test]
+    fn export_roundtrip() {
+        let mut expected = TestConstraintSystem::new();
+        BitsAndSquare { x: Some(13) }
+            .synthesize(&mut expected)
+            .unwrap();
+        assert!(expected.is_satisfied());
+
+        let exporter = R1csExporter::synthesize(BitsAndSquare { x: Some(13) }).unwrap();
+        assert_eq!(exporter.num_inputs(), expected.num_inputs());
+        assert_eq!(exporter.num_constraints(), expected.num_constraints());
+        assert_eq!(exporter.wire_names()[1], "pack/input 0");
+
+        let mut r1cs_bytes = vec![];
+        exporter.to_r1cs().write(&mut r1cs_bytes).unwrap();
+        let mut wtns_bytes = vec![];
+        write_witness(&exporter.witness().unwrap(), &mut wtns_bytes).unwrap();
+
+        let r1cs = R1cs::<Scalar>::read(&r1cs_bytes[..]).unwrap();
+        assert_eq!(r1cs.num_wires, exporter.num_inputs() + exporter.num_aux());
+        assert_eq!(r1cs.num_public(), exporter.num_inputs() - 1);
+
+        let circuit = CircomCircuit {
+            r1cs: &r1cs,
+            witness: Some(read_witness(&wtns_bytes[..]).unwrap()),
+        };
+        assert_eq!(
+            circuit.public_inputs(),
+            Some(vec![Scalar::from(13), Scalar::from(169)])
+        );
+
+        let mut cs = TestConstraintSystem::new();
+        circuit.synthesize(&mut cs).unwrap();
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.hash(), expected.hash());
+
+        // Without a witness the shape is still exported.
+        let exporter = R1csExporter::synthesize(BitsAndSquare { x: None }).unwrap();
+        assert!(exporter.witness().is_none());
+        let mut shape_bytes = vec![];
+        exporter.to_r1cs().write(&mut shape_bytes).unwrap();
+        assert_eq!(shape_bytes, r1cs_bytes);
+
+        let mut sym = vec![];
+        exporter.write_sym(&mut sym).unwrap();
+        let sym = String::from_utf8(sym).unwrap();
+        assert!(sym.starts_with("0,0,0,ONE\n1,1,0,pack/input 0\n"));
+    }
+
+    #[cfg(feature = "groth16")]
+    #[test]
+    fn groth16_proof() {
+        use crate::groth16::{
+            create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
+        };
+        use bls12_381::Bls12;
+        use rand::thread_rng;
+
+        let mut rng = thread_rng();
+        let r1cs = R1cs::<Scalar>::read(&cubic_r1cs()[..]).unwrap();
+
+        let params = generate_random_parameters::<Bls12, _, _>(
+            CircomCircuit {
+                r1cs: &r1cs,
+                witness: None,
+            },
+            &mut rng,
+        )
+        .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let circuit = CircomCircuit {
+            r1cs: &r1cs,
+            witness: Some(read_witness(&cubic_wtns(7)[..]).unwrap()),
+        };
+        let inputs = circuit.public_inputs().unwrap();
+        let proof = create_random_proof(circuit, &params, &mut rng).unwrap();
+
+        assert!(verify_proof(&pvk, &proof, &inputs).is_ok());
+        assert!(verify_proof(&pvk, &proof, &[Scalar::from(35)]).is_err());
+    }
+}
@@ -0,0 +1,430 @@
+//! Pedersen vector commitments, computed with [`multiexp`].
+//!
+//! A [`CommitmentKey`] holds generators `G_1, ..., G_n` and a blinding
+//! generator `H`. A vector of values `v` with blinding factor `r` is committed
+//! to as
+//!
+//! ```text
+//! C = v_1 * G_1 + ... + v_n * G_n + r * H
+//! ```
+//!
+//! The commitment is binding as long as no discrete logarithm relation between
+//! the generators is known. [`CommitmentKey::new`] derives the generators by
+//! hashing, so that nobody knows such a relation.
+//!
+//! ```
+//! use bellman::{commitment::CommitmentKey, multicore::Worker};
+//! use bls12_381::{G1Projective, Scalar};
+//!
+//! let pool = Worker::new();
+//! let key = CommitmentKey::<G1Projective>::new(&pool, b"example", 4);
+//!
+//! let values = [1, 2, 3].map(Scalar::from);
+//! let blinding = Scalar::from(42);
+//! let commitment = key.commit(&pool, &values, blinding)?;
+//! assert!(key.verify(&pool, &commitment, &values, blinding)?);
+//! # Ok::<(), bellman::SynthesisError>(())
+//! ```
+
+use std::io;
+use std::sync::Arc;
+
+use blake2s_simd::Params as Blake2sParams;
+use ff::{Field, PrimeFieldBits};
+use group::{
+    prime::{PrimeCurve, PrimeCurveAffine},
+    GroupEncoding,
+};
+use rand_core::RngCore;
+
+use crate::multicore::Worker;
+use crate::multiexp::{multiexp, Exponent, FullDensity};
+use crate::SynthesisError;
+
+/// BLAKE2s personalization for deriving generators.
+const GENERATOR_PERSONALIZATION: &[u8; 8] = b"Bellman_";
+
+/// A group whose elements can be derived from uniformly random bytes without
+/// revealing their discrete logarithms.
+///
+/// [`GroupEncoding::from_bytes_unchecked`] must accept encodings of points
+/// outside the prime-order subgroup, which are then mapped into it by
+/// [`HashToGroup::clear_cofactor`].
+pub trait HashToGroup: PrimeCurve + GroupEncoding {
+    /// Maps a point on the curve into the prime-order subgroup.
+    fn clear_cofactor(&self) -> Self;
+}
+
+impl HashToGroup for bls12_381::G1Projective {
+    fn clear_cofactor(&self) -> Self {
+        bls12_381::G1Projective::clear_cofactor(self)
+    }
+}
+
+impl HashToGroup for bls12_381::G2Projective {
+    fn clear_cofactor(&self) -> Self {
+        bls12_381::G2Projective::clear_cofactor(self)
+    }
+}
+
+/// Derives `n` generators of `G` from `domain`.
+///
+/// Each generator is found by hashing `domain`, its index and a counter until
+/// the digest decodes to a curve point, which is then mapped into the
+/// prime-order subgroup. Different domains give independent generators.
+pub fn hash_to_generators<G: HashToGroup>(
+    pool: &Worker,
+    domain: &[u8],
+    n: usize,
+) -> Vec<G::Affine> {
+    let mut generators = vec![G::identity(); n];
+
+    pool.scope(n, |scope, chunk| {
+        for (i, generators) in generators.chunks_mut(chunk).enumerate() {
+            scope.spawn(move |_scope| {
+                for (j, generator) in generators.iter_mut().enumerate() {
+                    *generator = hash_to_generator(domain, (i * chunk + j) as u64);
+                }
+            });
+        }
+    });
+
+    let mut affine = vec![G::Affine::identity(); n];
+    G::batch_normalize(&generators, &mut affine);
+
+    affine
+}
+
+fn hash_to_generator<G: HashToGroup>(domain: &[u8], index: u64) -> G {
+    for counter in 0u32.. {
+        let mut repr = G::Repr::default();
+        for (block, bytes) in repr.as_mut().chunks_mut(32).enumerate() {
+            let hash = Blake2sParams::new()
+                .hash_length(32)
+                .personal(GENERATOR_PERSONALIZATION)
+                .to_state()
+                .update(domain)
+                .update(&index.to_le_bytes())
+                .update(&counter.to_le_bytes())
+                .update(&(block as u32).to_le_bytes())
+                .finalize();
+            bytes.copy_from_slice(&hash.as_bytes()[..bytes.len()]);
+        }
+
+        if let Some(point) = Option::<G>::from(G::from_bytes_unchecked(&repr)) {
+            let point = point.clear_cofactor();
+            if !bool::from(point.is_identity()) {
+                return point;
+            }
+        }
+    }
+
+    unreachable!("no generator found for any counter")
+}
+
+/// The generators for Pedersen vector commitments of up to
+/// [`CommitmentKey::len`] values.
+#[derive(Clone, Debug)]
+pub struct CommitmentKey<G: PrimeCurve> {
+    // The blinding generator, followed by the value generators.
+    bases: Arc<Vec<G::Affine>>,
+}
+
+/// A claimed opening of a commitment, for [`CommitmentKey::verify_batch`].
+#[derive(Clone, Copy, Debug)]
+pub struct Opening<'a, G: PrimeCurve> {
+    pub commitment: &'a G,
+    pub values: &'a [G::Scalar],
+    pub blinding: G::Scalar,
+}
+
+impl<G: HashToGroup> CommitmentKey<G> {
+    /// Derives a key for committing to up to `len` values, with generators
+    /// hashed from `domain`.
+    pub fn new(pool: &Worker, domain: &[u8], len: usize) -> Self {
+        CommitmentKey {
+            bases: Arc::new(hash_to_generators::<G>(pool, domain, len + 1)),
+        }
+    }
+}
+
+impl<G: PrimeCurve> CommitmentKey<G>
+where
+    G::Scalar: PrimeFieldBits,
+{
+    /// Creates a key from existing generators. The discrete logarithms between
+    /// the generators must be unknown for the commitments to be binding.
+    ///
+    /// Returns an error if any generator is the identity.
+    pub fn from_generators(
+        blinding: G::Affine,
+        generators: &[G::Affine],
+    ) -> Result<Self, SynthesisError> {
+        let bases = Some(blinding)
+            .into_iter()
+            .chain(generators.iter().copied())
+            .collect::<Vec<_>>();
+        if bases.iter().any(|base| bool::from(base.is_identity())) {
+            return Err(SynthesisError::UnexpectedIdentity);
+        }
+
+        Ok(CommitmentKey {
+            bases: Arc::new(bases),
+        })
+    }
+
+    /// Returns the maximum number of values in a commitment.
+    pub fn len(&self) -> usize {
+        self.bases.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the generators for the committed values.
+    pub fn generators(&self) -> &[G::Affine] {
+        &self.bases[1..]
+    }
+
+    /// Returns the generator for the blinding factor.
+    pub fn blinding_generator(&self) -> &G::Affine {
+        &self.bases[0]
+    }
+
+    /// Commits to `values` with the blinding factor `blinding`. If there are
+    /// fewer values than generators, the remaining values are zero.
+    ///
+    /// This blocks on `pool`, so it must not be called from within it.
+    pub fn commit(
+        &self,
+        pool: &Worker,
+        values: &[G::Scalar],
+        blinding: G::Scalar,
+    ) -> Result<G, SynthesisError> {
+        if values.len() > self.len() {
+            return Err(too_many_values());
+        }
+
+        let exponents = Some(blinding)
+            .iter()
+            .chain(values)
+            .map(Exponent::from)
+            .collect::<Vec<_>>();
+
+        multiexp(
+            pool,
+            (self.bases.clone(), 0),
+            FullDensity,
+            Arc::new(exponents),
+        )
+        .wait()
+    }
+
+    /// Checks that `commitment` opens to `values` with the blinding factor
+    /// `blinding`.
+    pub fn verify(
+        &self,
+        pool: &Worker,
+        commitment: &G,
+        values: &[G::Scalar],
+        blinding: G::Scalar,
+    ) -> Result<bool, SynthesisError> {
+        Ok(self.commit(pool, values, blinding)? == *commitment)
+    }
+
+    /// Checks several openings at once.
+    ///
+    /// The openings are combined with random weights drawn from `rng`, so that
+    /// all of them are checked with a single multiexp. If any opening is
+    /// invalid, this returns `false` except with negligible probability.
+    pub fn verify_batch<R: RngCore>(
+        &self,
+        pool: &Worker,
+        openings: &[Opening<'_, G>],
+        mut rng: R,
+    ) -> Result<bool, SynthesisError> {
+        if openings
+            .iter()
+            .any(|opening| opening.values.len() > self.len())
+        {
+            return Err(too_many_values());
+        }
+
+        // Check sum_i w_i * C_i - commit(sum_i w_i * v_i, sum_i w_i * r_i) = 0
+        let mut blinding = G::Scalar::zero();
+        let mut values = vec![G::Scalar::zero(); self.len()];
+        let mut commitments = Vec::with_capacity(openings.len());
+        let mut weights = Vec::with_capacity(openings.len());
+        for opening in openings {
+            let weight = G::Scalar::random(&mut rng);
+            blinding += opening.blinding * weight;
+            for (acc, value) in values.iter_mut().zip(opening.values) {
+                *acc += *value * weight;
+            }
+
+            // The identity has no affine representation that multiexp accepts,
+            // and contributes nothing to the sum.
+            if !bool::from(opening.commitment.is_identity()) {
+                commitments.push(*opening.commitment);
+                weights.push(weight);
+            }
+        }
+
+        let mut bases = vec![G::Affine::identity(); commitments.len()];
+        G::batch_normalize(&commitments, &mut bases);
+        bases.extend_from_slice(&self.bases);
+
+        let exponents = weights
+            .into_iter()
+            .chain(Some(-blinding))
+            .chain(values.into_iter().map(|value| -value))
+            .map(Exponent::from)
+            .collect::<Vec<_>>();
+
+        let result: G =
+            multiexp(pool, (Arc::new(bases), 0), FullDensity, Arc::new(exponents)).wait()?;
+
+        Ok(bool::from(result.is_identity()))
+    }
+}
+
+fn too_many_values() -> SynthesisError {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "more values than commitment generators",
+    )
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bls12_381::{G1Projective, G2Projective, Scalar};
+    use group::Curve;
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    fn naive_commit<G: PrimeCurve>(
+        key: &CommitmentKey<G>,
+        values: &[G::Scalar],
+        blinding: G::Scalar,
+    ) -> G
+    where
+        G::Scalar: PrimeFieldBits,
+    {
+        key.generators().iter().zip(values).fold(
+            key.blinding_generator().to_curve() * blinding,
+            |acc, (g, v)| acc + g.to_curve() * v,
+        )
+    }
+
+    fn random_values(rng: &mut XorShiftRng, n: usize) -> Vec<Scalar> {
+        (0..n).map(|_| Scalar::random(&mut *rng)).collect()
+    }
+
+    fn commit_and_verify<G: HashToGroup<Scalar = Scalar>>() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let pool = Worker::new();
+        let key = CommitmentKey::<G>::new(&pool, b"commitment tests", 20);
+        assert_eq!(key.len(), 20);
+
+        let values = random_values(&mut rng, 20);
+        let blinding = Scalar::random(&mut rng);
+        let commitment = key.commit(&pool, &values, blinding).unwrap();
+        assert_eq!(commitment, naive_commit(&key, &values, blinding));
+        assert!(key.verify(&pool, &commitment, &values, blinding).unwrap());
+        assert!(!key
+            .verify(&pool, &commitment, &values, blinding + Scalar::one())
+            .unwrap());
+
+        // Shorter vectors are padded with zeros.
+        let short = key.commit(&pool, &values[..5], blinding).unwrap();
+        assert_eq!(short, naive_commit(&key, &values[..5], blinding));
+
+        assert!(key
+            .commit(&pool, &random_values(&mut rng, 21), blinding)
+            .is_err());
+    }
+
+    #[test]
+    fn commit_g1() {
+        commit_and_verify::<G1Projective>();
+    }
+
+    #[test]
+    fn commit_g2() {
+        commit_and_verify::<G2Projective>();
+    }
+
+    #[test]
+    fn generators_are_deterministic_and_independent() {
+        let pool = Worker::new();
+        let a = hash_to_generators::<G1Projective>(&pool, b"a", 10);
+        assert_eq!(a, hash_to_generators::<G1Projective>(&pool, b"a", 10));
+        assert_eq!(
+            a[..4],
+            hash_to_generators::<G1Projective>(&pool, b"a", 4)[..]
+        );
+
+        let b = hash_to_generators::<G1Projective>(&pool, b"b", 10);
+        for (i, g) in a.iter().chain(&b).enumerate() {
+            assert!(bool::from(g.is_on_curve()));
+            assert!(bool::from(g.is_torsion_free()));
+            assert!(!bool::from(g.is_identity()));
+            for h in a.iter().chain(&b).skip(i + 1) {
+                assert_ne!(g, h);
+            }
+        }
+    }
+
+    #[test]
+    fn batch_opening() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let pool = Worker::new();
+        let key = CommitmentKey::<G1Projective>::new(&pool, b"batch", 8);
+
+        let openings = (0..5)
+            .map(|i| {
+                let values = random_values(&mut rng, i + 3);
+                let blinding = Scalar::random(&mut rng);
+                let commitment = key.commit(&pool, &values, blinding).unwrap();
+                (commitment, values, blinding)
+            })
+            .chain(Some((G1Projective::identity(), vec![], Scalar::zero())))
+            .collect::<Vec<_>>();
+        let mut batch = openings
+            .iter()
+            .map(|(commitment, values, blinding)| Opening {
+                commitment,
+                values,
+                blinding: *blinding,
+            })
+            .collect::<Vec<_>>();
+        assert!(key.verify_batch(&pool, &batch, &mut rng).unwrap());
+        assert!(key.verify_batch(&pool, &[], &mut rng).unwrap());
+
+        let wrong = [Scalar::one()];
+        batch[2].values = &wrong;
+        assert!(!key.verify_batch(&pool, &batch, &mut rng).unwrap());
+    }
+
+    #[test]
+    fn from_generators() {
+        let g = G1Projective::generator().to_affine();
+        let key = CommitmentKey::<G1Projective>::from_generators(g, &[g, g]).unwrap();
+        assert_eq!(key.len(), 2);
+        assert!(CommitmentKey::<G1Projective>::from_generators(
+            g,
+            &[bls12_381::G1Affine::identity()]
+        )
+        .is_err());
+    }
+}
@@ -8,9 +8,18 @@
 //! field. This allows us to perform polynomial operations in O(n) by performing
 //! an O(n log n) FFT over such a domain.
 //!
+//! Fields whose multiplicative group also has a [`SmallSubgroup`] of odd prime
+//! power order can use mixed-radix domains of size 2<sup>a</sup> p<sup>b</sup>
+//! instead, which need much less padding. These, and cosets with a shift other
+//! than the field's multiplicative generator, are configured with
+//! [`DomainConfig`].
+//!
 //! [`EvaluationDomain`]: crate::domain::EvaluationDomain
 //! [Groth16]: https://eprint.iacr.org/2016/260
 
+use std::io;
+use std::sync::Arc;
+
 use ff::PrimeField;
 use group::cofactor::CofactorCurve;
 
@@ -18,15 +27,239 @@ use super::SynthesisError;
 
 use super::multicore::Worker;
 
-pub struct EvaluationDomain<S: PrimeField, G: Group<S>> {
-    coeffs: Vec<G>,
+/// A multiplicative subgroup of order `base^max_exp`, where `base` is an odd
+/// prime.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SmallSubgroup<S> {
+    base: u32,
+    max_exp: u32,
+    root: S,
+}
+
+impl<S: PrimeField> SmallSubgroup<S> {
+    /// Describes the subgroup generated by `root`, which must be a primitive
+    /// `base^max_exp` root of unity. Returns `None` if `base` is not an odd
+    /// prime, `max_exp` is zero, or `root` does not have that order.
+    pub fn new(base: u32, max_exp: u32, root: S) -> Option<Self> {
+        let is_odd_prime = base > 2
+            && base % 2 == 1
+            && (3..)
+                .step_by(2)
+                .take_while(|d| d * d <= base)
+                .all(|d| base % d != 0);
+        if !is_odd_prime || max_exp == 0 {
+            return None;
+        }
+
+        let mut power = root;
+        for _ in 1..max_exp {
+            power = power.pow_vartime([u64::from(base)]);
+        }
+        if power == S::one() || power.pow_vartime([u64::from(base)]) != S::one() {
+            return None;
+        }
+
+        Some(SmallSubgroup {
+            base,
+            max_exp,
+            root,
+        })
+    }
+
+    pub fn base(&self) -> u32 {
+        self.base
+    }
+
+    pub fn max_exp(&self) -> u32 {
+        self.max_exp
+    }
+}
+
+/// Options for [`EvaluationDomain::from_coeffs_with_config`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DomainConfig<S> {
+    /// The shift of the coset used by [`EvaluationDomain::coset_fft`].
+    pub coset_shift: S,
+    /// An extra subgroup that domain sizes may use, or `None` for radix-2
+    /// domains.
+    pub subgroup: Option<SmallSubgroup<S>>,
+}
+
+impl<S: PrimeField> Default for DomainConfig<S> {
+    fn default() -> Self {
+        DomainConfig {
+            coset_shift: S::multiplicative_generator(),
+            subgroup: None,
+        }
+    }
+}
+
+/// The size and generators of an [`EvaluationDomain`].
+#[derive(Clone, Debug)]
+struct Shape<S> {
+    size: usize,
     exp: u32,
+    /// The odd prime factors of the domain size, if it is not a power of two.
+    odd_radices: Vec<u32>,
     omega: S,
     omegainv: S,
-    geninv: S,
+    shift: S,
+    shiftinv: S,
     minv: S,
 }
 
+impl<S: PrimeField> Shape<S> {
+    fn new(len: usize, config: DomainConfig<S>) -> Result<Self, SynthesisError> {
+        let (size, exp, odd_exp) = domain_size(len, config)?;
+
+        // Compute omega, the 2^exp primitive root of unity, times a
+        // base^odd_exp primitive root of unity
+        let mut omega = S::root_of_unity();
+        for _ in exp..S::S {
+            omega = omega.square();
+        }
+        let mut odd_radices = vec![];
+        if let Some(subgroup) = config.subgroup {
+            let mut root = subgroup.root;
+            for _ in odd_exp..subgroup.max_exp {
+                root = root.pow_vartime([u64::from(subgroup.base)]);
+            }
+            omega.mul_assign(&root);
+            odd_radices = vec![subgroup.base; odd_exp as usize];
+        }
+
+        let shift = config.coset_shift;
+        if bool::from(shift.is_zero()) || shift.pow_vartime([size as u64]) == S::one() {
+            return Err(SynthesisError::DivisionByZero);
+        }
+
+        Ok(Shape {
+            size,
+            exp,
+            odd_radices,
+            omega,
+            omegainv: omega.invert().unwrap(),
+            shift,
+            shiftinv: shift.invert().unwrap(),
+            minv: S::from(size as u64).invert().unwrap(),
+        })
+    }
+}
+
+/// Returns the size of the smallest domain supported by `config` that holds
+/// `len` elements, as `(2^exp * base^odd_exp, exp, odd_exp)`.
+fn domain_size<S: PrimeField>(
+    len: usize,
+    config: DomainConfig<S>,
+) -> Result<(usize, u32, u32), SynthesisError> {
+    let (base, max_odd_exp) = config
+        .subgroup
+        .map_or((1, 0), |subgroup| (subgroup.base, subgroup.max_exp));
+    let mut best: Option<(u128, u32, u32)> = None;
+    let mut odd_size = 1u128;
+    for odd_exp in 0..=max_odd_exp {
+        let mut m = odd_size;
+        let mut exp = 0;
+        while m < len as u128 {
+            m *= 2;
+            exp += 1;
+        }
+
+        // The pairing-friendly curve may not be able to support
+        // large enough (radix2) evaluation domains.
+        if exp < S::S && best.map_or(true, |(best, _, _)| m < best) {
+            best = Some((m, exp, odd_exp));
+        }
+        if odd_size >= len as u128 {
+            break;
+        }
+        odd_size *= u128::from(base);
+    }
+
+    let (m, exp, odd_exp) = best.ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+    Ok((m as usize, exp, odd_exp))
+}
+
+/// Precomputed tables for an [`EvaluationDomain`] of a fixed size, which can
+/// be shared by many domains through
+/// [`EvaluationDomain::from_coeffs_precomputed`].
+///
+/// This holds the powers of the domain generator, which are used as twiddle
+/// factors by every FFT, the powers of the coset shift and its inverse, and
+/// the inverse of the vanishing polynomial on the coset. It takes up the space
+/// of three domains of field elements.
+#[derive(Clone, Debug)]
+pub struct DomainPrecomputation<S: PrimeField> {
+    config: DomainConfig<S>,
+    shape: Shape<S>,
+    omega_powers: Vec<S>,
+    shift_powers: Vec<S>,
+    shiftinv_powers: Vec<S>,
+    zinv: S,
+}
+
+impl<S: PrimeField> DomainPrecomputation<S> {
+    /// Precomputes the tables for the domain that
+    /// [`EvaluationDomain::from_coeffs_with_config`] would choose for `len`
+    /// coefficients.
+    pub fn new(
+        worker: &Worker,
+        len: usize,
+        config: DomainConfig<S>,
+    ) -> Result<Self, SynthesisError> {
+        let shape = Shape::new(len, config)?;
+        let zinv = (shape.shift.pow_vartime([shape.size as u64]) - S::one())
+            .invert()
+            .unwrap();
+
+        Ok(DomainPrecomputation {
+            config,
+            omega_powers: powers(worker, shape.omega, shape.size),
+            shift_powers: powers(worker, shape.shift, shape.size),
+            shiftinv_powers: powers(worker, shape.shiftinv, shape.size),
+            shape,
+            zinv,
+        })
+    }
+
+    /// The number of elements of the domain.
+    pub fn size(&self) -> usize {
+        self.shape.size
+    }
+
+    fn roots(&self, inverse: bool) -> Roots<'_, S> {
+        Roots::Table {
+            powers: &self.omega_powers,
+            // omega^-k = omega^(k (n - 1))
+            stride: if inverse { self.shape.size - 1 } else { 1 },
+        }
+    }
+}
+
+/// Returns `[1, g, g^2, ..., g^(n - 1)]`.
+fn powers<S: PrimeField>(worker: &Worker, g: S, n: usize) -> Vec<S> {
+    let mut powers = vec![S::zero(); n];
+    worker.scope(n, |scope, chunk| {
+        for (i, powers) in powers.chunks_mut(chunk).enumerate() {
+            scope.spawn(move |_scope| {
+                let mut u = g.pow_vartime([(i * chunk) as u64]);
+                for power in powers {
+                    *power = u;
+                    u.mul_assign(&g);
+                }
+            });
+        }
+    });
+
+    powers
+}
+
+pub struct EvaluationDomain<S: PrimeField, G: Group<S>> {
+    coeffs: Vec<G>,
+    shape: Shape<S>,
+    precomputed: Option<Arc<DomainPrecomputation<S>>>,
+}
+
 impl<S: PrimeField, G: Group<S>> AsRef<[G]> for EvaluationDomain<S, G> {
     fn as_ref(&self) -> &[G] {
         &self.coeffs
@@ -44,49 +277,85 @@ impl<S: PrimeField, G: Group<S>> EvaluationDomain<S, G> {
         self.coeffs
     }
 
-    pub fn from_coeffs(mut coeffs: Vec<G>) -> Result<EvaluationDomain<S, G>, SynthesisError> {
-        // Compute the size of our evaluation domain
-        let mut m = 1;
-        let mut exp = 0;
-        while m < coeffs.len() {
-            m *= 2;
-            exp += 1;
+    pub fn from_coeffs(coeffs: Vec<G>) -> Result<EvaluationDomain<S, G>, SynthesisError> {
+        Self::from_coeffs_with_config(coeffs, DomainConfig::default())
+    }
 
-            // The pairing-friendly curve may not be able to support
-            // large enough (radix2) evaluation domains.
-            if exp >= S::S {
-                return Err(SynthesisError::PolynomialDegreeTooLarge);
-            }
-        }
+    /// Creates the smallest domain that holds `coeffs` and is supported by
+    /// `config`, padding `coeffs` with zeroes.
+    ///
+    /// Returns [`SynthesisError::DivisionByZero`] if the coset shift is zero or
+    /// lies in the domain, as the coset would then overlap it.
+    pub fn from_coeffs_with_config(
+        mut coeffs: Vec<G>,
+        config: DomainConfig<S>,
+    ) -> Result<EvaluationDomain<S, G>, SynthesisError> {
+        let shape = Shape::new(coeffs.len(), config)?;
 
-        // Compute omega, the 2^exp primitive root of unity
-        let mut omega = S::root_of_unity();
-        for _ in exp..S::S {
-            omega = omega.square();
+        // Extend the coeffs vector with zeroes if necessary
+        coeffs.resize(shape.size, G::group_zero());
+
+        Ok(EvaluationDomain {
+            coeffs,
+            shape,
+            precomputed: None,
+        })
+    }
+
+    /// Creates a domain that uses the tables in `precomputed`, which must have
+    /// been computed for the domain that
+    /// [`EvaluationDomain::from_coeffs_with_config`] would choose for
+    /// `coeffs`.
+    pub fn from_coeffs_precomputed(
+        mut coeffs: Vec<G>,
+        precomputed: &Arc<DomainPrecomputation<S>>,
+    ) -> Result<EvaluationDomain<S, G>, SynthesisError> {
+        let (size, _, _) = domain_size(coeffs.len(), precomputed.config)?;
+        if size != precomputed.size() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the precomputed domain has a different size",
+            )
+            .into());
         }
 
-        // Extend the coeffs vector with zeroes if necessary
-        coeffs.resize(m, G::group_zero());
+        coeffs.resize(size, G::group_zero());
 
         Ok(EvaluationDomain {
             coeffs,
-            exp,
-            omega,
-            omegainv: omega.invert().unwrap(),
-            geninv: S::multiplicative_generator().invert().unwrap(),
-            minv: S::from(m as u64).invert().unwrap(),
+            shape: precomputed.shape.clone(),
+            precomputed: Some(precomputed.clone()),
         })
     }
 
+    /// The generator of the domain, whose powers are the points at which
+    /// [`Self::fft`] evaluates.
+    pub fn omega(&self) -> S {
+        self.shape.omega
+    }
+
+    /// The shift of the coset used by [`Self::coset_fft`].
+    pub fn coset_shift(&self) -> S {
+        self.shape.shift
+    }
+
     pub fn fft(&mut self, worker: &Worker) {
-        best_fft(&mut self.coeffs, worker, &self.omega, self.exp);
+        let roots = match &self.precomputed {
+            Some(precomputed) => precomputed.roots(false),
+            None => Roots::Computed(self.shape.omega),
+        };
+        transform(&mut self.coeffs, worker, &self.shape, roots);
     }
 
     pub fn ifft(&mut self, worker: &Worker) {
-        best_fft(&mut self.coeffs, worker, &self.omegainv, self.exp);
+        let roots = match &self.precomputed {
+            Some(precomputed) => precomputed.roots(true),
+            None => Roots::Computed(self.shape.omegainv),
+        };
+        transform(&mut self.coeffs, worker, &self.shape, roots);
 
         worker.scope(self.coeffs.len(), |scope, chunk| {
-            let minv = self.minv;
+            let minv = self.shape.minv;
 
             for v in self.coeffs.chunks_mut(chunk) {
                 scope.spawn(move |_scope| {
@@ -112,20 +381,41 @@ impl<S: PrimeField, G: Group<S>> EvaluationDomain<S, G> {
         });
     }
 
+    /// Multiplies each element by the corresponding element of `powers`.
+    fn mul_powers(coeffs: &mut [G], worker: &Worker, powers: &[S]) {
+        worker.scope(coeffs.len(), |scope, chunk| {
+            for (v, powers) in coeffs.chunks_mut(chunk).zip(powers.chunks(chunk)) {
+                scope.spawn(move |_scope| {
+                    for (v, power) in v.iter_mut().zip(powers) {
+                        v.group_mul_assign(power);
+                    }
+                });
+            }
+        });
+    }
+
     pub fn coset_fft(&mut self, worker: &Worker) {
-        self.distribute_powers(worker, S::multiplicative_generator());
+        match &self.precomputed {
+            Some(precomputed) => {
+                Self::mul_powers(&mut self.coeffs, worker, &precomputed.shift_powers)
+            }
+            None => self.distribute_powers(worker, self.shape.shift),
+        }
         self.fft(worker);
     }
 
     pub fn icoset_fft(&mut self, worker: &Worker) {
-        let geninv = self.geninv;
-
         self.ifft(worker);
-        self.distribute_powers(worker, geninv);
+        match &self.precomputed {
+            Some(precomputed) => {
+                Self::mul_powers(&mut self.coeffs, worker, &precomputed.shiftinv_powers)
+            }
+            None => self.distribute_powers(worker, self.shape.shiftinv),
+        }
     }
 
     /// This evaluates t(tau) for this domain, which is
-    /// tau^m - 1 for these radix-2 domains.
+    /// tau^m - 1 for these multiplicative subgroups.
     pub fn z(&self, tau: &S) -> S {
         let mut tmp = tau.pow_vartime(&[self.coeffs.len() as u64]);
         tmp.sub_assign(&S::one());
@@ -137,7 +427,10 @@ impl<S: PrimeField, G: Group<S>> EvaluationDomain<S, G> {
     /// evaluation domain, so we must perform division over
     /// a coset.
     pub fn divide_by_z_on_coset(&mut self, worker: &Worker) {
-        let i = self.z(&S::multiplicative_generator()).invert().unwrap();
+        let i = match &self.precomputed {
+            Some(precomputed) => precomputed.zinv,
+            None => self.z(&self.shape.shift).invert().unwrap(),
+        };
 
         worker.scope(self.coeffs.len(), |scope, chunk| {
             for v in self.coeffs.chunks_mut(chunk) {
@@ -258,18 +551,101 @@ impl<S: PrimeField> Group<S> for Scalar<S> {
     }
 }
 
-fn best_fft<S: PrimeField, T: Group<S>>(a: &mut [T], worker: &Worker, omega: &S, log_n: u32) {
+/// The powers of a root of unity that an FFT needs, which are either computed
+/// as needed or read from the precomputed powers of the domain generator.
+#[derive(Clone, Copy)]
+enum Roots<'a, S> {
+    Computed(S),
+    /// The root is `powers[stride]`.
+    Table {
+        powers: &'a [S],
+        stride: usize,
+    },
+}
+
+impl<'a, S: PrimeField> Roots<'a, S> {
+    /// Returns `root^k`.
+    fn pow(&self, k: usize) -> S {
+        match *self {
+            Roots::Computed(root) => root.pow_vartime([k as u64]),
+            Roots::Table { powers, stride } => powers[mul_mod(k, stride, powers.len())],
+        }
+    }
+
+    /// Returns the roots of `root^by`.
+    fn power(&self, by: usize) -> Self {
+        match *self {
+            Roots::Computed(root) => Roots::Computed(root.pow_vartime([by as u64])),
+            Roots::Table { powers, stride } => Roots::Table {
+                powers,
+                stride: mul_mod(stride, by, powers.len()),
+            },
+        }
+    }
+
+    /// Sets `out` to `[1, root, ..., root^(m - 1)]`.
+    fn fill(&self, m: usize, out: &mut Vec<S>) {
+        out.clear();
+        match *self {
+            Roots::Computed(root) => {
+                let mut w = S::one();
+                for _ in 0..m {
+                    out.push(w);
+                    w.mul_assign(&root);
+                }
+            }
+            Roots::Table { powers, stride } => {
+                let mut idx = 0;
+                for _ in 0..m {
+                    out.push(powers[idx]);
+                    idx += stride;
+                    if idx >= powers.len() {
+                        idx -= powers.len();
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn mul_mod(a: usize, b: usize, n: usize) -> usize {
+    (a as u128 * b as u128 % n as u128) as usize
+}
+
+/// Runs the FFT for a domain of the given shape, with `roots` of the domain
+/// generator or its inverse.
+fn transform<S: PrimeField, T: Group<S>>(
+    a: &mut [T],
+    worker: &Worker,
+    shape: &Shape<S>,
+    roots: Roots<'_, S>,
+) {
+    if shape.odd_radices.is_empty() {
+        best_fft(a, worker, roots, shape.exp);
+    } else {
+        let mut radices = shape.odd_radices.clone();
+        radices.resize(radices.len() + shape.exp as usize, 2);
+        mixed_radix_fft(a, worker, roots, &radices);
+    }
+}
+
+fn best_fft<S: PrimeField, T: Group<S>>(
+    a: &mut [T],
+    worker: &Worker,
+    roots: Roots<'_, S>,
+    log_n: u32,
+) {
     let log_cpus = worker.log_num_threads();
 
     if log_n <= log_cpus {
-        serial_fft(a, omega, log_n);
+        serial_fft(a, roots, log_n);
     } else {
-        parallel_fft(a, worker, omega, log_n, log_cpus);
+        parallel_fft(a, worker, roots, log_n, log_cpus);
     }
 }
 
 #[allow(clippy::many_single_char_names)]
-fn serial_fft<S: PrimeField, T: Group<S>>(a: &mut [T], omega: &S, log_n: u32) {
+fn serial_fft<S: PrimeField, T: Group<S>>(a: &mut [T], roots: Roots<'_, S>, log_n: u32) {
     fn bitreverse(mut n: u32, l: u32) -> u32 {
         let mut r = 0;
         for _ in 0..l {
@@ -289,21 +665,22 @@ fn serial_fft<S: PrimeField, T: Group<S>>(a: &mut [T], omega: &S, log_n: u32) {
         }
     }
 
+    let mut twiddles = Vec::with_capacity((n / 2) as usize);
     let mut m = 1;
     for _ in 0..log_n {
-        let w_m = omega.pow_vartime(&[u64::from(n / (2 * m))]);
+        roots
+            .power((n / (2 * m)) as usize)
+            .fill(m as usize, &mut twiddles);
 
         let mut k = 0;
         while k < n {
-            let mut w = S::one();
-            for j in 0..m {
+            for (j, w) in (0..m).zip(&twiddles) {
                 let mut t = a[(k + j + m) as usize];
-                t.group_mul_assign(&w);
+                t.group_mul_assign(w);
                 let mut tmp = a[(k + j) as usize];
                 tmp.group_sub_assign(&t);
                 a[(k + j + m) as usize] = tmp;
                 a[(k + j) as usize].group_add_assign(&t);
-                w.mul_assign(&w_m);
             }
 
             k += 2 * m;
@@ -316,7 +693,7 @@ fn serial_fft<S: PrimeField, T: Group<S>>(a: &mut [T], omega: &S, log_n: u32) {
 fn parallel_fft<S: PrimeField, T: Group<S>>(
     a: &mut [T],
     worker: &Worker,
-    omega: &S,
+    roots: Roots<'_, S>,
     log_n: u32,
     log_cpus: u32,
 ) {
@@ -325,16 +702,22 @@ fn parallel_fft<S: PrimeField, T: Group<S>>(
     let num_cpus = 1 << log_cpus;
     let log_new_n = log_n - log_cpus;
     let mut tmp = vec![vec![T::group_zero(); 1 << log_new_n]; num_cpus];
-    let new_omega = omega.pow_vartime(&[num_cpus as u64]);
+    let new_roots = roots.power(num_cpus);
 
     worker.scope(0, |scope, _| {
         let a = &*a;
 
         for (j, tmp) in tmp.iter_mut().enumerate() {
             scope.spawn(move |_scope| {
+                // A cancelled FFT leaves garbage behind, which the caller
+                // discards once it sees the cancellation.
+                if worker.is_cancelled() {
+                    return;
+                }
+
                 // Shuffle into a sub-FFT
-                let omega_j = omega.pow_vartime(&[j as u64]);
-                let omega_step = omega.pow_vartime(&[(j as u64) << log_new_n]);
+                let omega_j = roots.pow(j);
+                let omega_step = roots.pow(j << log_new_n);
 
                 let mut elt = S::one();
                 for (i, tmp) in tmp.iter_mut().enumerate() {
@@ -349,7 +732,7 @@ fn parallel_fft<S: PrimeField, T: Group<S>>(
                 }
 
                 // Perform sub-FFT
-                serial_fft(tmp, &new_omega, log_new_n);
+                serial_fft(tmp, new_roots, log_new_n);
             });
         }
     });
@@ -371,6 +754,94 @@ fn parallel_fft<S: PrimeField, T: Group<S>>(
     });
 }
 
+/// Cooley-Tukey FFT over a domain whose size is the product of `radices`.
+/// The sub-transforms of the first split are computed in parallel.
+fn mixed_radix_fft<S: PrimeField, T: Group<S>>(
+    a: &mut [T],
+    worker: &Worker,
+    roots: Roots<'_, S>,
+    radices: &[u32],
+) {
+    let (r, rest) = match radices.split_first() {
+        Some((&r, rest)) => (r as usize, rest),
+        None => return,
+    };
+    assert_eq!(a.len() % r, 0);
+
+    let sub_roots = roots.power(r);
+    let mut subs = split_strided(a, r);
+    worker.scope(0, |scope, _| {
+        for sub in subs.iter_mut() {
+            scope.spawn(move |_scope| {
+                if !worker.is_cancelled() {
+                    serial_mixed_radix_fft(sub, sub_roots, rest);
+                }
+            });
+        }
+    });
+
+    worker.scope(a.len(), |scope, chunk| {
+        let subs = &subs;
+
+        for (i, a) in a.chunks_mut(chunk).enumerate() {
+            scope.spawn(move |_scope| combine(a, i * chunk, subs, roots));
+        }
+    });
+}
+
+fn serial_mixed_radix_fft<S: PrimeField, T: Group<S>>(
+    a: &mut [T],
+    roots: Roots<'_, S>,
+    radices: &[u32],
+) {
+    if radices.iter().all(|&r| r == 2) {
+        serial_fft(a, roots, radices.len() as u32);
+        return;
+    }
+
+    let r = radices[0] as usize;
+    let mut subs = split_strided(a, r);
+    for sub in subs.iter_mut() {
+        serial_mixed_radix_fft(sub, roots.power(r), &radices[1..]);
+    }
+    combine(a, 0, &subs, roots);
+}
+
+/// Splits `a` into `r` subsequences, where subsequence `j` holds the elements
+/// at indices `j mod r`.
+fn split_strided<S: PrimeField, T: Group<S>>(a: &[T], r: usize) -> Vec<Vec<T>> {
+    (0..r)
+        .map(|j| a.iter().skip(j).step_by(r).copied().collect())
+        .collect()
+}
+
+/// Sets `out[i] = sum_j omega^(j (start + i)) subs[j][(start + i) mod m]`,
+/// which recombines the transforms of the subsequences from
+/// [`split_strided`].
+fn combine<S: PrimeField, T: Group<S>>(
+    out: &mut [T],
+    start: usize,
+    subs: &[Vec<T>],
+    roots: Roots<'_, S>,
+) {
+    let m = subs[0].len();
+    let omega = roots.pow(1);
+    let mut w = roots.pow(start);
+
+    for (i, out) in (start..).zip(out.iter_mut()) {
+        let mut acc = T::group_zero();
+        let mut twiddle = S::one();
+        for sub in subs {
+            let mut t = sub[i % m];
+            t.group_mul_assign(&twiddle);
+            acc.group_add_assign(&t);
+            twiddle.mul_assign(&w);
+        }
+        *out = acc;
+        w.mul_assign(&omega);
+    }
+}
+
 // Test multiplying various (low degree) polynomials together and
 // comparing with naive evaluations.
 #[cfg(feature = "pairing")]
@@ -483,8 +954,9 @@ fn parallel_fft_consistency() {
                 let mut v2 = EvaluationDomain::from_coeffs(v1.coeffs.clone()).unwrap();
 
                 for log_cpus in log_d..min(log_d + 1, 3) {
-                    parallel_fft(&mut v1.coeffs, &worker, &v1.omega, log_d, log_cpus);
-                    serial_fft(&mut v2.coeffs, &v2.omega, log_d);
+                    let roots = Roots::Computed(v1.shape.omega);
+                    parallel_fft(&mut v1.coeffs, &worker, roots, log_d, log_cpus);
+                    serial_fft(&mut v2.coeffs, roots, log_d);
 
                     assert!(v1.coeffs == v2.coeffs);
                 }
@@ -496,3 +968,136 @@ fn parallel_fft_consistency() {
 
     test_consistency::<Fr, _>(rng);
 }
+
+#[cfg(feature = "pairing")]
+#[test]
+fn mixed_radix_domains() {
+    use bls12_381::Scalar as Fr;
+    use rand_core::RngCore;
+
+    fn evaluate<S: PrimeField>(coeffs: &[Scalar<S>], x: S) -> S {
+        coeffs.iter().rev().fold(S::zero(), |acc, c| acc * x + c.0)
+    }
+
+    fn test_mixed<S: PrimeField, R: RngCore>(mut rng: &mut R, subgroup: SmallSubgroup<S>) {
+        let worker = Worker::new();
+        let config = DomainConfig {
+            coset_shift: S::from(5),
+            subgroup: Some(subgroup),
+        };
+
+        for (len, size) in [
+            (1, 1),
+            (3, 3),
+            (5, 6),
+            (7, 8),
+            (40, 48),
+            (100, 128),
+            (150, 192),
+        ] {
+            let v: Vec<_> = (0..len).map(|_| Scalar::<S>(S::random(&mut rng))).collect();
+            let mut domain = EvaluationDomain::from_coeffs_with_config(v.clone(), config).unwrap();
+            assert_eq!(domain.coeffs.len(), size);
+            let padded = domain.coeffs.clone();
+            let (omega, shift) = (domain.shape.omega, domain.shape.shift);
+            assert_eq!(omega.pow_vartime([size as u64]), S::one());
+
+            domain.fft(&worker);
+            for (i, eval) in domain.coeffs.iter().enumerate() {
+                assert!(eval.0 == evaluate(&v, omega.pow_vartime([i as u64])));
+            }
+            domain.ifft(&worker);
+            assert!(domain.coeffs == padded);
+
+            domain.coset_fft(&worker);
+            for (i, eval) in domain.coeffs.iter().enumerate() {
+                assert!(eval.0 == evaluate(&v, shift * omega.pow_vartime([i as u64])));
+            }
+            domain.icoset_fft(&worker);
+            assert!(domain.coeffs == padded);
+        }
+
+        // The coset must not overlap the domain.
+        for coset_shift in [S::zero(), S::one(), subgroup.root] {
+            let config = DomainConfig {
+                coset_shift,
+                subgroup: Some(subgroup),
+            };
+            assert!(matches!(
+                EvaluationDomain::from_coeffs_with_config(vec![Scalar(S::one()); 3], config),
+                Err(SynthesisError::DivisionByZero)
+            ));
+        }
+    }
+
+    // -3 is a square as 3 divides p - 1, and (-1 + sqrt(-3)) / 2 is a
+    // primitive cube root of unity.
+    let sqrt = (-Fr::from(3)).sqrt().unwrap();
+    let root = (sqrt - Fr::one()) * Fr::from(2).invert().unwrap();
+    assert!(SmallSubgroup::new(3, 1, Fr::one()).is_none());
+    assert!(SmallSubgroup::new(3, 2, root).is_none());
+    assert!(SmallSubgroup::new(9, 1, root).is_none());
+    let subgroup = SmallSubgroup::new(3, 1, root).unwrap();
+
+    let rng = &mut rand::thread_rng();
+
+    test_mixed::<Fr, _>(rng, subgroup);
+}
+
+#[cfg(feature = "pairing")]
+#[test]
+fn precomputed_domains() {
+    use bls12_381::Scalar as Fr;
+    use rand_core::RngCore;
+
+    fn test_precomputed<S: PrimeField, R: RngCore>(mut rng: &mut R, subgroup: SmallSubgroup<S>) {
+        let worker = Worker::new();
+        let mixed = DomainConfig {
+            coset_shift: S::from(5),
+            subgroup: Some(subgroup),
+        };
+
+        for config in [DomainConfig::default(), mixed] {
+            for len in [1, 2, 5, 24, 100, 1000] {
+                let precomputed =
+                    Arc::new(DomainPrecomputation::new(&worker, len, config).unwrap());
+                let v: Vec<_> = (0..len).map(|_| Scalar::<S>(S::random(&mut rng))).collect();
+                let mut plain =
+                    EvaluationDomain::from_coeffs_with_config(v.clone(), config).unwrap();
+                let mut domain =
+                    EvaluationDomain::from_coeffs_precomputed(v, &precomputed).unwrap();
+                assert_eq!(domain.coeffs.len(), precomputed.size());
+                assert!(domain.coeffs == plain.coeffs);
+
+                domain.fft(&worker);
+                plain.fft(&worker);
+                assert!(domain.coeffs == plain.coeffs);
+                domain.ifft(&worker);
+                plain.ifft(&worker);
+                assert!(domain.coeffs == plain.coeffs);
+                domain.coset_fft(&worker);
+                plain.coset_fft(&worker);
+                assert!(domain.coeffs == plain.coeffs);
+                domain.divide_by_z_on_coset(&worker);
+                plain.divide_by_z_on_coset(&worker);
+                assert!(domain.coeffs == plain.coeffs);
+                domain.icoset_fft(&worker);
+                plain.icoset_fft(&worker);
+                assert!(domain.coeffs == plain.coeffs);
+
+                // Only coefficients that need a domain of the same size fit.
+                let bigger = vec![Scalar(S::one()); precomputed.size() + 1];
+                assert!(EvaluationDomain::from_coeffs_precomputed(bigger, &precomputed).is_err());
+            }
+        }
+    }
+
+    // (-1 + sqrt(-3)) / 2 is a primitive cube root of unity.
+    let sqrt = (-Fr::from(3)).sqrt().unwrap();
+    let root = (sqrt - Fr::one()) * Fr::from(2).invert().unwrap();
+    let subgroup = SmallSubgroup::new(3, 1, root).unwrap();
+
+    let rng = &mut rand::thread_rng();
+
+    test_precomputed::<Fr, _>(rng, subgroup);
+}
@@ -2,17 +2,24 @@
 
 pub mod test;
 
+pub mod blake2b;
 pub mod blake2s;
 pub mod boolean;
 pub mod constants;
 pub mod ecc;
+pub mod keccak;
 pub mod lookup;
+pub mod metrics;
 pub mod mimc7;
 pub mod multieq;
 pub mod multipack;
+pub mod nonnative;
 pub mod num;
 pub mod pedersen;
+pub mod secp256k1;
 pub mod sha256;
+pub mod sha512;
+pub mod uint;
 pub mod uint32;
 
 use crate::SynthesisError;
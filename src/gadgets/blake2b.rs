@@ -0,0 +1,181 @@
+//! The [BLAKE2b] hash function with key, salt and personalization support.
+//!
+//! This shares its compression function with the [`blake2s`] gadget, over
+//! 64-bit words.
+//!
+//! [BLAKE2b]: https://tools.ietf.org/html/rfc7693
+//! [`blake2s`]: super::blake2s
+
+use super::blake2s::{blake2, Word};
+use super::boolean::Boolean;
+use crate::{ConstraintSystem, SynthesisError};
+use ff::PrimeField;
+
+impl Word for u64 {
+    const ROUNDS: usize = 12;
+    const ROTATIONS: [usize; 4] = [32, 24, 16, 63];
+
+    /*
+    static const uint64_t blake2b_IV[8] =
+    {
+        0x6a09e667f3bcc908ULL, 0xbb67ae8584caa73bULL,
+        0x3c6ef372fe94f82bULL, 0xa54ff53a5f1d36f1ULL,
+        0x510e527fade682d1ULL, 0x9b05688c2b3e6c1fULL,
+        0x1f83d9abfb41bd6bULL, 0x5be0cd19137e2179ULL
+    };
+    */
+    const IV: [u64; 8] = [
+        0x6a09e667f3bcc908,
+        0xbb67ae8584caa73b,
+        0x3c6ef372fe94f82b,
+        0xa54ff53a5f1d36f1,
+        0x510e527fade682d1,
+        0x9b05688c2b3e6c1f,
+        0x1f83d9abfb41bd6b,
+        0x5be0cd19137e2179,
+    ];
+}
+
+/// Parameters of the BLAKE2b hash function.
+#[derive(Clone, Debug)]
+pub struct Params {
+    /// The length of the hash in bytes, between 1 and 64.
+    pub hash_length: usize,
+    /// The salt.
+    pub salt: [u8; 16],
+    /// The personalization string.
+    pub personalization: [u8; 16],
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            hash_length: 64,
+            salt: [0; 16],
+            personalization: [0; 16],
+        }
+    }
+}
+
+/// Computes the 64-byte BLAKE2b hash of `input` with the given 16-byte
+/// personalization.
+pub fn blake2b<Scalar: PrimeField, CS: ConstraintSystem<Scalar>>(
+    cs: CS,
+    input: &[Boolean],
+    personalization: &[u8],
+) -> Result<Vec<Boolean>, SynthesisError> {
+    assert_eq!(personalization.len(), 16);
+
+    blake2::<_, _, u64>(cs, input, &[], &[0; 16], personalization, 64)
+}
+
+/// Computes the BLAKE2b hash of `input` with the given parameters.
+///
+/// `key` may be up to 64 bytes long, or empty for an unkeyed hash. A keyed
+/// hash is a MAC of `input`; the key is given in circuit bits so that it can
+/// be kept secret.
+pub fn blake2b_with_params<Scalar: PrimeField, CS: ConstraintSystem<Scalar>>(
+    cs: CS,
+    input: &[Boolean],
+    key: &[Boolean],
+    params: &Params,
+) -> Result<Vec<Boolean>, SynthesisError> {
+    blake2::<_, _, u64>(
+        cs,
+        input,
+        key,
+        &params.salt,
+        &params.personalization,
+        params.hash_length,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use bls12_381::Scalar;
+    use hex_literal::hex;
+
+    use super::{blake2b, blake2b_with_params, Params};
+    use crate::gadgets::multipack::bytes_to_bits_le;
+    use crate::gadgets::test::{alloc_bits, bit_values, TestConstraintSystem};
+    use crate::ConstraintSystem;
+
+    #[test]
+    fn test_blake2b_abc() {
+        let mut cs = TestConstraintSystem::<Scalar>::new();
+        let input = alloc_bits(cs.namespace(|| "input"), &bytes_to_bits_le(b"abc"));
+        let out = blake2b(cs.namespace(|| "blake2b"), &input, &[0; 16]).unwrap();
+
+        assert!(cs.is_satisfied());
+        // RFC 7693, Appendix A
+        assert_eq!(
+            bit_values(&out),
+            bytes_to_bits_le(&hex!(
+                "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1"
+                "7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_blake2b_with_params() {
+        let key: Vec<u8> = (0..64).collect();
+        let data: Vec<u8> = (0..300).map(|i| (i * 7 + 3) as u8).collect();
+        let personalization = *b"zcash-personal!!";
+
+        // >>> from hashlib import blake2b
+        // >>> blake2b(b'', key=bytes(range(64))).hexdigest()
+        let mut cs = TestConstraintSystem::<Scalar>::new();
+        let key_bits = alloc_bits(cs.namespace(|| "key"), &bytes_to_bits_le(&key));
+        let out = blake2b_with_params(
+            cs.namespace(|| "blake2b"),
+            &[],
+            &key_bits,
+            &Params::default(),
+        )
+        .unwrap();
+        assert!(cs.is_satisfied());
+        assert_eq!(
+            bit_values(&out),
+            bytes_to_bits_le(&hex!(
+                "10ebb67700b1868efb4417987acf4690ae9d972fb7a590c2f02871799aaa4786"
+                "b5e996e8f0f4eb981fc214b005f42d2ff4233499391653df7aefcbc13fc51568"
+            ))
+        );
+
+        // >>> blake2b(data, key=bytes(range(20)), salt=bytes(range(16, 32)),
+        // ...         person=b'zcash-personal!!', digest_size=40).hexdigest()
+        let mut cs = TestConstraintSystem::<Scalar>::new();
+        let key_bits = alloc_bits(cs.namespace(|| "key"), &bytes_to_bits_le(&key[..20]));
+        let input = alloc_bits(cs.namespace(|| "input"), &bytes_to_bits_le(&data));
+        let params = Params {
+            hash_length: 40,
+            salt: (16..32).collect::<Vec<_>>().try_into().unwrap(),
+            personalization,
+        };
+        let out =
+            blake2b_with_params(cs.namespace(|| "blake2b"), &input, &key_bits, &params).unwrap();
+        assert!(cs.is_satisfied());
+        assert_eq!(
+            bit_values(&out),
+            bytes_to_bits_le(&hex!(
+                "527b1a12be9209217ea2b4aa114fdeb18012be358f0d2ba3467c82ecba1cdfae"
+                "5c69fb38ac5c7916"
+            ))
+        );
+
+        // A single full block.
+        // >>> blake2b(data[:128], person=b'zcash-personal!!').hexdigest()
+        let mut cs = TestConstraintSystem::<Scalar>::new();
+        let input = alloc_bits(cs.namespace(|| "input"), &bytes_to_bits_le(&data[..128]));
+        let out = blake2b(cs.namespace(|| "blake2b"), &input, &personalization).unwrap();
+        assert!(cs.is_satisfied());
+        assert_eq!(
+            bit_values(&out),
+            bytes_to_bits_le(&hex!(
+                "68bb9b07043d82e816426d687cbed3e157c435edf303ff6728fcb4a82b0a7b3d"
+                "5f8f2b26da3f35c4b21f375da1a9c50ffb12184b4172cbdb18e3c0392b4c8149"
+            ))
+        );
+    }
+}
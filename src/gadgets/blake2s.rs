@@ -1,8 +1,16 @@
-//! The [BLAKE2s] hash function with personalization support.
+//! The [BLAKE2s] hash function with key, salt and personalization support.
+//!
+//! The compression function is generic over the word size, and is shared
+//! with the [`blake2b`] gadget.
 //!
 //! [BLAKE2s]: https://tools.ietf.org/html/rfc7693
+//! [`blake2b`]: super::blake2b
 
-use super::{boolean::Boolean, multieq::MultiEq, uint32::UInt32};
+use super::{
+    boolean::Boolean,
+    multieq::MultiEq,
+    uint::{UInt, UIntValue},
+};
 use crate::{ConstraintSystem, SynthesisError};
 use ff::PrimeField;
 
@@ -23,10 +31,34 @@ use ff::PrimeField;
               --------------+------------------+------------------+
 */
 
-const R1: usize = 16;
-const R2: usize = 12;
-const R3: usize = 8;
-const R4: usize = 7;
+/// A word of one of the BLAKE2 variants.
+pub(super) trait Word: UIntValue {
+    /// The number of rounds of the compression function.
+    const ROUNDS: usize;
+
+    /// The rotation constants `(R1, R2, R3, R4)` of the mixing function.
+    const ROTATIONS: [usize; 4];
+
+    /// The initialization vector.
+    const IV: [Self; 8];
+}
+
+impl Word for u32 {
+    const ROUNDS: usize = 10;
+    const ROTATIONS: [usize; 4] = [16, 12, 8, 7];
+
+    /*
+    static const uint32_t blake2s_iv[8] =
+    {
+        0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A,
+        0x510E527F, 0x9B05688C, 0x1F83D9AB, 0x5BE0CD19
+    };
+    */
+    const IV: [u32; 8] = [
+        0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A, 0x510E527F, 0x9B05688C, 0x1F83D9AB,
+        0x5BE0CD19,
+    ];
+}
 
 /*
           Round   |  0  1  2  3  4  5  6  7  8  9 10 11 12 13 14 15 |
@@ -80,36 +112,38 @@ const SIGMA: [[usize; 16]; 10] = [
 */
 
 #[allow(clippy::many_single_char_names)]
-fn mixing_g<Scalar: PrimeField, CS: ConstraintSystem<Scalar>, M>(
+fn mixing_g<Scalar: PrimeField, CS: ConstraintSystem<Scalar>, M, W: Word>(
     mut cs: M,
-    v: &mut [UInt32],
+    v: &mut [UInt<W>],
     (a, b, c, d): (usize, usize, usize, usize),
-    x: &UInt32,
-    y: &UInt32,
+    x: &UInt<W>,
+    y: &UInt<W>,
 ) -> Result<(), SynthesisError>
 where
     M: ConstraintSystem<Scalar, Root = MultiEq<Scalar, CS>>,
 {
-    v[a] = UInt32::addmany(
+    let [r1, r2, r3, r4] = W::ROTATIONS;
+
+    v[a] = UInt::addmany_multieq(
         cs.namespace(|| "mixing step 1"),
         &[v[a].clone(), v[b].clone(), x.clone()],
     )?;
-    v[d] = v[d].xor(cs.namespace(|| "mixing step 2"), &v[a])?.rotr(R1);
-    v[c] = UInt32::addmany(
+    v[d] = v[d].xor(cs.namespace(|| "mixing step 2"), &v[a])?.rotr(r1);
+    v[c] = UInt::addmany_multieq(
         cs.namespace(|| "mixing step 3"),
         &[v[c].clone(), v[d].clone()],
     )?;
-    v[b] = v[b].xor(cs.namespace(|| "mixing step 4"), &v[c])?.rotr(R2);
-    v[a] = UInt32::addmany(
+    v[b] = v[b].xor(cs.namespace(|| "mixing step 4"), &v[c])?.rotr(r2);
+    v[a] = UInt::addmany_multieq(
         cs.namespace(|| "mixing step 5"),
         &[v[a].clone(), v[b].clone(), y.clone()],
     )?;
-    v[d] = v[d].xor(cs.namespace(|| "mixing step 6"), &v[a])?.rotr(R3);
-    v[c] = UInt32::addmany(
+    v[d] = v[d].xor(cs.namespace(|| "mixing step 6"), &v[a])?.rotr(r3);
+    v[c] = UInt::addmany_multieq(
         cs.namespace(|| "mixing step 7"),
         &[v[c].clone(), v[d].clone()],
     )?;
-    v[b] = v[b].xor(cs.namespace(|| "mixing step 8"), &v[c])?.rotr(R4);
+    v[b] = v[b].xor(cs.namespace(|| "mixing step 8"), &v[c])?.rotr(r4);
 
     Ok(())
 }
@@ -163,54 +197,42 @@ where
 */
 
 #[allow(clippy::many_single_char_names)]
-fn blake2s_compression<Scalar: PrimeField, CS: ConstraintSystem<Scalar>>(
+fn compression<Scalar: PrimeField, CS: ConstraintSystem<Scalar>, W: Word>(
     mut cs: CS,
-    h: &mut [UInt32],
-    m: &[UInt32],
-    t: u64,
+    h: &mut [UInt<W>],
+    m: &[UInt<W>],
+    t: u128,
     f: bool,
 ) -> Result<(), SynthesisError> {
     assert_eq!(h.len(), 8);
     assert_eq!(m.len(), 16);
 
-    /*
-    static const uint32_t blake2s_iv[8] =
-    {
-        0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A,
-        0x510E527F, 0x9B05688C, 0x1F83D9AB, 0x5BE0CD19
-    };
-    */
-
     let mut v = Vec::with_capacity(16);
     v.extend_from_slice(h);
-    v.push(UInt32::constant(0x6A09E667));
-    v.push(UInt32::constant(0xBB67AE85));
-    v.push(UInt32::constant(0x3C6EF372));
-    v.push(UInt32::constant(0xA54FF53A));
-    v.push(UInt32::constant(0x510E527F));
-    v.push(UInt32::constant(0x9B05688C));
-    v.push(UInt32::constant(0x1F83D9AB));
-    v.push(UInt32::constant(0x5BE0CD19));
+    v.extend(W::IV.iter().map(|&iv| UInt::constant(iv)));
 
     assert_eq!(v.len(), 16);
 
-    v[12] = v[12].xor(cs.namespace(|| "first xor"), &UInt32::constant(t as u32))?;
+    v[12] = v[12].xor(
+        cs.namespace(|| "first xor"),
+        &UInt::constant(W::truncate(t)),
+    )?;
     v[13] = v[13].xor(
         cs.namespace(|| "second xor"),
-        &UInt32::constant((t >> 32) as u32),
+        &UInt::constant(W::truncate(t >> W::BITS)),
     )?;
 
     if f {
         v[14] = v[14].xor(
             cs.namespace(|| "third xor"),
-            &UInt32::constant(u32::max_value()),
+            &UInt::constant(W::truncate(u128::MAX)),
         )?;
     }
 
     {
         let mut cs = MultiEq::new(&mut cs);
 
-        for i in 0..10 {
+        for i in 0..W::ROUNDS {
             let mut cs = cs.namespace(|| format!("round {}", i));
 
             let s = SIGMA[i % 10];
@@ -312,68 +334,166 @@ fn blake2s_compression<Scalar: PrimeField, CS: ConstraintSystem<Scalar>>(
         END FUNCTION.
 */
 
-pub fn blake2s<Scalar: PrimeField, CS: ConstraintSystem<Scalar>>(
+/// Computes the BLAKE2 hash of `input` with the given word size, which must
+/// be a whole number of bytes.
+///
+/// `key` is given in circuit bits, and may be empty for an unkeyed hash.
+/// `salt` and `personalization` are each two words long.
+pub(super) fn blake2<Scalar: PrimeField, CS: ConstraintSystem<Scalar>, W: Word>(
     mut cs: CS,
     input: &[Boolean],
+    key: &[Boolean],
+    salt: &[u8],
     personalization: &[u8],
+    hash_length: usize,
 ) -> Result<Vec<Boolean>, SynthesisError> {
-    use byteorder::{ByteOrder, LittleEndian};
+    let word_bytes = W::BITS / 8;
+    let block_bits = 16 * W::BITS;
 
-    assert_eq!(personalization.len(), 8);
     assert!(input.len() % 8 == 0);
+    assert!(key.len() % 8 == 0);
+    assert!(key.len() <= 8 * W::BITS);
+    assert!(hash_length >= 1 && hash_length <= 8 * word_bytes);
+    assert_eq!(salt.len(), 2 * word_bytes);
+    assert_eq!(personalization.len(), 2 * word_bytes);
+
+    // Reads the little-endian word at the start of `bytes`.
+    let read_word = |bytes: &[u8]| {
+        W::truncate(
+            bytes[..word_bytes]
+                .iter()
+                .rev()
+                .fold(0, |acc, &b| (acc << 8) | u128::from(b)),
+        )
+    };
+    let xor_word = |a: W, b: W| W::truncate(a.to_u128() ^ b.to_u128());
+
+    let mut h: Vec<_> = W::IV.to_vec();
+
+    // Parameter block p[0]
+    h[0] = xor_word(
+        h[0],
+        W::truncate(0x01010000 ^ ((key.len() as u128 / 8) << 8) ^ hash_length as u128),
+    );
+    // Salt is stored here
+    h[4] = xor_word(h[4], read_word(&salt[..word_bytes]));
+    h[5] = xor_word(h[5], read_word(&salt[word_bytes..]));
+    // Personalization is stored here
+    h[6] = xor_word(h[6], read_word(&personalization[..word_bytes]));
+    h[7] = xor_word(h[7], read_word(&personalization[word_bytes..]));
+
+    let mut h: Vec<_> = h.into_iter().map(UInt::constant).collect();
+
+    // The key is padded to a full block, which is processed first.
+    let mut data = key.to_vec();
+    if !key.is_empty() {
+        data.resize(block_bits, Boolean::constant(false));
+    }
+    data.extend_from_slice(input);
 
-    let mut h = vec![
-        UInt32::constant(0x6A09E667 ^ 0x01010000 ^ 32),
-        UInt32::constant(0xBB67AE85),
-        UInt32::constant(0x3C6EF372),
-        UInt32::constant(0xA54FF53A),
-        UInt32::constant(0x510E527F),
-        UInt32::constant(0x9B05688C),
-        // Personalization is stored here
-        UInt32::constant(0x1F83D9AB ^ LittleEndian::read_u32(&personalization[0..4])),
-        UInt32::constant(0x5BE0CD19 ^ LittleEndian::read_u32(&personalization[4..8])),
-    ];
-
-    let mut blocks: Vec<Vec<UInt32>> = vec![];
+    let mut blocks: Vec<Vec<UInt<W>>> = vec![];
 
-    for block in input.chunks(512) {
+    for block in data.chunks(block_bits) {
         let mut this_block = Vec::with_capacity(16);
-        for word in block.chunks(32) {
+        for word in block.chunks(W::BITS) {
             let mut tmp = word.to_vec();
-            while tmp.len() < 32 {
+            while tmp.len() < W::BITS {
                 tmp.push(Boolean::constant(false));
             }
-            this_block.push(UInt32::from_bits(&tmp));
+            this_block.push(UInt::from_bits(&tmp));
         }
         while this_block.len() < 16 {
-            this_block.push(UInt32::constant(0));
+            this_block.push(UInt::constant(W::truncate(0)));
         }
         blocks.push(this_block);
     }
 
     if blocks.is_empty() {
-        blocks.push((0..16).map(|_| UInt32::constant(0)).collect());
+        blocks.push((0..16).map(|_| UInt::constant(W::truncate(0))).collect());
     }
 
     for (i, block) in blocks[0..blocks.len() - 1].iter().enumerate() {
         let cs = cs.namespace(|| format!("block {}", i));
 
-        blake2s_compression(cs, &mut h, block, ((i as u64) + 1) * 64, false)?;
+        compression(
+            cs,
+            &mut h,
+            block,
+            ((i as u128) + 1) * (block_bits as u128 / 8),
+            false,
+        )?;
     }
 
     {
         let cs = cs.namespace(|| "final block");
 
-        blake2s_compression(
+        compression(
             cs,
             &mut h,
             &blocks[blocks.len() - 1],
-            (input.len() / 8) as u64,
+            (data.len() / 8) as u128,
             true,
         )?;
     }
 
-    Ok(h.into_iter().flat_map(|b| b.into_bits()).collect())
+    Ok(h.into_iter()
+        .flat_map(|b| b.into_bits())
+        .take(hash_length * 8)
+        .collect())
+}
+
+/// Parameters of the BLAKE2s hash function.
+#[derive(Clone, Debug)]
+pub struct Params {
+    /// The length of the hash in bytes, between 1 and 32.
+    pub hash_length: usize,
+    /// The salt.
+    pub salt: [u8; 8],
+    /// The personalization string.
+    pub personalization: [u8; 8],
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            hash_length: 32,
+            salt: [0; 8],
+            personalization: [0; 8],
+        }
+    }
+}
+
+/// Computes the 32-byte BLAKE2s hash of `input` with the given 8-byte
+/// personalization.
+pub fn blake2s<Scalar: PrimeField, CS: ConstraintSystem<Scalar>>(
+    cs: CS,
+    input: &[Boolean],
+    personalization: &[u8],
+) -> Result<Vec<Boolean>, SynthesisError> {
+    assert_eq!(personalization.len(), 8);
+
+    blake2::<_, _, u32>(cs, input, &[], &[0; 8], personalization, 32)
+}
+
+/// Computes the BLAKE2s hash of `input` with the given parameters.
+///
+/// `key` may be up to 32 bytes long, or empty for an unkeyed hash. A keyed
+/// hash is a MAC of `input`; the key is given in circuit bits so that it can
+/// be kept secret.
+pub fn blake2s_with_params<Scalar: PrimeField, CS: ConstraintSystem<Scalar>>(
+    cs: CS,
+    input: &[Boolean],
+    key: &[Boolean],
+    params: &Params,
+) -> Result<Vec<Boolean>, SynthesisError> {
+    blake2::<_, _, u32>(
+        cs,
+        input,
+        key,
+        &params.salt,
+        &params.personalization,
+        params.hash_length,
+    )
 }
 
 #[cfg(test)]
@@ -384,7 +504,7 @@ mod test {
     use rand_core::{RngCore, SeedableRng};
     use rand_xorshift::XorShiftRng;
 
-    use super::blake2s;
+    use super::{blake2s, blake2s_with_params, Params};
     use crate::gadgets::boolean::{AllocatedBit, Boolean};
     use crate::gadgets::test::TestConstraintSystem;
     use crate::ConstraintSystem;
@@ -665,4 +785,69 @@ mod test {
             assert_eq!(expected, hash_result.as_bytes());
         }
     }
+
+    #[test]
+    fn test_blake2s_with_params() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        for (key_len, input_len, hash_length) in [
+            (0, 0, 1),
+            (32, 0, 32),
+            (1, 3, 20),
+            (16, 64, 32),
+            (32, 65, 16),
+            (7, 200, 31),
+        ] {
+            let key: Vec<u8> = (0..key_len).map(|_| rng.next_u32() as u8).collect();
+            let data: Vec<u8> = (0..input_len).map(|_| rng.next_u32() as u8).collect();
+            let mut params = Params {
+                hash_length,
+                ..Default::default()
+            };
+            rng.fill_bytes(&mut params.salt);
+            rng.fill_bytes(&mut params.personalization);
+
+            let expected = Blake2sParams::new()
+                .hash_length(hash_length)
+                .key(&key)
+                .salt(&params.salt)
+                .personal(&params.personalization)
+                .hash(&data);
+
+            let mut cs = TestConstraintSystem::<Scalar>::new();
+            let mut alloc = |name: &str, bytes: &[u8]| -> Vec<Boolean> {
+                bytes
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(byte_i, byte)| (0..8).map(move |bit_i| (byte_i, bit_i, byte)))
+                    .map(|(byte_i, bit_i, byte)| {
+                        AllocatedBit::alloc(
+                            cs.namespace(|| format!("{} bit {} {}", name, byte_i, bit_i)),
+                            Some((byte >> bit_i) & 1 == 1),
+                        )
+                        .unwrap()
+                        .into()
+                    })
+                    .collect()
+            };
+            let key_bits = alloc("key", &key);
+            let input_bits = alloc("input", &data);
+
+            let r = blake2s_with_params(&mut cs, &input_bits, &key_bits, &params).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(r.len(), hash_length * 8);
+
+            let expected_bits = expected
+                .as_bytes()
+                .iter()
+                .flat_map(|&byte| (0..8).map(move |i| (byte >> i) & 1u8 == 1u8));
+            for (b, e) in r.iter().zip(expected_bits) {
+                assert_eq!(b.get_value().unwrap(), e);
+            }
+        }
+    }
 }
@@ -0,0 +1,329 @@
+//! Circuits for the [Keccak-f\[1600\]] permutation and the Keccak-256 and
+//! [SHA3-256] hash functions built on it.
+//!
+//! As with [`blake2s`], input and output bits are in little-endian order
+//! within each byte.
+//!
+//! [Keccak-f\[1600\]]: https://keccak.team/keccak_specs_summary.html
+//! [SHA3-256]: https://doi.org/10.6028/NIST.FIPS.202
+//! [`blake2s`]: super::blake2s
+
+use super::boolean::Boolean;
+use super::uint::UInt64;
+use crate::{ConstraintSystem, SynthesisError};
+use ff::PrimeField;
+
+#[allow(clippy::unreadable_literal)]
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+/// The rotation offsets of the rho step, indexed by `x + 5 * y`.
+const ROTATION_OFFSETS: [usize; 25] = [
+    0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8, 18, 2, 61, 56, 14,
+];
+
+/// The rate of Keccak-256 and SHA3-256 in bits.
+const RATE_256: usize = 1088;
+
+/// Applies the Keccak-f\[1600\] permutation to a state of 25 lanes, where lane
+/// `(x, y)` is at index `x + 5 * y`.
+pub fn keccak_f1600<Scalar, CS>(mut cs: CS, state: &[UInt64]) -> Result<Vec<UInt64>, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    assert_eq!(state.len(), 25);
+
+    let mut a = state.to_vec();
+
+    for (round, rc) in ROUND_CONSTANTS.iter().enumerate() {
+        let cs = &mut cs.namespace(|| format!("round {}", round));
+
+        // theta: c[x] := a[x, 0] xor a[x, 1] xor a[x, 2] xor a[x, 3] xor a[x, 4]
+        let c = (0..5)
+            .map(|x| {
+                let mut c = a[x].clone();
+                for y in 1..5 {
+                    c = c.xor(
+                        cs.namespace(|| format!("theta c[{}] xor a[{}, {}]", x, x, y)),
+                        &a[x + 5 * y],
+                    )?;
+                }
+                Ok(c)
+            })
+            .collect::<Result<Vec<_>, SynthesisError>>()?;
+
+        // theta: a[x, y] := a[x, y] xor c[x - 1] xor (c[x + 1] leftrotate 1)
+        for x in 0..5 {
+            let d = c[(x + 4) % 5].xor(
+                cs.namespace(|| format!("theta d[{}]", x)),
+                &c[(x + 1) % 5].rotl(1),
+            )?;
+            for y in 0..5 {
+                a[x + 5 * y] =
+                    a[x + 5 * y].xor(cs.namespace(|| format!("theta a[{}, {}]", x, y)), &d)?;
+            }
+        }
+
+        // rho and pi: b[y, 2x + 3y] := a[x, y] leftrotate r[x, y]
+        let mut b = a.clone();
+        for x in 0..5 {
+            for y in 0..5 {
+                b[y + 5 * ((2 * x + 3 * y) % 5)] = a[x + 5 * y].rotl(ROTATION_OFFSETS[x + 5 * y]);
+            }
+        }
+
+        // chi: a[x, y] := b[x, y] xor ((not b[x + 1, y]) and b[x + 2, y])
+        for x in 0..5 {
+            for y in 0..5 {
+                let t = b[(x + 1) % 5 + 5 * y].not().and(
+                    cs.namespace(|| format!("chi and_not [{}, {}]", x, y)),
+                    &b[(x + 2) % 5 + 5 * y],
+                )?;
+                a[x + 5 * y] =
+                    b[x + 5 * y].xor(cs.namespace(|| format!("chi xor [{}, {}]", x, y)), &t)?;
+            }
+        }
+
+        // iota: a[0, 0] := a[0, 0] xor rc
+        a[0] = a[0].xor(cs.namespace(|| "iota"), &UInt64::constant(*rc))?;
+    }
+
+    Ok(a)
+}
+
+/// Computes the Keccak-256 hash of `input`, as used by Ethereum.
+pub fn keccak256<Scalar, CS>(cs: CS, input: &[Boolean]) -> Result<Vec<Boolean>, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    sponge(cs, input, &[], RATE_256, 256)
+}
+
+/// Computes the SHA3-256 hash of `input`.
+pub fn sha3_256<Scalar, CS>(cs: CS, input: &[Boolean]) -> Result<Vec<Boolean>, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    // SHA-3 appends the domain separation bits 01 to the message.
+    sponge(
+        cs,
+        input,
+        &[Boolean::constant(false), Boolean::constant(true)],
+        RATE_256,
+        256,
+    )
+}
+
+/// Absorbs `input` followed by `suffix` into the Keccak sponge with the
+/// given rate, and squeezes `output_len` bits, which must not exceed the rate.
+fn sponge<Scalar, CS>(
+    mut cs: CS,
+    input: &[Boolean],
+    suffix: &[Boolean],
+    rate: usize,
+    output_len: usize,
+) -> Result<Vec<Boolean>, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    assert!(input.len() % 8 == 0);
+    assert!(rate % 64 == 0 && rate < 1600);
+    assert!(output_len <= rate);
+
+    // pad10*1
+    let mut padded = input.to_vec();
+    padded.extend_from_slice(suffix);
+    padded.push(Boolean::constant(true));
+    while padded.len() % rate != rate - 1 {
+        padded.push(Boolean::constant(false));
+    }
+    padded.push(Boolean::constant(true));
+    assert!(padded.len() % rate == 0);
+
+    let mut state = vec![UInt64::constant(0); 25];
+    for (i, block) in padded.chunks(rate).enumerate() {
+        let cs = &mut cs.namespace(|| format!("block {}", i));
+
+        for (j, lane) in block.chunks(64).enumerate() {
+            state[j] = state[j].xor(
+                cs.namespace(|| format!("absorb lane {}", j)),
+                &UInt64::from_bits(lane),
+            )?;
+        }
+
+        state = keccak_f1600(cs.namespace(|| "permutation"), &state)?;
+    }
+
+    Ok(state
+        .into_iter()
+        .flat_map(|lane| lane.into_bits())
+        .take(output_len)
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::gadgets::multipack::bytes_to_bits_le;
+    use crate::gadgets::test::{alloc_bits, bit_values, TestConstraintSystem};
+    use bls12_381::Scalar;
+    use hex_literal::hex;
+    use rand_core::{RngCore, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+
+    /// A native implementation of Keccak-f[1600] to test against.
+    fn native_keccak_f1600(a: &mut [u64; 25]) {
+        for rc in ROUND_CONSTANTS {
+            let c: Vec<u64> = (0..5)
+                .map(|x| (0..5).fold(0, |c, y| c ^ a[x + 5 * y]))
+                .collect();
+            for x in 0..5 {
+                let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+                for y in 0..5 {
+                    a[x + 5 * y] ^= d;
+                }
+            }
+
+            let mut b = [0; 25];
+            for x in 0..5 {
+                for y in 0..5 {
+                    b[y + 5 * ((2 * x + 3 * y) % 5)] =
+                        a[x + 5 * y].rotate_left(ROTATION_OFFSETS[x + 5 * y] as u32);
+                }
+            }
+
+            for x in 0..5 {
+                for y in 0..5 {
+                    a[x + 5 * y] =
+                        b[x + 5 * y] ^ (!b[(x + 1) % 5 + 5 * y] & b[(x + 2) % 5 + 5 * y]);
+                }
+            }
+
+            a[0] ^= rc;
+        }
+    }
+
+    #[test]
+    fn test_permutation() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        // The first lane of the permutation of the zero state.
+        let mut zero = [0; 25];
+        native_keccak_f1600(&mut zero);
+        assert_eq!(zero[0], 0xf1258f7940e1dde7);
+
+        let mut cs = TestConstraintSystem::<Scalar>::new();
+        let mut native = [0; 25];
+        let state = (0..25)
+            .map(|i| {
+                native[i] = rng.next_u64();
+                UInt64::alloc(cs.namespace(|| format!("lane {}", i)), Some(native[i])).unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        let out = keccak_f1600(cs.namespace(|| "keccak"), &state).unwrap();
+
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_constraints() - 1600, 24 * 6400);
+
+        native_keccak_f1600(&mut native);
+        for (lane, expected) in out.iter().zip(native.iter()) {
+            assert_eq!(lane.get_value(), Some(*expected));
+        }
+    }
+
+    #[test]
+    fn test_against_vectors() {
+        let vectors: [(&[u8], [u8; 32], [u8; 32]); 2] = [
+            (
+                b"",
+                hex!("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"),
+                hex!("a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a"),
+            ),
+            (
+                b"abc",
+                hex!("4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"),
+                hex!("3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532"),
+            ),
+        ];
+
+        for (data, keccak, sha3) in vectors {
+            let mut cs = TestConstraintSystem::<Scalar>::new();
+            let input_bits = alloc_bits(cs.namespace(|| "input"), &bytes_to_bits_le(data));
+
+            let k = keccak256(cs.namespace(|| "keccak256"), &input_bits).unwrap();
+            let s = sha3_256(cs.namespace(|| "sha3_256"), &input_bits).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(bit_values(&k), bytes_to_bits_le(&keccak));
+            assert_eq!(bit_values(&s), bytes_to_bits_le(&sha3));
+        }
+    }
+
+    #[test]
+    fn test_multiple_blocks() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        // Lengths on either side of the padding spilling into another block.
+        for input_len in [135, 136, 200, 271, 272] {
+            let data: Vec<u8> = (0..input_len).map(|_| rng.next_u32() as u8).collect();
+
+            // Hash the data natively with the Keccak-256 padding.
+            let mut padded = data.clone();
+            padded.push(0x01);
+            while padded.len() % 136 != 0 {
+                padded.push(0);
+            }
+            *padded.last_mut().unwrap() |= 0x80;
+            let mut state = [0u64; 25];
+            for block in padded.chunks(136) {
+                for (lane, bytes) in state.iter_mut().zip(block.chunks(8)) {
+                    *lane ^= u64::from_le_bytes(bytes.try_into().unwrap());
+                }
+                native_keccak_f1600(&mut state);
+            }
+            let expected: Vec<u8> = state[..4].iter().flat_map(|l| l.to_le_bytes()).collect();
+
+            let mut cs = TestConstraintSystem::<Scalar>::new();
+            let input_bits = alloc_bits(cs.namespace(|| "input"), &bytes_to_bits_le(&data));
+            let k = keccak256(cs.namespace(|| "keccak256"), &input_bits).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(bit_values(&k), bytes_to_bits_le(&expected));
+        }
+    }
+}
@@ -0,0 +1,364 @@
+//! A constraint system that measures the cost of a circuit.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::ops::{Add, AddAssign};
+
+use ff::PrimeField;
+
+use crate::lc::proc_lc;
+use crate::{ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
+
+/// The costs attributed to a namespace.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// The number of constraints enforced.
+    pub constraints: usize,
+    /// The number of auxiliary variables allocated.
+    pub aux: usize,
+    /// The number of public inputs allocated.
+    pub inputs: usize,
+    /// The number of nonzero terms across the `A`, `B` and `C` linear
+    /// combinations of each constraint, after merging repeated variables.
+    pub lc_terms: usize,
+}
+
+impl Add for Metrics {
+    type Output = Metrics;
+
+    fn add(mut self, other: Metrics) -> Metrics {
+        self += other;
+        self
+    }
+}
+
+impl AddAssign for Metrics {
+    fn add_assign(&mut self, other: Metrics) {
+        self.constraints += other.constraints;
+        self.aux += other.aux;
+        self.inputs += other.inputs;
+        self.lc_terms += other.lc_terms;
+    }
+}
+
+/// The metrics of a namespace and its children.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NamespaceMetrics {
+    /// The name of this namespace, which is empty for the root.
+    pub name: String,
+    /// Costs incurred directly in this namespace, excluding its children.
+    pub own: Metrics,
+    /// Child namespaces, in the order they were first entered.
+    pub children: Vec<NamespaceMetrics>,
+    /// Indices into `children` by name.
+    index: HashMap<String, usize>,
+}
+
+impl NamespaceMetrics {
+    fn new(name: String) -> Self {
+        NamespaceMetrics {
+            name,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the costs of this namespace including all of its children.
+    pub fn total(&self) -> Metrics {
+        self.children
+            .iter()
+            .fold(self.own, |acc, child| acc + child.total())
+    }
+
+    /// Looks up a descendant namespace by its `/`-separated path relative to
+    /// this one.
+    pub fn get(&self, path: &str) -> Option<&NamespaceMetrics> {
+        path.split('/').try_fold(self, |ns, name| {
+            ns.index.get(name).map(|&i| &ns.children[i])
+        })
+    }
+
+    fn child_index(&mut self, name: String) -> usize {
+        match self.index.get(&name) {
+            Some(&i) => i,
+            None => {
+                let i = self.children.len();
+                self.index.insert(name.clone(), i);
+                self.children.push(NamespaceMetrics::new(name));
+                i
+            }
+        }
+    }
+
+    fn pretty_print(&self, s: &mut String, depth: usize) {
+        let total = self.total();
+        writeln!(
+            s,
+            "{:indent$}{}: {} constraints, {} aux, {} inputs, {} terms",
+            "",
+            if depth == 0 { "<root>" } else { &self.name },
+            total.constraints,
+            total.aux,
+            total.inputs,
+            total.lc_terms,
+            indent = depth * 2,
+        )
+        .unwrap();
+
+        for child in &self.children {
+            child.pretty_print(s, depth + 1);
+        }
+    }
+}
+
+/// Constraint system that counts the constraints, variables and linear
+/// combination terms of a circuit, broken down by namespace.
+///
+/// Witness values are never computed, so circuits can be measured without an
+/// assignment. Namespaces that are entered more than once under the same
+/// parent are merged.
+pub struct MetricConstraintSystem<Scalar: PrimeField> {
+    root: NamespaceMetrics,
+    // Indices into `children` from the root to the current namespace.
+    current_namespace: Vec<usize>,
+    num_inputs: usize,
+    num_aux: usize,
+    _marker: std::marker::PhantomData<Scalar>,
+}
+
+impl<Scalar: PrimeField> Default for MetricConstraintSystem<Scalar> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Scalar: PrimeField> MetricConstraintSystem<Scalar> {
+    pub fn new() -> MetricConstraintSystem<Scalar> {
+        let mut root = NamespaceMetrics::default();
+        // The implicit `ONE` input.
+        root.own.inputs = 1;
+
+        MetricConstraintSystem {
+            root,
+            current_namespace: vec![],
+            num_inputs: 1,
+            num_aux: 0,
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    /// Returns the metrics tree. The root's own inputs include the implicit
+    /// `ONE` input.
+    pub fn metrics(&self) -> &NamespaceMetrics {
+        &self.root
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        self.root.total().constraints
+    }
+
+    pub fn num_inputs(&self) -> usize {
+        self.num_inputs
+    }
+
+    pub fn num_aux(&self) -> usize {
+        self.num_aux
+    }
+
+    /// Renders the metrics tree, with each namespace showing its totals.
+    pub fn pretty_print(&self) -> String {
+        let mut s = String::new();
+        self.root.pretty_print(&mut s, 0);
+
+        s
+    }
+
+    fn current(&mut self) -> &mut NamespaceMetrics {
+        self.current_namespace
+            .iter()
+            .fold(&mut self.root, |ns, &i| &mut ns.children[i])
+    }
+}
+
+impl<Scalar: PrimeField> ConstraintSystem<Scalar> for MetricConstraintSystem<Scalar> {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(&mut self, _: A, _: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<Scalar, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let index = self.num_aux;
+        self.num_aux += 1;
+        self.current().own.aux += 1;
+
+        Ok(Variable::new_unchecked(Index::Aux(index)))
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, _: A, _: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<Scalar, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let index = self.num_inputs;
+        self.num_inputs += 1;
+        self.current().own.inputs += 1;
+
+        Ok(Variable::new_unchecked(Index::Input(index)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, a: LA, b: LB, c: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LB: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LC: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+    {
+        let terms = proc_lc(a(LinearCombination::zero()).as_ref()).len()
+            + proc_lc(b(LinearCombination::zero()).as_ref()).len()
+            + proc_lc(c(LinearCombination::zero()).as_ref()).len();
+
+        let current = &mut self.current().own;
+        current.constraints += 1;
+        current.lc_terms += terms;
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        let index = self.current().child_index(name_fn().into());
+        self.current_namespace.push(index);
+    }
+
+    fn pop_namespace(&mut self) {
+        assert!(self.current_namespace.pop().is_some());
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::boolean::{AllocatedBit, Boolean};
+    use crate::gadgets::num::AllocatedNum;
+    use crate::gadgets::sha256::sha256;
+    use crate::gadgets::test::TestConstraintSystem;
+
+    use bls12_381::Scalar;
+
+    fn synthesize<CS: ConstraintSystem<Scalar>>(cs: &mut CS, x: Option<u64>) {
+        let bits = (0..64)
+            .map(|i| {
+                AllocatedBit::alloc(
+                    cs.namespace(|| format!("bit {}", i)),
+                    x.map(|x| (x >> i) & 1 == 1),
+                )
+                .map(Boolean::from)
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+        sha256(cs.namespace(|| "sha256"), &bits).unwrap();
+
+        let x = AllocatedNum::alloc(cs.namespace(|| "x"), || {
+            x.map(Scalar::from).ok_or(SynthesisError::AssignmentMissing)
+        })
+        .unwrap();
+        let mut cs = cs.namespace(|| "powers");
+        let x2 = x.square(cs.namespace(|| "x^2")).unwrap();
+        x2.mul(cs.namespace(|| "x^3"), &x).unwrap();
+        x2.inputize(cs.namespace(|| "inputize")).unwrap();
+    }
+
+    #[test]
+    fn metrics_tree() {
+        let mut cs = MetricConstraintSystem::<Scalar>::new();
+        synthesize(&mut cs, None);
+
+        let root = cs.metrics();
+        let total = root.total();
+        assert_eq!(total.constraints, cs.num_constraints());
+        assert_eq!(total.inputs, cs.num_inputs());
+        assert_eq!(total.aux, cs.num_aux());
+        assert_eq!(root.children.len(), 64 + 3);
+        assert_eq!(root.children[0].name, "bit 0");
+        assert_eq!(
+            root.get("bit 0").unwrap().total(),
+            Metrics {
+                constraints: 1,
+                aux: 1,
+                inputs: 0,
+                lc_terms: 3,
+            }
+        );
+
+        let powers = root.get("powers").unwrap();
+        assert_eq!(powers.own, Metrics::default());
+        assert_eq!(powers.children.len(), 3);
+        assert_eq!(
+            powers.total(),
+            Metrics {
+                constraints: 3,
+                aux: 2,
+                inputs: 1,
+                lc_terms: 9,
+            }
+        );
+        assert_eq!(root.get("powers/inputize").unwrap().own.inputs, 1);
+        assert!(root.get("powers/x^4").is_none());
+
+        let s = cs.pretty_print();
+        assert!(s.starts_with("<root>: "));
+        assert!(s.contains(&format!(
+            "\n  sha256: {} constraints",
+            root.get("sha256").unwrap().total().constraints
+        )));
+        assert!(s.contains("\n    x^3: 1 constraints, 1 aux, 0 inputs, 3 terms\n"));
+    }
+
+    #[test]
+    fn reentered_namespaces_are_merged() {
+        let mut cs = MetricConstraintSystem::<Scalar>::new();
+        for i in 0..2 {
+            let mut ns = cs.namespace(|| "repeated");
+            let a = ns.alloc(|| format!("a {}", i), || unreachable!()).unwrap();
+            ns.enforce(
+                || "a = 0",
+                |lc| lc + a + a,
+                |lc| lc + MetricConstraintSystem::<Scalar>::one(),
+                |lc| lc,
+            );
+        }
+
+        let root = cs.metrics();
+        assert_eq!(root.children.len(), 1);
+        assert_eq!(
+            root.get("repeated").unwrap().own,
+            Metrics {
+                constraints: 2,
+                aux: 2,
+                inputs: 0,
+                lc_terms: 4,
+            }
+        );
+    }
+
+    #[test]
+    fn matches_test_constraint_system() {
+        let mut metrics = MetricConstraintSystem::<Scalar>::new();
+        synthesize(&mut metrics, None);
+
+        let mut cs = TestConstraintSystem::<Scalar>::new();
+        synthesize(&mut cs, Some(0x0123_4567_89ab_cdef));
+        assert!(cs.is_satisfied());
+
+        assert_eq!(metrics.num_constraints(), cs.num_constraints());
+        assert_eq!(metrics.num_inputs(), cs.num_inputs());
+    }
+}
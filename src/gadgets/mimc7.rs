@@ -45,7 +45,7 @@ pub fn mimc7_cs<S: PrimeField, CS: ConstraintSystem<S>>(
     // currerntly unused
     mut xr_value: Option<S>,
     round_constants: &[S],
-) -> Option<S> {
+) -> (Variable, Option<S>) {
     assert_eq!(round_constants.len(), MIMC7_ROUNDS);
 
     // let mut xl_value = self.xl;
@@ -203,5 +203,5 @@ pub fn mimc7_cs<S: PrimeField, CS: ConstraintSystem<S>>(
         |lc| lc + output,
     );
 
-    output_value
+    (output, output_value)
 }
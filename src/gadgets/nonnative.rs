@@ -0,0 +1,1265 @@
+//! Gadgets for arithmetic modulo a prime other than the scalar field, such as
+//! the base and scalar fields of secp256k1 or Curve25519.
+//!
+//! An element is a sequence of limbs `x_i` representing the integer
+//! `x = Σ x_i 2^(b·i)` for the limb width `b`. Freshly allocated elements have
+//! limbs that are [`AllocatedNum`]s range checked to `b` bits. Adding and
+//! subtracting elements works on the limbs directly, without creating any
+//! constraints, and only tracks how large each limb can grow.
+//!
+//! Multiplication and reduction witness a result `r` and a quotient `q`, and
+//! check the integer identity `x = q·p + r` limb by limb. The carries between
+//! limbs are range checked by bit decomposition, and the equations for each
+//! limb are packed into as few constraints as possible with [`MultiEq`].
+
+use std::cmp::Ordering;
+
+use ff::{PrimeField, PrimeFieldBits};
+
+use super::boolean::Boolean;
+use super::multieq::MultiEq;
+use super::num::{AllocatedNum, Num};
+use crate::{ConstraintSystem, LinearCombination, SynthesisError, Variable};
+
+/// An arbitrary precision natural number, used for witnesses and bounds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Nat(Vec<u64>);
+
+impl Nat {
+    fn zero() -> Self {
+        Nat(vec![])
+    }
+
+    fn from_u64(value: u64) -> Self {
+        Nat(vec![value]).normalized()
+    }
+
+    fn from_bytes_le(bytes: &[u8]) -> Self {
+        Nat(bytes
+            .chunks(8)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .rev()
+                    .fold(0u64, |acc, byte| (acc << 8) | *byte as u64)
+            })
+            .collect())
+        .normalized()
+    }
+
+    fn to_bytes_le(&self, len: usize) -> Vec<u8> {
+        assert!(self.bits() <= len * 8);
+        (0..len)
+            .map(|i| {
+                self.0
+                    .get(i / 8)
+                    .map_or(0, |word| (word >> (8 * (i % 8))) as u8)
+            })
+            .collect()
+    }
+
+    /// Returns `2^n`.
+    fn power_of_two(n: usize) -> Self {
+        Nat::from_u64(1).shl(n)
+    }
+
+    fn normalized(mut self) -> Self {
+        while self.0.last() == Some(&0) {
+            self.0.pop();
+        }
+        self
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn bits(&self) -> usize {
+        match self.0.last() {
+            Some(last) => 64 * self.0.len() - last.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    fn bit(&self, i: usize) -> bool {
+        self.0
+            .get(i / 64)
+            .map_or(false, |word| (word >> (i % 64)) & 1 == 1)
+    }
+
+    fn add(&self, other: &Nat) -> Nat {
+        let mut words = Vec::with_capacity(self.0.len().max(other.0.len()) + 1);
+        let mut carry = 0;
+        for i in 0..self.0.len().max(other.0.len()) {
+            let sum = *self.0.get(i).unwrap_or(&0) as u128
+                + *other.0.get(i).unwrap_or(&0) as u128
+                + carry;
+            words.push(sum as u64);
+            carry = sum >> 64;
+        }
+        words.push(carry as u64);
+        Nat(words).normalized()
+    }
+
+    fn checked_sub(&self, other: &Nat) -> Option<Nat> {
+        if *self < *other {
+            return None;
+        }
+
+        let mut words = Vec::with_capacity(self.0.len());
+        let mut borrow = false;
+        for (i, word) in self.0.iter().enumerate() {
+            let (diff, b1) = word.overflowing_sub(*other.0.get(i).unwrap_or(&0));
+            let (diff, b2) = diff.overflowing_sub(borrow as u64);
+            words.push(diff);
+            borrow = b1 || b2;
+        }
+        Some(Nat(words).normalized())
+    }
+
+    fn sub(&self, other: &Nat) -> Nat {
+        self.checked_sub(other).expect("subtraction underflow")
+    }
+
+    fn mul(&self, other: &Nat) -> Nat {
+        let mut words = vec![0u64; self.0.len() + other.0.len()];
+        for (i, a) in self.0.iter().enumerate() {
+            let mut carry = 0u128;
+            for (j, b) in other.0.iter().enumerate() {
+                let t = *a as u128 * *b as u128 + words[i + j] as u128 + carry;
+                words[i + j] = t as u64;
+                carry = t >> 64;
+            }
+            words[i + other.0.len()] = carry as u64;
+        }
+        Nat(words).normalized()
+    }
+
+    fn shl(&self, n: usize) -> Nat {
+        if self.is_zero() {
+            return Nat::zero();
+        }
+        let (words, bits) = (n / 64, n % 64);
+        let mut result = vec![0u64; words];
+        let mut carry = 0;
+        for word in &self.0 {
+            result.push((word << bits) | carry);
+            carry = if bits == 0 { 0 } else { word >> (64 - bits) };
+        }
+        result.push(carry);
+        Nat(result).normalized()
+    }
+
+    fn shr(&self, n: usize) -> Nat {
+        let (words, bits) = (n / 64, n % 64);
+        let result = (words..self.0.len())
+            .map(|i| {
+                let high = match self.0.get(i + 1) {
+                    Some(next) if bits != 0 => next << (64 - bits),
+                    _ => 0,
+                };
+                (self.0[i] >> bits) | high
+            })
+            .collect();
+        Nat(result).normalized()
+    }
+
+    /// Returns `self mod 2^n`.
+    fn low_bits(&self, n: usize) -> Nat {
+        let mut words: Vec<u64> = self.0.iter().take((n + 63) / 64).cloned().collect();
+        if n % 64 != 0 {
+            if let Some(last) = words.get_mut(n / 64) {
+                *last &= (1 << (n % 64)) - 1;
+            }
+        }
+        Nat(words).normalized()
+    }
+
+    /// Returns the quotient and remainder of dividing by `divisor`.
+    fn div_rem(&self, divisor: &Nat) -> (Nat, Nat) {
+        assert!(!divisor.is_zero());
+
+        let mut quotient = vec![0u64; self.0.len()];
+        let mut remainder = Nat::zero();
+        for i in (0..self.bits()).rev() {
+            remainder = remainder.shl(1);
+            if self.bit(i) {
+                remainder = remainder.add(&Nat::from_u64(1));
+            }
+            if remainder >= *divisor {
+                remainder = remainder.sub(divisor);
+                quotient[i / 64] |= 1 << (i % 64);
+            }
+        }
+        (Nat(quotient).normalized(), remainder)
+    }
+
+    fn rem(&self, modulus: &Nat) -> Nat {
+        self.div_rem(modulus).1
+    }
+
+    /// Returns the inverse of `self` modulo the odd `modulus`, or `None` if
+    /// there is none.
+    fn inverse_mod(&self, modulus: &Nat) -> Option<Nat> {
+        let one = Nat::from_u64(1);
+        let halve = |x: Nat| {
+            if x.bit(0) {
+                x.add(modulus).shr(1)
+            } else {
+                x.shr(1)
+            }
+        };
+        let sub_mod = |x: &Nat, y: &Nat| match x.checked_sub(y) {
+            Some(d) => d,
+            None => x.add(modulus).sub(y),
+        };
+
+        // Binary extended Euclidean algorithm, maintaining
+        // u = x1 * self and v = x2 * self modulo the modulus.
+        let mut u = self.rem(modulus);
+        let mut v = modulus.clone();
+        let mut x1 = one.clone();
+        let mut x2 = Nat::zero();
+        if u.is_zero() {
+            return None;
+        }
+        while u != one && v != one {
+            while !u.bit(0) {
+                u = u.shr(1);
+                x1 = halve(x1);
+            }
+            while !v.bit(0) {
+                v = v.shr(1);
+                x2 = halve(x2);
+            }
+            if u >= v {
+                u = u.sub(&v);
+                x1 = sub_mod(&x1, &x2);
+            } else {
+                v = v.sub(&u);
+                x2 = sub_mod(&x2, &x1);
+            }
+            if u.is_zero() {
+                // The modulus is not prime, and shares a factor with self.
+                return None;
+            }
+        }
+        Some(if u == one { x1 } else { x2 })
+    }
+
+    fn to_scalar<Scalar: PrimeField>(&self) -> Scalar {
+        assert!(self.bits() <= Scalar::CAPACITY as usize);
+        let shift = Scalar::from(2).pow_vartime([64]);
+        self.0.iter().rev().fold(Scalar::zero(), |acc, word| {
+            acc * shift + Scalar::from(*word)
+        })
+    }
+}
+
+impl PartialOrd for Nat {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Nat {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0
+            .len()
+            .cmp(&other.0.len())
+            .then_with(|| self.0.iter().rev().cmp(other.0.iter().rev()))
+    }
+}
+
+/// A limb of a non-native field element, with an upper bound on its value.
+#[derive(Clone)]
+struct Limb<Scalar: PrimeField> {
+    num: Num<Scalar>,
+    value: Option<Nat>,
+    max: Nat,
+    /// The little-endian bits of an allocated limb, from its range check.
+    bits: Option<Vec<Boolean>>,
+}
+
+impl<Scalar: PrimeField> Limb<Scalar> {
+    fn zero() -> Self {
+        Limb {
+            num: Num::zero(),
+            value: Some(Nat::zero()),
+            max: Nat::zero(),
+            bits: None,
+        }
+    }
+
+    fn constant(one: Variable, value: Nat) -> Self {
+        Limb {
+            num: Num::constant(one, value.to_scalar()),
+            value: Some(value.clone()),
+            max: value,
+            bits: None,
+        }
+    }
+
+    /// Allocates a limb of `num_bits` bits.
+    fn alloc<CS>(mut cs: CS, value: Option<Nat>, num_bits: usize) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeFieldBits,
+        CS: ConstraintSystem<Scalar>,
+    {
+        let num = AllocatedNum::alloc(cs.namespace(|| "limb"), || {
+            value
+                .as_ref()
+                .map(Nat::to_scalar)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let bits = num.to_bits_le_in_range(cs.namespace(|| "range check"), num_bits)?;
+
+        Ok(Limb {
+            num: num.into(),
+            value,
+            max: Nat::power_of_two(num_bits).sub(&Nat::from_u64(1)),
+            bits: Some(bits),
+        })
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        Limb {
+            num: self.num.clone() + &other.num,
+            value: zip_with(&self.value, &other.value, Nat::add),
+            max: self.max.add(&other.max),
+            bits: None,
+        }
+    }
+
+    /// Multiplies this limb by a constant.
+    fn scale(&self, coeff: &Nat) -> Self {
+        Limb {
+            num: self.num.clone() * coeff.to_scalar::<Scalar>(),
+            value: self.value.as_ref().map(|v| v.mul(coeff)),
+            max: self.max.mul(coeff),
+            bits: None,
+        }
+    }
+
+    /// Multiplies this limb by `2^shift`.
+    fn shl(&self, shift: usize) -> Self {
+        self.scale(&Nat::power_of_two(shift))
+    }
+
+    fn lc(&self) -> LinearCombination<Scalar> {
+        self.num.lc(Scalar::one())
+    }
+}
+
+fn zip_with<F: FnOnce(&Nat, &Nat) -> Nat>(a: &Option<Nat>, b: &Option<Nat>, f: F) -> Option<Nat> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(f(a, b)),
+        _ => None,
+    }
+}
+
+/// An element of a [`NonNativeField`].
+///
+/// The limbs of an element are not necessarily reduced: the integer it
+/// represents may be any value congruent to the element, and is only bounded
+/// by the bounds on its limbs.
+#[derive(Clone)]
+pub struct NonNativeElement<Scalar: PrimeField> {
+    limbs: Vec<Limb<Scalar>>,
+}
+
+impl<Scalar: PrimeField> NonNativeElement<Scalar> {
+    /// Returns the number of limbs of this element.
+    pub fn num_limbs(&self) -> usize {
+        self.limbs.len()
+    }
+}
+
+/// A prime field emulated in the scalar field of a constraint system.
+#[derive(Clone, Debug)]
+pub struct NonNativeField {
+    modulus: Nat,
+    limb_bits: usize,
+    num_limbs: usize,
+}
+
+impl NonNativeField {
+    /// Describes arithmetic modulo the odd prime `modulus`, given as
+    /// little-endian bytes, with elements split into limbs of `limb_bits`
+    /// bits.
+    pub fn new(modulus: &[u8], limb_bits: usize) -> Self {
+        assert!((1..=64).contains(&limb_bits));
+        let modulus = Nat::from_bytes_le(modulus);
+        assert!(
+            modulus.bits() > 1 && modulus.bit(0),
+            "modulus must be an odd prime"
+        );
+
+        NonNativeField {
+            num_limbs: (modulus.bits() + limb_bits - 1) / limb_bits,
+            modulus,
+            limb_bits,
+        }
+    }
+
+    /// Returns the modulus as little-endian bytes.
+    pub fn modulus(&self) -> Vec<u8> {
+        self.modulus.to_bytes_le(self.num_bytes())
+    }
+
+    /// Returns the number of bits of each limb of a reduced element.
+    pub fn limb_bits(&self) -> usize {
+        self.limb_bits
+    }
+
+    /// Returns the number of limbs of a reduced element.
+    pub fn num_limbs(&self) -> usize {
+        self.num_limbs
+    }
+
+    fn num_bytes(&self) -> usize {
+        (self.modulus.bits() + 7) / 8
+    }
+
+    /// Returns the value of `a` modulo the modulus, as little-endian bytes of
+    /// the same length as the modulus.
+    pub fn get_value<Scalar: PrimeField>(&self, a: &NonNativeElement<Scalar>) -> Option<Vec<u8>> {
+        self.value(a)
+            .map(|v| v.rem(&self.modulus).to_bytes_le(self.num_bytes()))
+    }
+
+    /// Returns the integer represented by `a`.
+    fn value<Scalar: PrimeField>(&self, a: &NonNativeElement<Scalar>) -> Option<Nat> {
+        a.limbs
+            .iter()
+            .enumerate()
+            .try_fold(Nat::zero(), |acc, (i, limb)| {
+                limb.value
+                    .as_ref()
+                    .map(|v| acc.add(&v.shl(i * self.limb_bits)))
+            })
+    }
+
+    /// Returns an upper bound on the integer represented by `a`.
+    fn max<Scalar: PrimeField>(&self, a: &NonNativeElement<Scalar>) -> Nat {
+        a.limbs
+            .iter()
+            .enumerate()
+            .fold(Nat::zero(), |acc, (i, limb)| {
+                acc.add(&limb.max.shl(i * self.limb_bits))
+            })
+    }
+
+    /// Splits `value` into limbs of `limb_bits` bits.
+    fn split(&self, value: &Nat, num_limbs: usize) -> Vec<Nat> {
+        (0..num_limbs)
+            .map(|i| value.shr(i * self.limb_bits).low_bits(self.limb_bits))
+            .collect()
+    }
+
+    /// Allocates `num_limbs` limbs holding the `num_bits`-bit `value`.
+    fn alloc_limbs<Scalar, CS>(
+        &self,
+        mut cs: CS,
+        value: Option<&Nat>,
+        num_bits: usize,
+    ) -> Result<NonNativeElement<Scalar>, SynthesisError>
+    where
+        Scalar: PrimeFieldBits,
+        CS: ConstraintSystem<Scalar>,
+    {
+        let num_limbs = (num_bits + self.limb_bits - 1) / self.limb_bits;
+        let values = value.map(|v| self.split(v, num_limbs));
+        let limbs = (0..num_limbs)
+            .map(|i| {
+                Limb::alloc(
+                    cs.namespace(|| format!("limb {}", i)),
+                    values.as_ref().map(|v| v[i].clone()),
+                    self.limb_bits.min(num_bits - i * self.limb_bits),
+                )
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(NonNativeElement { limbs })
+    }
+
+    /// Allocates an element with the given value, as little-endian bytes.
+    ///
+    /// The value is reduced modulo the modulus outside the circuit, but the
+    /// element is only constrained to have range-checked limbs; use
+    /// [`NonNativeField::enforce_canonical`] to also constrain it to be less
+    /// than the modulus.
+    pub fn alloc<Scalar, CS>(
+        &self,
+        cs: CS,
+        value: Option<&[u8]>,
+    ) -> Result<NonNativeElement<Scalar>, SynthesisError>
+    where
+        Scalar: PrimeFieldBits,
+        CS: ConstraintSystem<Scalar>,
+    {
+        let value = value.map(|v| Nat::from_bytes_le(v).rem(&self.modulus));
+        self.alloc_limbs(cs, value.as_ref(), self.num_limbs * self.limb_bits)
+    }
+
+    /// Returns a constant element, where `one` is the variable
+    /// [`ConstraintSystem::one`].
+    pub fn constant<Scalar: PrimeField>(
+        &self,
+        one: Variable,
+        value: &[u8],
+    ) -> NonNativeElement<Scalar> {
+        let value = Nat::from_bytes_le(value).rem(&self.modulus);
+        NonNativeElement {
+            limbs: self
+                .split(&value, self.num_limbs)
+                .into_iter()
+                .map(|limb| Limb::constant(one, limb))
+                .collect(),
+        }
+    }
+
+    /// Returns the element whose value has the given little-endian bits,
+    /// where `one` is the variable [`ConstraintSystem::one`]. This creates no
+    /// constraints.
+    pub fn from_bits_le<Scalar: PrimeField>(
+        &self,
+        one: Variable,
+        bits: &[Boolean],
+    ) -> NonNativeElement<Scalar> {
+        let limbs = bits
+            .chunks(self.limb_bits)
+            .map(|chunk| {
+                let mut num = Num::zero();
+                let mut coeff = Scalar::one();
+                let mut value = Some(Nat::zero());
+                for (i, bit) in chunk.iter().enumerate() {
+                    num = num.add_bool_with_coeff(one, bit, coeff);
+                    coeff = coeff.double();
+                    value = match (value, bit.get_value()) {
+                        (Some(v), Some(true)) => Some(v.add(&Nat::power_of_two(i))),
+                        (Some(v), Some(false)) => Some(v),
+                        _ => None,
+                    };
+                }
+                Limb {
+                    num,
+                    value,
+                    max: Nat::power_of_two(chunk.len()).sub(&Nat::from_u64(1)),
+                    bits: Some(chunk.to_vec()),
+                }
+            })
+            .collect();
+
+        NonNativeElement { limbs }
+    }
+
+    /// Returns `a` if `condition` is true, and `b` otherwise.
+    pub fn conditionally_select<Scalar, CS>(
+        &self,
+        mut cs: CS,
+        a: &NonNativeElement<Scalar>,
+        b: &NonNativeElement<Scalar>,
+        condition: &Boolean,
+    ) -> Result<NonNativeElement<Scalar>, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        let limbs = (0..a.limbs.len().max(b.limbs.len()))
+            .map(|i| {
+                let a = a.limbs.get(i).cloned().unwrap_or_else(Limb::zero);
+                let b = b.limbs.get(i).cloned().unwrap_or_else(Limb::zero);
+                let value = match condition.get_value() {
+                    Some(true) => a.value.clone(),
+                    Some(false) => b.value.clone(),
+                    None => None,
+                };
+                let num = AllocatedNum::alloc(cs.namespace(|| format!("limb {}", i)), || {
+                    value
+                        .as_ref()
+                        .map(Nat::to_scalar)
+                        .ok_or(SynthesisError::AssignmentMissing)
+                })?;
+
+                // (a - b) * condition = r - b
+                cs.enforce(
+                    || format!("selection {}", i),
+                    |_| a.lc() - &b.lc(),
+                    |_| condition.lc(CS::one(), Scalar::one()),
+                    |lc| lc + num.get_variable() - &b.lc(),
+                );
+
+                Ok(Limb {
+                    num: num.into(),
+                    value,
+                    max: a.max.max(b.max),
+                    bits: None,
+                })
+            })
+            .collect::<Result<_, SynthesisError>>()?;
+
+        Ok(NonNativeElement { limbs })
+    }
+
+    /// Returns `a + b`, without reducing it.
+    pub fn add<Scalar: PrimeField>(
+        &self,
+        a: &NonNativeElement<Scalar>,
+        b: &NonNativeElement<Scalar>,
+    ) -> NonNativeElement<Scalar> {
+        let limbs = (0..a.limbs.len().max(b.limbs.len()))
+            .map(|i| match (a.limbs.get(i), b.limbs.get(i)) {
+                (Some(a), Some(b)) => a.add(b),
+                (Some(limb), None) | (None, Some(limb)) => limb.clone(),
+                (None, None) => unreachable!(),
+            })
+            .collect();
+
+        NonNativeElement { limbs }
+    }
+
+    /// Returns `a - b`, without reducing it, where `one` is the variable
+    /// [`ConstraintSystem::one`].
+    ///
+    /// To keep every limb nonnegative, this adds a multiple of the modulus
+    /// whose limbs are at least as large as the bounds on the limbs of `b`.
+    pub fn sub<Scalar: PrimeField>(
+        &self,
+        one: Variable,
+        a: &NonNativeElement<Scalar>,
+        b: &NonNativeElement<Scalar>,
+    ) -> NonNativeElement<Scalar> {
+        // Find k·p = Σ c_i 2^(b·i) with c_i >= max(b_i): for the smallest
+        // k·p >= t = Σ max(b_i) 2^(b·i), the difference k·p - t is less than
+        // p and can be split into digits that are added to the max(b_i).
+        let num_limbs = b.limbs.len().max(self.num_limbs);
+        let t = NonNativeElement::<Scalar> {
+            limbs: b.limbs.clone(),
+        };
+        let t = self.max(&t);
+        let d = self.modulus.sub(&t.rem(&self.modulus)).rem(&self.modulus);
+        let digits = self.split(&d, num_limbs);
+
+        let limbs = (0..a.limbs.len().max(num_limbs))
+            .map(|i| {
+                let a = a.limbs.get(i).cloned().unwrap_or_else(Limb::zero);
+                let (c, b) = match b.limbs.get(i) {
+                    Some(b) => (b.max.add(&digits[i]), b.clone()),
+                    None => (
+                        digits.get(i).cloned().unwrap_or_else(Nat::zero),
+                        Limb::zero(),
+                    ),
+                };
+                Limb {
+                    num: a.num + Num::constant(one, c.to_scalar()) - b.num,
+                    value: match (a.value, b.value) {
+                        (Some(a), Some(b)) => Some(a.add(&c).sub(&b)),
+                        _ => None,
+                    },
+                    max: a.max.add(&c),
+                    bits: None,
+                }
+            })
+            .collect();
+
+        NonNativeElement { limbs }
+    }
+
+    /// Returns `a · b`, without reducing it.
+    ///
+    /// This allocates the coefficients of the product of the limb
+    /// polynomials, and checks it at as many points as there are
+    /// coefficients.
+    pub fn mul_unreduced<Scalar, CS>(
+        &self,
+        mut cs: CS,
+        a: &NonNativeElement<Scalar>,
+        b: &NonNativeElement<Scalar>,
+    ) -> Result<NonNativeElement<Scalar>, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        assert!(!a.limbs.is_empty() && !b.limbs.is_empty());
+
+        let num_limbs = a.limbs.len() + b.limbs.len() - 1;
+        let limbs = (0..num_limbs)
+            .map(|k| {
+                let terms = || {
+                    (0..a.limbs.len())
+                        .filter(move |i| k >= *i && k - i < b.limbs.len())
+                        .map(move |i| (&a.limbs[i], &b.limbs[k - i]))
+                };
+                let value = terms().try_fold(Nat::zero(), |acc, (a, b)| {
+                    zip_with(&a.value, &b.value, Nat::mul).map(|v| acc.add(&v))
+                });
+                let max = terms().fold(Nat::zero(), |acc, (a, b)| acc.add(&a.max.mul(&b.max)));
+                assert!(
+                    max.bits() < Scalar::CAPACITY as usize,
+                    "operands must be reduced before multiplying"
+                );
+
+                let num = AllocatedNum::alloc(cs.namespace(|| format!("coeff {}", k)), || {
+                    value
+                        .as_ref()
+                        .map(Nat::to_scalar)
+                        .ok_or(SynthesisError::AssignmentMissing)
+                })?;
+                Ok(Limb {
+                    num: num.into(),
+                    value,
+                    max,
+                    bits: None,
+                })
+            })
+            .collect::<Result<Vec<_>, SynthesisError>>()?;
+
+        // a(x) * b(x) = c(x) at x = 0, 1, ..., num_limbs - 1
+        for i in 0..num_limbs {
+            let x = Scalar::from(i as u64);
+            let eval = |limbs: &[Limb<Scalar>]| {
+                let mut coeff = Scalar::one();
+                let mut lc = LinearCombination::zero();
+                for limb in limbs {
+                    lc = lc + &limb.num.lc(coeff);
+                    coeff *= x;
+                }
+                lc
+            };
+            cs.enforce(
+                || format!("evaluation at {}", i),
+                |_| eval(&a.limbs),
+                |_| eval(&b.limbs),
+                |_| eval(&limbs),
+            );
+        }
+
+        Ok(NonNativeElement { limbs })
+    }
+
+    /// Returns `a · b`, reduced.
+    pub fn mul<Scalar, CS>(
+        &self,
+        mut cs: CS,
+        a: &NonNativeElement<Scalar>,
+        b: &NonNativeElement<Scalar>,
+    ) -> Result<NonNativeElement<Scalar>, SynthesisError>
+    where
+        Scalar: PrimeFieldBits,
+        CS: ConstraintSystem<Scalar>,
+    {
+        let product = self.mul_unreduced(cs.namespace(|| "product"), a, b)?;
+        self.reduce(cs.namespace(|| "reduce"), &product)
+    }
+
+    /// Returns an element congruent to `a` whose limbs are range checked to
+    /// `limb_bits` bits.
+    ///
+    /// The result is less than `2^(limb_bits · num_limbs)`, but not
+    /// necessarily less than the modulus.
+    pub fn reduce<Scalar, CS>(
+        &self,
+        mut cs: CS,
+        a: &NonNativeElement<Scalar>,
+    ) -> Result<NonNativeElement<Scalar>, SynthesisError>
+    where
+        Scalar: PrimeFieldBits,
+        CS: ConstraintSystem<Scalar>,
+    {
+        let value = self.value(a).map(|v| v.rem(&self.modulus));
+        let r = self.alloc_limbs(
+            cs.namespace(|| "result"),
+            value.as_ref(),
+            self.num_limbs * self.limb_bits,
+        )?;
+        self.enforce_congruent(cs, a, &r)?;
+
+        Ok(r)
+    }
+
+    /// Returns `a / b`, reduced.
+    ///
+    /// Returns [`SynthesisError::DivisionByZero`] if `b` is zero.
+    pub fn div<Scalar, CS>(
+        &self,
+        mut cs: CS,
+        a: &NonNativeElement<Scalar>,
+        b: &NonNativeElement<Scalar>,
+    ) -> Result<NonNativeElement<Scalar>, SynthesisError>
+    where
+        Scalar: PrimeFieldBits,
+        CS: ConstraintSystem<Scalar>,
+    {
+        let value = match (self.value(a), self.value(b)) {
+            (Some(a), Some(b)) => {
+                let inverse = b
+                    .inverse_mod(&self.modulus)
+                    .ok_or(SynthesisError::DivisionByZero)?;
+                Some(a.mul(&inverse).rem(&self.modulus))
+            }
+            _ => None,
+        };
+        let r = self.alloc_limbs(
+            cs.namespace(|| "quotient"),
+            value.as_ref(),
+            self.num_limbs * self.limb_bits,
+        )?;
+
+        // b * r = a
+        let product = self.mul_unreduced(cs.namespace(|| "product"), b, &r)?;
+        self.enforce_equal(cs.namespace(|| "division"), &product, a)?;
+
+        Ok(r)
+    }
+
+    /// Enforces that `a` and `b` are congruent modulo the modulus.
+    pub fn enforce_equal<Scalar, CS>(
+        &self,
+        cs: CS,
+        a: &NonNativeElement<Scalar>,
+        b: &NonNativeElement<Scalar>,
+    ) -> Result<(), SynthesisError>
+    where
+        Scalar: PrimeFieldBits,
+        CS: ConstraintSystem<Scalar>,
+    {
+        let difference = self.sub(CS::one(), a, b);
+        self.enforce_congruent(cs, &difference, &NonNativeElement { limbs: vec![] })
+    }
+
+    /// Enforces that the integer represented by `a` is less than the modulus.
+    pub fn enforce_canonical<Scalar, CS>(
+        &self,
+        mut cs: CS,
+        a: &NonNativeElement<Scalar>,
+    ) -> Result<(), SynthesisError>
+    where
+        Scalar: PrimeFieldBits,
+        CS: ConstraintSystem<Scalar>,
+    {
+        // a + s = p - 1 for some nonnegative s.
+        let p_minus_one = self.modulus.sub(&Nat::from_u64(1));
+        let s = self
+            .value(a)
+            .map(|a| p_minus_one.checked_sub(&a).unwrap_or_else(Nat::zero));
+        let s = self.alloc_limbs(
+            cs.namespace(|| "difference"),
+            s.as_ref(),
+            self.modulus.bits(),
+        )?;
+        let constant = self.split(&p_minus_one, self.num_limbs);
+        let rhs: Vec<_> = constant
+            .into_iter()
+            .map(|limb| Limb::constant(CS::one(), limb))
+            .collect();
+
+        enforce_limbs_equal(
+            cs.namespace(|| "sum"),
+            &self.add(a, &s).limbs,
+            &rhs,
+            self.limb_bits,
+        )
+    }
+
+    /// Returns the little-endian bits of an integer congruent to `a` and less
+    /// than `2^(limb_bits · num_limbs)`.
+    pub fn to_bits_le<Scalar, CS>(
+        &self,
+        mut cs: CS,
+        a: &NonNativeElement<Scalar>,
+    ) -> Result<Vec<Boolean>, SynthesisError>
+    where
+        Scalar: PrimeFieldBits,
+        CS: ConstraintSystem<Scalar>,
+    {
+        // The limbs of a reduced element are allocated with `limb_bits` bits
+        // each, which their range checks have already decomposed them into.
+        let r = self.reduce(cs.namespace(|| "reduce"), a)?;
+
+        Ok(r.limbs
+            .into_iter()
+            .flat_map(|limb| limb.bits.expect("reduced limbs are allocated"))
+            .collect())
+    }
+
+    /// Enforces `a ≡ r` given that `a >= r`, by witnessing `q` with
+    /// `a = q·p + r`.
+    fn enforce_congruent<Scalar, CS>(
+        &self,
+        mut cs: CS,
+        a: &NonNativeElement<Scalar>,
+        r: &NonNativeElement<Scalar>,
+    ) -> Result<(), SynthesisError>
+    where
+        Scalar: PrimeFieldBits,
+        CS: ConstraintSystem<Scalar>,
+    {
+        let q_max = self.max(a).div_rem(&self.modulus).0;
+        let q = zip_with(&self.value(a), &self.value(r), |a, r| {
+            match a.checked_sub(r).map(|d| d.div_rem(&self.modulus)) {
+                Some((q, rem)) if rem.is_zero() => q,
+                // Not congruent, so no quotient will satisfy the constraints.
+                _ => Nat::zero(),
+            }
+        });
+        let q = if q_max.is_zero() {
+            NonNativeElement { limbs: vec![] }
+        } else {
+            self.alloc_limbs(cs.namespace(|| "quotient"), q.as_ref(), q_max.bits())?
+        };
+
+        // The limbs of q·p + r.
+        let p = self.split(&self.modulus, self.num_limbs);
+        let mut rhs: Vec<_> = (0..(q.limbs.len() + self.num_limbs - 1).max(r.limbs.len()))
+            .map(|k| r.limbs.get(k).cloned().unwrap_or_else(Limb::zero))
+            .collect();
+        for (i, q) in q.limbs.iter().enumerate() {
+            for (j, p) in p.iter().enumerate() {
+                rhs[i + j] = rhs[i + j].add(&q.scale(p));
+            }
+        }
+
+        enforce_limbs_equal(
+            cs.namespace(|| "congruence"),
+            &a.limbs,
+            &rhs,
+            self.limb_bits,
+        )
+    }
+}
+
+/// Enforces that two limb sequences represent the same integer.
+///
+/// Consecutive limbs are grouped so that each group fits comfortably in the
+/// scalar field, and the groups are then compared from the least significant
+/// upwards, with a range-checked carry from each group into the next.
+fn enforce_limbs_equal<Scalar, CS>(
+    cs: CS,
+    lhs: &[Limb<Scalar>],
+    rhs: &[Limb<Scalar>],
+    limb_bits: usize,
+) -> Result<(), SynthesisError>
+where
+    Scalar: PrimeFieldBits,
+    CS: ConstraintSystem<Scalar>,
+{
+    let len = lhs.len().max(rhs.len());
+    let group = |limbs: &[Limb<Scalar>], size: usize| -> Vec<Limb<Scalar>> {
+        (0..len)
+            .step_by(size)
+            .map(|start| {
+                (start..len.min(start + size))
+                    .filter_map(|i| limbs.get(i).map(|limb| limb.shl((i - start) * limb_bits)))
+                    .fold(Limb::zero(), |acc, limb| acc.add(&limb))
+            })
+            .collect()
+    };
+
+    // Each equation is L_k + c_{k-1} + C_k·2^w = R_k + c_k·2^w + C_{k-1},
+    // where the carry c_k is offset by its bound C_k so that it is
+    // nonnegative. Returns the carry bounds and the widths of the equations.
+    let plan = |lhs: &[Limb<Scalar>], rhs: &[Limb<Scalar>], width: usize| {
+        let mut carry_bounds = Vec::with_capacity(lhs.len());
+        let mut widths = Vec::with_capacity(lhs.len());
+        let mut carry_in = Nat::zero();
+        for (k, (l, r)) in lhs.iter().zip(rhs.iter()).enumerate() {
+            let carry = if k + 1 == lhs.len() {
+                Nat::zero()
+            } else {
+                l.max.clone().max(r.max.clone()).add(&carry_in).shr(width)
+            };
+            // The range check on an offset carry allows values up to the next
+            // power of two.
+            let range = |bound: &Nat| Nat::power_of_two(bound.shl(1).bits()).sub(&Nat::from_u64(1));
+            let left = l.max.add(&range(&carry_in)).add(&carry.shl(width));
+            let right = r.max.add(&range(&carry).shl(width)).add(&carry_in);
+            let bits = left.max(right).bits();
+            if bits >= Scalar::CAPACITY as usize {
+                return None;
+            }
+            widths.push(bits);
+            carry_bounds.push(carry.clone());
+            carry_in = carry;
+        }
+        Some((carry_bounds, widths))
+    };
+
+    let (size, lhs, rhs, carry_bounds, widths) = (1..=len)
+        .rev()
+        .filter(|size| size * limb_bits < Scalar::CAPACITY as usize)
+        .find_map(|size| {
+            let (lhs, rhs) = (group(lhs, size), group(rhs, size));
+            plan(&lhs, &rhs, size * limb_bits)
+                .map(|(carry_bounds, widths)| (size, lhs, rhs, carry_bounds, widths))
+        })
+        .expect("limbs are too large to compare; reduce the operands first");
+    let width = size * limb_bits;
+    let shift = Scalar::from(2).pow_vartime([width as u64]);
+
+    let mut cs = MultiEq::new(cs);
+    let mut carry_in: Option<(Limb<Scalar>, Nat)> = None;
+    for (k, (l, r)) in lhs.iter().zip(rhs.iter()).enumerate() {
+        let (carry_lc, carry_value) = match &carry_in {
+            Some((carry, _)) => (carry.lc(), carry.value.clone()),
+            None => (LinearCombination::zero(), Some(Nat::zero())),
+        };
+        let carry_in_bound = carry_in
+            .as_ref()
+            .map_or_else(Nat::zero, |(_, bound)| bound.clone());
+        let bound = &carry_bounds[k];
+
+        let mut lhs_lc = l.lc() + &carry_lc;
+        let mut rhs_lc = r.lc();
+        if !carry_in_bound.is_zero() {
+            rhs_lc = rhs_lc + (carry_in_bound.to_scalar::<Scalar>(), CS::one());
+        }
+
+        let carry = if bound.is_zero() {
+            None
+        } else {
+            let value = match (&l.value, &r.value, &carry_value) {
+                (Some(l), Some(r), Some(c)) => Some(
+                    l.add(c)
+                        .add(&bound.shl(width))
+                        .checked_sub(&r.add(&carry_in_bound))
+                        .map_or_else(Nat::zero, |d| d.shr(width)),
+                ),
+                _ => None,
+            };
+            let carry = Limb::alloc(
+                cs.namespace(|| format!("carry {}", k)),
+                value,
+                bound.shl(1).bits(),
+            )?;
+            lhs_lc = lhs_lc + (bound.to_scalar::<Scalar>() * shift, CS::one());
+            rhs_lc = rhs_lc + &carry.num.lc(shift);
+            Some((carry, bound.clone()))
+        };
+
+        cs.enforce_equal(widths[k], &lhs_lc, &rhs_lc);
+        carry_in = carry;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use bls12_381::Scalar;
+    use hex_literal::hex;
+    use rand_core::{RngCore, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+
+    use super::{Nat, NonNativeField};
+    use crate::gadgets::test::TestConstraintSystem;
+    use crate::ConstraintSystem;
+
+    // 2^256 - 2^32 - 977
+    const SECP256K1_P: [u8; 32] =
+        hex!("2ffcfffffeffffffffffffffffffffffffffffffffffffffffffffffffffffff");
+    // 2^255 - 19
+    const ED25519_P: [u8; 32] =
+        hex!("edffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f");
+
+    fn random_bytes(rng: &mut XorShiftRng) -> [u8; 32] {
+        let mut bytes = [0; 32];
+        rng.fill_bytes(&mut bytes);
+        bytes
+    }
+
+    #[test]
+    fn test_nat() {
+        let a = Nat::from_bytes_le(&hex!("0123456789abcdef0123456789abcdef0123"));
+        let b = Nat::from_bytes_le(&hex!("fedcba9876543210fe"));
+        let (q, r) = a.div_rem(&b);
+        assert!(r < b);
+        assert_eq!(q.mul(&b).add(&r), a);
+        assert_eq!(a.shl(77).shr(77), a);
+        assert_eq!(a.sub(&a), Nat::zero());
+        assert_eq!(b.checked_sub(&a), None);
+
+        let p = Nat::from_bytes_le(&SECP256K1_P);
+        let inverse = a.inverse_mod(&p).unwrap();
+        assert_eq!(a.mul(&inverse).rem(&p), Nat::from_u64(1));
+        assert_eq!(p.inverse_mod(&p), None);
+    }
+
+    #[test]
+    fn test_nonnative_known_values() {
+        let field = NonNativeField::new(&SECP256K1_P, 64);
+        assert_eq!(field.num_limbs(), 4);
+
+        let mut cs = TestConstraintSystem::<Scalar>::new();
+
+        // 2^255 * 2 = 2^256 - p = 2^32 + 977
+        let mut two_255 = [0; 32];
+        two_255[31] = 0x80;
+        let a = field.alloc(cs.namespace(|| "a"), Some(&two_255)).unwrap();
+        let two = field.constant(TestConstraintSystem::<Scalar>::one(), &[2]);
+        let c = field.mul(cs.namespace(|| "a times 2"), &a, &two).unwrap();
+        let before = cs.num_constraints();
+        field.mul(cs.namespace(|| "a squared"), &a, &a).unwrap();
+        assert_eq!(cs.num_constraints() - before, 735);
+        let mut expected = [0; 32];
+        expected[..5].copy_from_slice(&hex!("d103000001"));
+        assert_eq!(field.get_value(&c).unwrap(), expected);
+
+        // (p - 1)^2 = 1
+        let mut p_minus_one = SECP256K1_P;
+        p_minus_one[0] -= 1;
+        let b = field
+            .alloc(cs.namespace(|| "b"), Some(&p_minus_one))
+            .unwrap();
+        let d = field.mul(cs.namespace(|| "b squared"), &b, &b).unwrap();
+        let one = field.constant(TestConstraintSystem::<Scalar>::one(), &[1]);
+        field
+            .enforce_equal(cs.namespace(|| "d is one"), &d, &one)
+            .unwrap();
+        field
+            .enforce_canonical(cs.namespace(|| "b is canonical"), &b)
+            .unwrap();
+
+        assert!(cs.is_satisfied());
+
+        let field = NonNativeField::new(&ED25519_P, 51);
+        assert_eq!(field.num_limbs(), 5);
+
+        // 2^254 * 2 = 19
+        let mut cs = TestConstraintSystem::<Scalar>::new();
+        let mut two_254 = [0; 32];
+        two_254[31] = 0x40;
+        let a = field.alloc(cs.namespace(|| "a"), Some(&two_254)).unwrap();
+        let sum = field.add(&a, &a);
+        let c = field.reduce(cs.namespace(|| "reduce"), &sum).unwrap();
+        let mut expected = [0; 32];
+        expected[0] = 19;
+        assert_eq!(field.get_value(&c).unwrap(), expected);
+        assert!(cs.is_satisfied());
+    }
+
+    #[test]
+    fn test_nonnative_arithmetic() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x3d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        for (modulus, limb_bits) in [(SECP256K1_P, 64), (ED25519_P, 51), (SECP256K1_P, 52)] {
+            let field = NonNativeField::new(&modulus, limb_bits);
+            let p = Nat::from_bytes_le(&modulus);
+
+            for _ in 0..5 {
+                let a_bytes = random_bytes(&mut rng);
+                let b_bytes = random_bytes(&mut rng);
+                let a_nat = Nat::from_bytes_le(&a_bytes).rem(&p);
+                let b_nat = Nat::from_bytes_le(&b_bytes).rem(&p);
+
+                let mut cs = TestConstraintSystem::<Scalar>::new();
+                let one = TestConstraintSystem::<Scalar>::one();
+                let a = field.alloc(cs.namespace(|| "a"), Some(&a_bytes)).unwrap();
+                let b = field.alloc(cs.namespace(|| "b"), Some(&b_bytes)).unwrap();
+
+                let expect = |value: Nat| value.rem(&p).to_bytes_le(32);
+
+                let sum = field.add(&a, &b);
+                assert_eq!(field.get_value(&sum).unwrap(), expect(a_nat.add(&b_nat)));
+                let sum = field.reduce(cs.namespace(|| "sum"), &sum).unwrap();
+                assert_eq!(field.get_value(&sum).unwrap(), expect(a_nat.add(&b_nat)));
+
+                let difference = field.sub(one, &a, &b);
+                assert_eq!(
+                    field.get_value(&difference).unwrap(),
+                    expect(a_nat.add(&p).sub(&b_nat))
+                );
+
+                let product = field.mul(cs.namespace(|| "product"), &a, &b).unwrap();
+                assert_eq!(
+                    field.get_value(&product).unwrap(),
+                    expect(a_nat.mul(&b_nat))
+                );
+
+                // (a + b) * (a - b) = a^2 - b^2, without intermediate reductions.
+                let lhs = field
+                    .mul_unreduced(cs.namespace(|| "lhs"), &field.add(&a, &b), &difference)
+                    .unwrap();
+                let a_squared = field.mul_unreduced(cs.namespace(|| "a^2"), &a, &a).unwrap();
+                let b_squared = field.mul_unreduced(cs.namespace(|| "b^2"), &b, &b).unwrap();
+                let rhs = field.sub(one, &a_squared, &b_squared);
+                field
+                    .enforce_equal(cs.namespace(|| "difference of squares"), &lhs, &rhs)
+                    .unwrap();
+
+                // (a / b) * b = a
+                let quotient = field.div(cs.namespace(|| "quotient"), &a, &b).unwrap();
+                let a_again = field
+                    .mul(cs.namespace(|| "quotient times b"), &quotient, &b)
+                    .unwrap();
+                assert_eq!(field.get_value(&a_again).unwrap(), expect(a_nat.clone()));
+                field
+                    .enforce_equal(cs.namespace(|| "a equals a again"), &a, &a_again)
+                    .unwrap();
+
+                let bits = field.to_bits_le(cs.namespace(|| "bits"), &product).unwrap();
+                let bits_value = bits.iter().rev().fold(Nat::zero(), |acc, bit| {
+                    let acc = acc.shl(1);
+                    if bit.get_value().unwrap() {
+                        acc.add(&Nat::from_u64(1))
+                    } else {
+                        acc
+                    }
+                });
+                assert_eq!(bits_value.rem(&p), a_nat.mul(&b_nat).rem(&p));
+
+                assert!(cs.is_satisfied());
+            }
+        }
+    }
+
+    #[test]
+    fn test_nonnative_unsatisfied() {
+        let field = NonNativeField::new(&SECP256K1_P, 64);
+
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x3d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let a_bytes = random_bytes(&mut rng);
+        let b_bytes = random_bytes(&mut rng);
+
+        // a * b is not a + b.
+        let mut cs = TestConstraintSystem::<Scalar>::new();
+        let a = field.alloc(cs.namespace(|| "a"), Some(&a_bytes)).unwrap();
+        let b = field.alloc(cs.namespace(|| "b"), Some(&b_bytes)).unwrap();
+        let product = field.mul(cs.namespace(|| "product"), &a, &b).unwrap();
+        field
+            .enforce_equal(cs.namespace(|| "equal"), &product, &field.add(&a, &b))
+            .unwrap();
+        assert!(!cs.is_satisfied());
+
+        // A tampered result limb.
+        let mut cs = TestConstraintSystem::<Scalar>::new();
+        let a = field.alloc(cs.namespace(|| "a"), Some(&a_bytes)).unwrap();
+        let b = field.alloc(cs.namespace(|| "b"), Some(&b_bytes)).unwrap();
+        field.mul(cs.namespace(|| "product"), &a, &b).unwrap();
+        assert!(cs.is_satisfied());
+        let path = "product/reduce/result/limb 1/limb/num";
+        let value = cs.get(path);
+        cs.set(path, value + Scalar::one());
+        assert!(!cs.is_satisfied());
+
+        // The modulus is not canonical.
+        let mut cs = TestConstraintSystem::<Scalar>::new();
+        let p = field
+            .alloc_limbs(
+                cs.namespace(|| "p"),
+                Some(&Nat::from_bytes_le(&SECP256K1_P)),
+                256,
+            )
+            .unwrap();
+        field
+            .enforce_canonical(cs.namespace(|| "canonical"), &p)
+            .unwrap();
+        assert!(!cs.is_satisfied());
+    }
+}
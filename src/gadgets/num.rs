@@ -1,5 +1,7 @@
 //! Gadgets representing numbers in the scalar field of the underlying curve.
 
+use std::ops::{Add, Mul, Neg, Sub};
+
 use ff::{PrimeField, PrimeFieldBits};
 
 use crate::{ConstraintSystem, LinearCombination, SynthesisError, Variable};
@@ -8,6 +10,21 @@ use super::Assignment;
 
 use super::boolean::{self, AllocatedBit, Boolean};
 
+/// How a range check constrains a number to a given number of bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RangeCheck {
+    /// Decomposes the number into bits, costing a boolean constraint for each
+    /// bit.
+    Bits,
+    /// Decomposes the number into limbs of `window` bits, each constrained to
+    /// the table of `window`-bit values by the vanishing polynomial of the
+    /// table. This costs `2^(window - 1)` constraints per limb, so in R1CS it
+    /// is never cheaper than `Bits`; it allocates fewer variables, and matches
+    /// the limb layout of proving systems with native lookups. `window` must
+    /// be between 1 and 8.
+    Lookup { window: usize },
+}
+
 pub struct AllocatedNum<Scalar: PrimeField> {
     value: Option<Scalar>,
     variable: Variable,
@@ -356,6 +373,250 @@ impl<Scalar: PrimeField> AllocatedNum<Scalar> {
         Ok((c, d))
     }
 
+    /// Returns the sum of this number and `other`.
+    pub fn add<CS>(&self, cs: CS, other: &Self) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        (Num::from(self.clone()) + Num::from(other.clone())).into_allocated_num(cs)
+    }
+
+    /// Returns the difference of this number and `other`.
+    pub fn sub<CS>(&self, cs: CS, other: &Self) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        (Num::from(self.clone()) - Num::from(other.clone())).into_allocated_num(cs)
+    }
+
+    /// Returns the sum of this number and a constant.
+    pub fn add_constant<CS>(&self, cs: CS, constant: Scalar) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        (Num::from(self.clone()) + Num::constant(CS::one(), constant)).into_allocated_num(cs)
+    }
+
+    /// Returns the product of this number and a constant.
+    pub fn mul_constant<CS>(&self, cs: CS, constant: Scalar) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        (Num::from(self.clone()) * constant).into_allocated_num(cs)
+    }
+
+    /// Returns the multiplicative inverse of this number, which must be
+    /// nonzero.
+    pub fn inverse<CS>(&self, mut cs: CS) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let inv = Self::alloc(cs.namespace(|| "inverse"), || {
+            let tmp = *self.value.get()?;
+
+            if tmp.is_zero_vartime() {
+                Err(SynthesisError::DivisionByZero)
+            } else {
+                Ok(tmp.invert().unwrap())
+            }
+        })?;
+
+        // Constrain a * inv = 1, which also ensures that a is nonzero
+        cs.enforce(
+            || "inverse constraint",
+            |lc| lc + self.variable,
+            |lc| lc + inv.variable,
+            |lc| lc + CS::one(),
+        );
+
+        Ok(inv)
+    }
+
+    /// Returns the quotient of this number by `other`, which must be nonzero.
+    pub fn div<CS>(&self, mut cs: CS, other: &Self) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let inv = other.inverse(cs.namespace(|| "inverse of divisor"))?;
+        self.mul(cs.namespace(|| "quotient"), &inv)
+    }
+
+    /// Takes two allocated numbers (a, b) and returns
+    /// a if the condition is true, and b otherwise.
+    pub fn conditionally_select<CS>(
+        mut cs: CS,
+        a: &Self,
+        b: &Self,
+        condition: &Boolean,
+    ) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let c = Self::alloc(cs.namespace(|| "conditional selection result"), || {
+            if *condition.get_value().get()? {
+                Ok(*a.value.get()?)
+            } else {
+                Ok(*b.value.get()?)
+            }
+        })?;
+
+        // Constrain (a - b) * condition = c - b
+        cs.enforce(
+            || "conditional selection constraint",
+            |lc| lc + a.variable - b.variable,
+            |_| condition.lc(CS::one(), Scalar::one()),
+            |lc| lc + c.variable - b.variable,
+        );
+
+        Ok(c)
+    }
+
+    /// Returns whether `a` and `b` are equal. This is the same as
+    /// [`AllocatedNum::is_equal`].
+    pub fn equals<CS>(cs: CS, a: &Self, b: &Self) -> Result<Boolean, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        a.is_equal(cs, b)
+    }
+
+    /// Enforces that this number is less than `2^num_bits`.
+    pub fn enforce_in_range<CS>(
+        &self,
+        cs: CS,
+        num_bits: usize,
+        method: RangeCheck,
+    ) -> Result<(), SynthesisError>
+    where
+        Scalar: PrimeFieldBits,
+        CS: ConstraintSystem<Scalar>,
+    {
+        enforce_lc_in_range(
+            cs,
+            LinearCombination::zero() + self.variable,
+            self.value,
+            num_bits,
+            method,
+        )?;
+
+        Ok(())
+    }
+
+    /// Enforces that this number is less than `2^num_bits` as
+    /// [`RangeCheck::Bits`] does, returning the little-endian bits it is
+    /// decomposed into.
+    pub fn to_bits_le_in_range<CS>(
+        &self,
+        cs: CS,
+        num_bits: usize,
+    ) -> Result<Vec<Boolean>, SynthesisError>
+    where
+        Scalar: PrimeFieldBits,
+        CS: ConstraintSystem<Scalar>,
+    {
+        enforce_lc_in_range(
+            cs,
+            LinearCombination::zero() + self.variable,
+            self.value,
+            num_bits,
+            RangeCheck::Bits,
+        )
+    }
+
+    /// Returns whether this number is less than `other`.
+    ///
+    /// Both numbers must be less than `2^num_bits`, which should be enforced
+    /// separately if they are not already known to be.
+    pub fn less_than<CS>(
+        &self,
+        mut cs: CS,
+        other: &Self,
+        num_bits: usize,
+        method: RangeCheck,
+    ) -> Result<Boolean, SynthesisError>
+    where
+        Scalar: PrimeFieldBits,
+        CS: ConstraintSystem<Scalar>,
+    {
+        assert!(num_bits < Scalar::CAPACITY as usize);
+
+        // 2^num_bits + self - other has bit num_bits set exactly when
+        // self >= other.
+        let offset = Scalar::from(2).pow_vartime([num_bits as u64]);
+        let value = match (self.value, other.value) {
+            (Some(a), Some(b)) => Some(offset + a - b),
+            _ => None,
+        };
+
+        let top = AllocatedBit::alloc(
+            cs.namespace(|| "top bit"),
+            value.map(|v| v.to_le_bits()[num_bits]),
+        )?;
+
+        enforce_lc_in_range(
+            cs.namespace(|| "range check"),
+            LinearCombination::zero() + (offset, CS::one()) + self.variable
+                - other.variable
+                - (offset, top.get_variable()),
+            value.map(|v| {
+                if v.to_le_bits()[num_bits] {
+                    v - offset
+                } else {
+                    v
+                }
+            }),
+            num_bits,
+            method,
+        )?;
+
+        Ok(Boolean::from(top).not())
+    }
+
+    /// Returns whether this number is less than or equal to `other`.
+    ///
+    /// Both numbers must be less than `2^num_bits`, which should be enforced
+    /// separately if they are not already known to be.
+    pub fn less_or_equal<CS>(
+        &self,
+        cs: CS,
+        other: &Self,
+        num_bits: usize,
+        method: RangeCheck,
+    ) -> Result<Boolean, SynthesisError>
+    where
+        Scalar: PrimeFieldBits,
+        CS: ConstraintSystem<Scalar>,
+    {
+        other
+            .less_than(cs, self, num_bits, method)
+            .map(|gt| gt.not())
+    }
+
+    /// Returns whether this number is zero.
+    pub fn is_zero<CS>(&self, cs: CS) -> Result<Boolean, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        is_lc_zero(cs, LinearCombination::zero() + self.variable, self.value)
+    }
+
+    /// Returns whether this number is equal to `other`.
+    pub fn is_equal<CS>(&self, cs: CS, other: &Self) -> Result<Boolean, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let value = match (self.value, other.value) {
+            (Some(a), Some(b)) => Some(a - b),
+            _ => None,
+        };
+
+        is_lc_zero(
+            cs,
+            LinearCombination::zero() + self.variable - other.variable,
+            value,
+        )
+    }
+
     pub fn get_value(&self) -> Option<Scalar> {
         self.value
     }
@@ -365,6 +626,203 @@ impl<Scalar: PrimeField> AllocatedNum<Scalar> {
     }
 }
 
+/// Returns whether `lc`, which has the given value, is zero.
+fn is_lc_zero<Scalar, CS>(
+    mut cs: CS,
+    lc: LinearCombination<Scalar>,
+    value: Option<Scalar>,
+) -> Result<Boolean, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    let zero = AllocatedBit::alloc(
+        cs.namespace(|| "is zero"),
+        value.map(|v| v.is_zero_vartime()),
+    )?;
+    let inv = cs.alloc(
+        || "inverse",
+        || {
+            let value = value.ok_or(SynthesisError::AssignmentMissing)?;
+            Ok(value.invert().unwrap_or_else(Scalar::zero))
+        },
+    )?;
+
+    // lc * inv = 1 - zero
+    cs.enforce(
+        || "inverse constraint",
+        |_| lc.clone(),
+        |lc| lc + inv,
+        |lc| lc + CS::one() - zero.get_variable(),
+    );
+
+    // lc * zero = 0
+    cs.enforce(
+        || "zero constraint",
+        |_| lc,
+        |lc| lc + zero.get_variable(),
+        |lc| lc,
+    );
+
+    Ok(Boolean::from(zero))
+}
+
+/// Enforces that `lc`, which has the given value, is less than `2^num_bits`.
+///
+/// Returns the little-endian bits of `lc` if `method` is [`RangeCheck::Bits`],
+/// and nothing otherwise.
+fn enforce_lc_in_range<Scalar, CS>(
+    mut cs: CS,
+    lc: LinearCombination<Scalar>,
+    value: Option<Scalar>,
+    num_bits: usize,
+    method: RangeCheck,
+) -> Result<Vec<Boolean>, SynthesisError>
+where
+    Scalar: PrimeFieldBits,
+    CS: ConstraintSystem<Scalar>,
+{
+    assert!(num_bits < Scalar::CAPACITY as usize);
+
+    let window = match method {
+        RangeCheck::Bits => 1,
+        RangeCheck::Lookup { window } => {
+            assert!((1..=8).contains(&window));
+            window
+        }
+    };
+
+    let bits = value.map(|v| v.to_le_bits());
+    let mut packed = LinearCombination::zero();
+    let mut coeff = Scalar::one();
+    let mut allocated_bits = vec![];
+
+    for (i, start) in (0..num_bits).step_by(window).enumerate() {
+        let cs = &mut cs.namespace(|| format!("limb {}", i));
+        let width = window.min(num_bits - start);
+        let limb_value = bits.as_ref().map(|bits| {
+            bits[start..start + width]
+                .iter()
+                .by_vals()
+                .rev()
+                .fold(0u64, |acc, b| (acc << 1) | b as u64)
+        });
+
+        let limb = if let RangeCheck::Bits = method {
+            let bit = AllocatedBit::alloc(cs.namespace(|| "bit"), limb_value.map(|v| v == 1))?;
+            let variable = bit.get_variable();
+            allocated_bits.push(Boolean::from(bit));
+            variable
+        } else {
+            let limb = cs.alloc(
+                || "limb",
+                || {
+                    limb_value
+                        .map(Scalar::from)
+                        .ok_or(SynthesisError::AssignmentMissing)
+                },
+            )?;
+            enforce_in_table(cs, limb, limb_value, width)?;
+            limb
+        };
+
+        packed = packed + (coeff, limb);
+        coeff *= Scalar::from(1 << width);
+    }
+
+    cs.enforce(
+        || "packing constraint",
+        |_| packed,
+        |lc| lc + CS::one(),
+        |_| lc,
+    );
+
+    Ok(allocated_bits)
+}
+
+/// Enforces that `limb`, which has the given value, is less than `2^width`.
+///
+/// The table values `t` and `m - t`, where `m = 2^width - 1`, are the roots of
+/// `x(x - m) + t(m - t)`, so the vanishing polynomial of the table is a product
+/// of `2^(width - 1)` such factors sharing `x(x - m)`.
+fn enforce_in_table<Scalar, CS>(
+    cs: &mut CS,
+    limb: Variable,
+    value: Option<u64>,
+    width: usize,
+) -> Result<(), SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    let m = (1u64 << width) - 1;
+    let num_factors = 1u64 << (width - 1);
+    let value = value.map(Scalar::from);
+    let m_scalar = Scalar::from(m);
+
+    if num_factors == 1 {
+        // limb * (limb - 1) = 0
+        cs.enforce(
+            || "table constraint",
+            |lc| lc + limb,
+            |lc| lc + limb - CS::one(),
+            |lc| lc,
+        );
+        return Ok(());
+    }
+
+    // x = limb * (limb - m)
+    let x_value = value.map(|v| v * (v - m_scalar));
+    let x = cs.alloc(|| "x", || x_value.ok_or(SynthesisError::AssignmentMissing))?;
+    cs.enforce(
+        || "x constraint",
+        |lc| lc + limb,
+        |lc| lc + limb - (m_scalar, CS::one()),
+        |lc| lc + x,
+    );
+
+    let factor = |t: u64| {
+        let c = Scalar::from(t * (m - t));
+        (
+            LinearCombination::zero() + x + (c, CS::one()),
+            x_value.map(|x| x + c),
+        )
+    };
+
+    // Accumulate the product of the factors, the last of which must take
+    // it to zero.
+    let (mut acc, mut acc_value) = factor(0);
+    for t in 1..num_factors {
+        let (f, f_value) = factor(t);
+        let product_value = acc_value.zip(f_value).map(|(a, f)| a * f);
+
+        if t == num_factors - 1 {
+            cs.enforce(|| "table constraint", |_| acc, |_| f, |lc| lc);
+            break;
+        }
+
+        let product = cs.alloc(
+            || format!("product {}", t),
+            || product_value.ok_or(SynthesisError::AssignmentMissing),
+        )?;
+        cs.enforce(
+            || format!("product {} constraint", t),
+            |_| acc,
+            |_| f,
+            |lc| lc + product,
+        );
+        acc = LinearCombination::zero() + product;
+        acc_value = product_value;
+    }
+
+    Ok(())
+}
+
+/// A linear combination of variables with a known value.
+///
+/// `Num`s can be added, subtracted, negated and multiplied by constants
+/// without creating any constraints.
+#[derive(Clone)]
 pub struct Num<Scalar: PrimeField> {
     value: Option<Scalar>,
     lc: LinearCombination<Scalar>,
@@ -387,6 +845,15 @@ impl<Scalar: PrimeField> Num<Scalar> {
         }
     }
 
+    /// Returns a constant `Num`, where `one` is the variable
+    /// [`ConstraintSystem::one`].
+    pub fn constant(one: Variable, value: Scalar) -> Self {
+        Num {
+            value: Some(value),
+            lc: LinearCombination::zero() + (value, one),
+        }
+    }
+
     pub fn get_value(&self) -> Option<Scalar> {
         self.value
     }
@@ -412,18 +879,108 @@ impl<Scalar: PrimeField> Num<Scalar> {
             lc: self.lc + &bit.lc(one, coeff),
         }
     }
+
+    /// Allocates a variable equal to this `Num`.
+    pub fn into_allocated_num<CS>(self, mut cs: CS) -> Result<AllocatedNum<Scalar>, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let num = AllocatedNum::alloc(cs.namespace(|| "num"), || {
+            self.value.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        cs.enforce(
+            || "allocation constraint",
+            |_| self.lc,
+            |lc| lc + CS::one(),
+            |lc| lc + num.variable,
+        );
+
+        Ok(num)
+    }
+
+    /// Enforces that this `Num` is equal to `other`.
+    pub fn enforce_equal<CS>(&self, mut cs: CS, other: &Self)
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        cs.enforce(
+            || "equality constraint",
+            |lc| lc + &self.lc - &other.lc,
+            |lc| lc + CS::one(),
+            |lc| lc,
+        );
+    }
+}
+
+impl<Scalar: PrimeField> Add<&Num<Scalar>> for Num<Scalar> {
+    type Output = Num<Scalar>;
+
+    fn add(self, other: &Num<Scalar>) -> Num<Scalar> {
+        Num {
+            value: self.value.zip(other.value).map(|(a, b)| a + b),
+            lc: self.lc + &other.lc,
+        }
+    }
+}
+
+impl<Scalar: PrimeField> Add<Num<Scalar>> for Num<Scalar> {
+    type Output = Num<Scalar>;
+
+    fn add(self, other: Num<Scalar>) -> Num<Scalar> {
+        self + &other
+    }
+}
+
+impl<Scalar: PrimeField> Sub<&Num<Scalar>> for Num<Scalar> {
+    type Output = Num<Scalar>;
+
+    fn sub(self, other: &Num<Scalar>) -> Num<Scalar> {
+        Num {
+            value: self.value.zip(other.value).map(|(a, b)| a - b),
+            lc: self.lc - &other.lc,
+        }
+    }
+}
+
+impl<Scalar: PrimeField> Sub<Num<Scalar>> for Num<Scalar> {
+    type Output = Num<Scalar>;
+
+    fn sub(self, other: Num<Scalar>) -> Num<Scalar> {
+        self - &other
+    }
+}
+
+impl<Scalar: PrimeField> Neg for Num<Scalar> {
+    type Output = Num<Scalar>;
+
+    fn neg(self) -> Num<Scalar> {
+        Num::zero() - self
+    }
+}
+
+impl<Scalar: PrimeField> Mul<Scalar> for Num<Scalar> {
+    type Output = Num<Scalar>;
+
+    fn mul(self, coeff: Scalar) -> Num<Scalar> {
+        Num {
+            value: self.value.map(|v| v * coeff),
+            lc: self.lc(coeff),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::ConstraintSystem;
+    use crate::{ConstraintSystem, SynthesisError};
     use bls12_381::Scalar;
     use ff::{Field, PrimeField, PrimeFieldBits};
     use rand_core::SeedableRng;
     use rand_xorshift::XorShiftRng;
     use std::ops::{Neg, SubAssign};
 
-    use super::{AllocatedNum, Boolean};
+    use super::{enforce_in_table, AllocatedNum, Boolean, Num, RangeCheck};
+    use crate::gadgets::boolean::AllocatedBit;
     use crate::gadgets::test::*;
 
     #[test]
@@ -439,7 +996,7 @@ mod test {
     fn test_allocated_num1() {
         let mut cs = TestConstraintSystem::new();
 
-        AllocatedNum::alloc(&mut cs,  || Ok(Scalar::zero())).unwrap();
+        AllocatedNum::alloc(&mut cs, || Ok(Scalar::zero())).unwrap();
 
         println!("test_allocated_num1 executed...");
 
@@ -608,4 +1165,231 @@ mod test {
             }
         }
     }
+
+    const METHODS: [RangeCheck; 5] = [
+        RangeCheck::Bits,
+        RangeCheck::Lookup { window: 1 },
+        RangeCheck::Lookup { window: 2 },
+        RangeCheck::Lookup { window: 3 },
+        RangeCheck::Lookup { window: 4 },
+    ];
+
+    #[test]
+    fn test_enforce_in_table() {
+        for width in 1..=8 {
+            let values = (0..1u64 << (width + 1)).chain(Some(u64::MAX));
+            for value in values {
+                let mut cs = TestConstraintSystem::<Scalar>::new();
+                let limb = cs.alloc(|| "limb", || Ok(Scalar::from(value))).unwrap();
+                enforce_in_table(&mut cs, limb, Some(value), width).unwrap();
+
+                assert_eq!(cs.num_constraints(), 1 << (width - 1));
+                assert_eq!(cs.is_satisfied(), value < 1 << width, "{} {}", width, value);
+            }
+        }
+    }
+
+    #[test]
+    fn test_enforce_in_range() {
+        for method in METHODS {
+            for num_bits in 0..7 {
+                let values = (0..1u64 << (num_bits + 1))
+                    .map(|v| (Scalar::from(v), v < 1 << num_bits))
+                    .chain(Some((-Scalar::one(), false)));
+                for (value, in_range) in values {
+                    let mut cs = TestConstraintSystem::new();
+                    let n = AllocatedNum::alloc(&mut cs, || Ok(value)).unwrap();
+                    n.enforce_in_range(&mut cs, num_bits, method).unwrap();
+
+                    assert_eq!(
+                        cs.is_satisfied(),
+                        in_range,
+                        "{:?} {} {:?}",
+                        method,
+                        num_bits,
+                        value
+                    );
+                }
+            }
+        }
+
+        let mut cs = TestConstraintSystem::<Scalar>::new();
+        let n = AllocatedNum::alloc(&mut cs, || Ok(Scalar::from(100))).unwrap();
+        n.enforce_in_range(&mut cs, 7, RangeCheck::Lookup { window: 3 })
+            .unwrap();
+        // Two 3-bit limbs and a 1-bit limb, and the packing constraint
+        assert_eq!(cs.num_constraints(), 4 + 4 + 1 + 1);
+        assert!(cs.is_satisfied());
+        cs.set("limb 0/limb", Scalar::from(12));
+        cs.set("limb 1/limb", Scalar::from(0));
+        assert!(!cs.is_satisfied());
+    }
+
+    #[test]
+    fn test_comparisons() {
+        for method in METHODS {
+            for a in 0..8 {
+                for b in 0..8 {
+                    let mut cs = TestConstraintSystem::new();
+                    let x =
+                        AllocatedNum::alloc(cs.namespace(|| "a"), || Ok(Scalar::from(a))).unwrap();
+                    let y =
+                        AllocatedNum::alloc(cs.namespace(|| "b"), || Ok(Scalar::from(b))).unwrap();
+
+                    let lt = x.less_than(cs.namespace(|| "lt"), &y, 3, method).unwrap();
+                    let le = x
+                        .less_or_equal(cs.namespace(|| "le"), &y, 3, method)
+                        .unwrap();
+
+                    assert!(cs.is_satisfied());
+                    assert_eq!(lt.get_value(), Some(a < b));
+                    assert_eq!(le.get_value(), Some(a <= b));
+
+                    // The result can't be flipped.
+                    let top = cs.get("lt/top bit/boolean");
+                    cs.set("lt/top bit/boolean", Scalar::one() - top);
+                    assert!(!cs.is_satisfied());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_zero_and_is_equal() {
+        let values = [
+            Scalar::zero(),
+            Scalar::one(),
+            Scalar::from(2),
+            -Scalar::one(),
+        ];
+
+        for a in values {
+            let mut cs = TestConstraintSystem::new();
+            let x = AllocatedNum::alloc(&mut cs, || Ok(a)).unwrap();
+            let zero = x.is_zero(&mut cs).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(zero.get_value(), Some(a.is_zero_vartime()));
+
+            // Neither result can be proven for the wrong value.
+            cs.set("is zero/boolean", Scalar::from(!a.is_zero_vartime() as u64));
+            assert!(!cs.is_satisfied());
+
+            for b in values {
+                let mut cs = TestConstraintSystem::new();
+                let x = AllocatedNum::alloc(cs.namespace(|| "a"), || Ok(a)).unwrap();
+                let y = AllocatedNum::alloc(cs.namespace(|| "b"), || Ok(b)).unwrap();
+                let equal = x.is_equal(cs.namespace(|| "eq"), &y).unwrap();
+
+                assert!(cs.is_satisfied());
+                assert_eq!(equal.get_value(), Some(a == b));
+
+                cs.set("eq/is zero/boolean", Scalar::from((a != b) as u64));
+                assert!(!cs.is_satisfied());
+            }
+        }
+    }
+
+    #[test]
+    fn test_num_arithmetic() {
+        let mut cs = TestConstraintSystem::new();
+
+        let a = AllocatedNum::alloc(cs.namespace(|| "a"), || Ok(Scalar::from(12))).unwrap();
+        let b = AllocatedNum::alloc(cs.namespace(|| "b"), || Ok(Scalar::from(5))).unwrap();
+
+        let sum = a.add(cs.namespace(|| "a + b"), &b).unwrap();
+        let diff = b.sub(cs.namespace(|| "b - a"), &a).unwrap();
+        let shifted = a
+            .add_constant(cs.namespace(|| "a + 3"), Scalar::from(3))
+            .unwrap();
+        let scaled = a
+            .mul_constant(cs.namespace(|| "a * 3"), Scalar::from(3))
+            .unwrap();
+        let inv = b.inverse(cs.namespace(|| "inverse of b")).unwrap();
+        let quotient = a.div(cs.namespace(|| "a div b"), &b).unwrap();
+
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_constraints(), 7);
+        assert_eq!(sum.get_value().unwrap(), Scalar::from(17));
+        assert_eq!(diff.get_value().unwrap(), -Scalar::from(7));
+        assert_eq!(shifted.get_value().unwrap(), Scalar::from(15));
+        assert_eq!(scaled.get_value().unwrap(), Scalar::from(36));
+        assert_eq!(inv.get_value().unwrap() * Scalar::from(5), Scalar::one());
+        assert_eq!(
+            quotient.get_value().unwrap() * Scalar::from(5),
+            Scalar::from(12)
+        );
+
+        cs.set("a + b/num/num", Scalar::from(18));
+        assert_eq!(
+            cs.which_is_unsatisfied(),
+            Some("a + b/allocation constraint")
+        );
+        cs.set("a + b/num/num", Scalar::from(17));
+        cs.set("a div b/quotient/product num", Scalar::from(2));
+        assert!(!cs.is_satisfied());
+
+        // Division by zero has no witness.
+        let zero = AllocatedNum::alloc(cs.namespace(|| "zero"), || Ok(Scalar::zero())).unwrap();
+        assert!(matches!(
+            a.div(cs.namespace(|| "a div 0"), &zero),
+            Err(SynthesisError::DivisionByZero)
+        ));
+    }
+
+    #[test]
+    fn test_num_conditional_selection() {
+        for condition in [false, true] {
+            let mut cs = TestConstraintSystem::new();
+
+            let a = AllocatedNum::alloc(cs.namespace(|| "a"), || Ok(Scalar::from(7))).unwrap();
+            let b = AllocatedNum::alloc(cs.namespace(|| "b"), || Ok(Scalar::from(9))).unwrap();
+            let condition = Boolean::from(
+                AllocatedBit::alloc(cs.namespace(|| "condition"), Some(condition)).unwrap(),
+            );
+
+            let c = AllocatedNum::conditionally_select(&mut cs, &a, &b, &condition).unwrap();
+            let equal = AllocatedNum::equals(cs.namespace(|| "c == a"), &c, &a).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(equal.get_value(), condition.get_value());
+            assert_eq!(
+                c.get_value().unwrap(),
+                Scalar::from(if condition.get_value().unwrap() { 7 } else { 9 })
+            );
+
+            cs.set("conditional selection result/num", Scalar::from(8));
+            assert!(!cs.is_satisfied());
+        }
+    }
+
+    #[test]
+    fn test_num_operators() {
+        let mut cs = TestConstraintSystem::new();
+
+        let a = AllocatedNum::alloc(cs.namespace(|| "a"), || Ok(Scalar::from(4))).unwrap();
+        let b = AllocatedNum::alloc(cs.namespace(|| "b"), || Ok(Scalar::from(10))).unwrap();
+
+        // 3a - b + 2 + (-a) = 2a - b + 2
+        let x = Num::from(a.clone()) * Scalar::from(3) - Num::from(b.clone())
+            + Num::constant(TestConstraintSystem::<Scalar>::one(), Scalar::from(2))
+            + -Num::from(a);
+        assert_eq!(cs.num_constraints(), 0);
+        assert_eq!(x.get_value().unwrap(), Scalar::zero());
+
+        x.enforce_equal(cs.namespace(|| "x == 0"), &Num::zero());
+        let y = (x + Num::from(b))
+            .into_allocated_num(cs.namespace(|| "y"))
+            .unwrap();
+
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_constraints(), 2);
+        assert_eq!(y.get_value().unwrap(), Scalar::from(10));
+
+        cs.set("a/num", Scalar::from(5));
+        assert_eq!(
+            cs.which_is_unsatisfied(),
+            Some("x == 0/equality constraint")
+        );
+    }
 }
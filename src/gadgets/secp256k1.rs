@@ -0,0 +1,595 @@
+//! Gadgets for the secp256k1 elliptic curve and ECDSA signature verification,
+//! over [`nonnative`] elements of its base and scalar fields.
+//!
+//! Field elements are given as little-endian bytes throughout.
+//!
+//! [`nonnative`]: super::nonnative
+
+use ff::PrimeFieldBits;
+
+use super::boolean::Boolean;
+use super::nonnative::{NonNativeElement, NonNativeField};
+use crate::{ConstraintSystem, SynthesisError, Variable};
+
+/// The base field modulus, `2^256 - 2^32 - 977`, as little-endian words.
+const P: [u64; 4] = [
+    0xfffffffefffffc2f,
+    0xffffffffffffffff,
+    0xffffffffffffffff,
+    0xffffffffffffffff,
+];
+
+/// The order of the group.
+const N: [u64; 4] = [
+    0xbfd25e8cd0364141,
+    0xbaaedce6af48a03b,
+    0xfffffffffffffffe,
+    0xffffffffffffffff,
+];
+
+/// The standard generator.
+const G: ([u64; 4], [u64; 4]) = (
+    [
+        0x59f2815b16f81798,
+        0x029bfcdb2dce28d9,
+        0x55a06295ce870b07,
+        0x79be667ef9dcbbac,
+    ],
+    [
+        0x9c47d08ffb10d4b8,
+        0xfd17b448a6855419,
+        0x5da4fbfc0e1108a8,
+        0x483ada7726a3c465,
+    ],
+);
+
+/// The point with x-coordinate 1 and even y-coordinate, which is used to
+/// offset the accumulator of scalar multiplications away from the points
+/// being added to it.
+const OFFSET: ([u64; 4], [u64; 4]) = (
+    [1, 0, 0, 0],
+    [
+        0xbc750d587e76a7ee,
+        0x264ca8d2587fdd6f,
+        0x63db68605822fb14,
+        0x4218f20ae6c646b3,
+    ],
+);
+
+/// The point with x-coordinate 2 and even y-coordinate, which is added to
+/// every entry of the tables of scalar multiplications, so that building the
+/// tables never adds a point to itself or its negation.
+const TABLE_OFFSET: ([u64; 4], [u64; 4]) = (
+    [2, 0, 0, 0],
+    [
+        0x511f860657b8535e,
+        0x424c5fa425bbda1c,
+        0x9f5a98d70a5efce8,
+        0x66fbe727b2ba09e0,
+    ],
+);
+
+/// `-(2^256 · OFFSET + (2^256 - 1) · TABLE_OFFSET)`.
+const OFFSET_CORRECTION: ([u64; 4], [u64; 4]) = (
+    [
+        0x4937d55e9440131d,
+        0x3a2a80abcadbd338,
+        0xd03b7996cc4be69c,
+        0xb40fa56ab3f8168c,
+    ],
+    [
+        0x07425ff668f064fc,
+        0xca79d6a9478b3774,
+        0xaa0478d21fc0c53e,
+        0x34bf129280020ef4,
+    ],
+);
+
+/// The number of bits of a scalar.
+const SCALAR_BITS: usize = 256;
+
+fn to_bytes(words: [u64; 4]) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    for (chunk, word) in bytes.chunks_mut(8).zip(words.iter()) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    bytes
+}
+
+/// Returns the base field of secp256k1, with 64-bit limbs.
+pub fn base_field() -> NonNativeField {
+    NonNativeField::new(&to_bytes(P), 64)
+}
+
+/// Returns the scalar field of secp256k1, with 64-bit limbs.
+pub fn scalar_field() -> NonNativeField {
+    NonNativeField::new(&to_bytes(N), 64)
+}
+
+/// An affine point on secp256k1, other than the point at infinity.
+#[derive(Clone)]
+pub struct AffinePoint<Scalar: PrimeFieldBits> {
+    x: NonNativeElement<Scalar>,
+    y: NonNativeElement<Scalar>,
+}
+
+impl<Scalar: PrimeFieldBits> AffinePoint<Scalar> {
+    pub fn get_x(&self) -> &NonNativeElement<Scalar> {
+        &self.x
+    }
+
+    pub fn get_y(&self) -> &NonNativeElement<Scalar> {
+        &self.y
+    }
+
+    /// Returns the coordinates of this point, reduced.
+    pub fn get_value(&self) -> Option<([u8; 32], [u8; 32])> {
+        let field = base_field();
+        let x = field.get_value(&self.x)?;
+        let y = field.get_value(&self.y)?;
+        Some((x.try_into().unwrap(), y.try_into().unwrap()))
+    }
+
+    /// Allocates a point with the given coordinates, and enforces that it is
+    /// on the curve.
+    pub fn witness<CS>(
+        mut cs: CS,
+        value: Option<([u8; 32], [u8; 32])>,
+    ) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let field = base_field();
+        let x = field.alloc(cs.namespace(|| "x"), value.as_ref().map(|v| &v.0[..]))?;
+        let y = field.alloc(cs.namespace(|| "y"), value.as_ref().map(|v| &v.1[..]))?;
+
+        // y^2 = x^3 + 7
+        let x_squared = field.mul(cs.namespace(|| "x^2"), &x, &x)?;
+        let x_cubed = field.mul_unreduced(cs.namespace(|| "x^3"), &x_squared, &x)?;
+        let y_squared = field.mul_unreduced(cs.namespace(|| "y^2"), &y, &y)?;
+        let rhs = field.add(&x_cubed, &field.constant(CS::one(), &[7]));
+        field.enforce_equal(cs.namespace(|| "curve equation"), &y_squared, &rhs)?;
+
+        Ok(AffinePoint { x, y })
+    }
+
+    /// Returns a constant point, where `one` is the variable
+    /// [`ConstraintSystem::one`]. The point must be on the curve.
+    pub fn constant(one: Variable, x: &[u8; 32], y: &[u8; 32]) -> Self {
+        let field = base_field();
+        AffinePoint {
+            x: field.constant(one, x),
+            y: field.constant(one, y),
+        }
+    }
+
+    /// Returns the standard generator, where `one` is the variable
+    /// [`ConstraintSystem::one`].
+    pub fn generator(one: Variable) -> Self {
+        Self::constant(one, &to_bytes(G.0), &to_bytes(G.1))
+    }
+
+    /// Returns `a` if `condition` is true, and `b` otherwise.
+    pub fn conditionally_select<CS>(
+        mut cs: CS,
+        a: &Self,
+        b: &Self,
+        condition: &Boolean,
+    ) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let field = base_field();
+        Ok(AffinePoint {
+            x: field.conditionally_select(cs.namespace(|| "x"), &a.x, &b.x, condition)?,
+            y: field.conditionally_select(cs.namespace(|| "y"), &a.y, &b.y, condition)?,
+        })
+    }
+
+    /// Returns `self + other`.
+    ///
+    /// The points must have different x-coordinates, so that neither they
+    /// nor their sum is the point at infinity; otherwise this returns
+    /// [`SynthesisError::DivisionByZero`], or the constraints are
+    /// unsatisfiable if the values are unknown.
+    pub fn add<CS>(&self, mut cs: CS, other: &Self) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let field = base_field();
+
+        // Dividing 1 by x2 - x1 enforces that it is nonzero; otherwise lambda
+        // would be unconstrained when the points are equal.
+        let dx = field.sub(CS::one(), &other.x, &self.x);
+        let dx_inv = field.div(
+            cs.namespace(|| "dx inverse"),
+            &field.constant(CS::one(), &[1]),
+            &dx,
+        )?;
+
+        // lambda = (y2 - y1) / (x2 - x1)
+        let dy = field.sub(CS::one(), &other.y, &self.y);
+        let lambda = field.mul(cs.namespace(|| "lambda"), &dy, &dx_inv)?;
+
+        self.finish(cs, other, &lambda)
+    }
+
+    /// Returns `2 · self`.
+    pub fn double<CS>(&self, mut cs: CS) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let field = base_field();
+
+        // lambda = 3 x^2 / 2 y, where y is nonzero because the curve has no
+        // points of order two.
+        let x_squared = field.mul_unreduced(cs.namespace(|| "x^2"), &self.x, &self.x)?;
+        let numerator = field.add(&field.add(&x_squared, &x_squared), &x_squared);
+        let denominator = field.add(&self.y, &self.y);
+        let lambda = field.div(cs.namespace(|| "lambda"), &numerator, &denominator)?;
+
+        self.finish(cs, self, &lambda)
+    }
+
+    /// Returns the sum of `self` and `other`, given the slope `lambda` of
+    /// the line through them.
+    fn finish<CS>(
+        &self,
+        mut cs: CS,
+        other: &Self,
+        lambda: &NonNativeElement<Scalar>,
+    ) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        let field = base_field();
+
+        // x3 = lambda^2 - x1 - x2
+        let lambda_squared = field.mul_unreduced(cs.namespace(|| "lambda^2"), lambda, lambda)?;
+        let x = field.sub(
+            CS::one(),
+            &field.sub(CS::one(), &lambda_squared, &self.x),
+            &other.x,
+        );
+        let x = field.reduce(cs.namespace(|| "x"), &x)?;
+
+        // y3 = lambda (x1 - x3) - y1
+        let product = field.mul_unreduced(
+            cs.namespace(|| "lambda times dx"),
+            lambda,
+            &field.sub(CS::one(), &self.x, &x),
+        )?;
+        let y = field.reduce(
+            cs.namespace(|| "y"),
+            &field.sub(CS::one(), &product, &self.y),
+        )?;
+
+        Ok(AffinePoint { x, y })
+    }
+
+    /// Returns `by · self`, for the little-endian bits `by` of a scalar.
+    ///
+    /// The result must not be the point at infinity. Otherwise, and with
+    /// negligible probability for other scalars, this returns
+    /// [`SynthesisError::DivisionByZero`] or the constraints are
+    /// unsatisfiable.
+    pub fn mul<CS>(&self, cs: CS, by: &[Boolean]) -> Result<Self, SynthesisError>
+    where
+        CS: ConstraintSystem<Scalar>,
+    {
+        multi_mul(cs, &[(self, by)])
+    }
+}
+
+/// Returns `Σ by_i · P_i`, for up to a few points `P_i`, by double-and-add
+/// over all of the scalars at once.
+///
+/// The accumulator starts at a fixed offset point, and every table entry is
+/// offset by another fixed point, whose multiples are subtracted at the end.
+/// The incomplete addition formulas then only fail with negligible
+/// probability, even when some of the `P_i` are equal or opposite.
+fn multi_mul<Scalar, CS>(
+    mut cs: CS,
+    terms: &[(&AffinePoint<Scalar>, &[Boolean])],
+) -> Result<AffinePoint<Scalar>, SynthesisError>
+where
+    Scalar: PrimeFieldBits,
+    CS: ConstraintSystem<Scalar>,
+{
+    assert!(!terms.is_empty() && terms.len() <= 4);
+    for (_, by) in terms {
+        assert!(by.len() <= SCALAR_BITS);
+    }
+
+    // table[mask] is TABLE_OFFSET plus the sum of the points selected by
+    // mask, built by adding the lowest selected point to a smaller entry.
+    let mut table = vec![AffinePoint::constant(
+        CS::one(),
+        &to_bytes(TABLE_OFFSET.0),
+        &to_bytes(TABLE_OFFSET.1),
+    )];
+    for i in 1usize..(1 << terms.len()) {
+        let point = table[i & (i - 1)].add(
+            cs.namespace(|| format!("table {}", i)),
+            terms[i.trailing_zeros() as usize].0,
+        )?;
+        table.push(point);
+    }
+
+    let mut acc = AffinePoint::constant(CS::one(), &to_bytes(OFFSET.0), &to_bytes(OFFSET.1));
+    for i in (0..SCALAR_BITS).rev() {
+        let cs = &mut cs.namespace(|| format!("bit {}", i));
+        let bits: Vec<_> = terms
+            .iter()
+            .map(|(_, by)| by.get(i).cloned().unwrap_or(Boolean::constant(false)))
+            .collect();
+
+        acc = acc.double(cs.namespace(|| "double"))?;
+
+        // Select table[bits] by halving the table with each bit, from the
+        // last.
+        let mut candidates = table.clone();
+        for (j, bit) in bits.iter().enumerate().rev() {
+            let half = candidates.len() / 2;
+            candidates = (0..half)
+                .map(|k| {
+                    AffinePoint::conditionally_select(
+                        cs.namespace(|| format!("select {} {}", j, k)),
+                        &candidates[k + half],
+                        &candidates[k],
+                        bit,
+                    )
+                })
+                .collect::<Result<_, _>>()?;
+        }
+        acc = acc.add(cs.namespace(|| "add"), &candidates[0])?;
+    }
+
+    let correction = AffinePoint::constant(
+        CS::one(),
+        &to_bytes(OFFSET_CORRECTION.0),
+        &to_bytes(OFFSET_CORRECTION.1),
+    );
+    acc.add(cs.namespace(|| "correction"), &correction)
+}
+
+/// Verifies the ECDSA signature `(r, s)` of the message with hash `z` under
+/// `public_key`.
+///
+/// `z`, `r` and `s` are elements of the [`scalar_field`]; `z` is the
+/// leftmost 256 bits of the message hash, interpreted as a big-endian
+/// integer. `r` and `s` are enforced to be in the range `[1, n)`.
+///
+/// If the values are known and the signature is invalid, this may return an
+/// error instead of unsatisfiable constraints.
+pub fn verify_ecdsa<Scalar, CS>(
+    mut cs: CS,
+    public_key: &AffinePoint<Scalar>,
+    z: &NonNativeElement<Scalar>,
+    r: &NonNativeElement<Scalar>,
+    s: &NonNativeElement<Scalar>,
+) -> Result<(), SynthesisError>
+where
+    Scalar: PrimeFieldBits,
+    CS: ConstraintSystem<Scalar>,
+{
+    let field = scalar_field();
+    let one = field.constant(CS::one(), &[1]);
+
+    field.enforce_canonical(cs.namespace(|| "r canonical"), r)?;
+    field.enforce_canonical(cs.namespace(|| "s canonical"), s)?;
+    // Inverting r enforces that it is nonzero.
+    field.div(cs.namespace(|| "r inverse"), &one, r)?;
+    let s_inv = field.div(cs.namespace(|| "s inverse"), &one, s)?;
+
+    // R = (z / s) G + (r / s) Q
+    let u1 = field.mul(cs.namespace(|| "u1"), z, &s_inv)?;
+    let u2 = field.mul(cs.namespace(|| "u2"), r, &s_inv)?;
+    let u1 = field.to_bits_le(cs.namespace(|| "u1 bits"), &u1)?;
+    let u2 = field.to_bits_le(cs.namespace(|| "u2 bits"), &u2)?;
+    let generator = AffinePoint::generator(CS::one());
+    let point = multi_mul(
+        cs.namespace(|| "multiplication"),
+        &[(&generator, &u1), (public_key, &u2)],
+    )?;
+
+    // x(R) mod n = r, where x(R) must be reduced modulo p first.
+    base_field().enforce_canonical(cs.namespace(|| "x canonical"), &point.x)?;
+    field.enforce_equal(cs.namespace(|| "signature"), &point.x, r)
+}
+
+#[cfg(test)]
+mod test {
+    use bls12_381::Scalar;
+    use hex_literal::hex;
+
+    use super::{scalar_field, to_bytes, verify_ecdsa, AffinePoint, G, N};
+    use crate::gadgets::multipack::bytes_to_bits_le;
+    use crate::gadgets::test::{alloc_bits, TestConstraintSystem};
+    use crate::ConstraintSystem;
+
+    fn reversed(mut bytes: [u8; 32]) -> [u8; 32] {
+        bytes.reverse();
+        bytes
+    }
+
+    fn verify(public_key: ([u8; 32], [u8; 32]), z: &[u8; 32], r: &[u8; 32], s: &[u8; 32]) -> bool {
+        let mut cs = TestConstraintSystem::<Scalar>::new();
+        let field = scalar_field();
+        let public_key =
+            AffinePoint::witness(cs.namespace(|| "public key"), Some(public_key)).unwrap();
+        // Elements built from their bits are not reduced, so that values of r
+        // and s that are at least n reach the gadget.
+        let mut from_bytes = |name: &'static str, bytes: &[u8; 32]| {
+            field.from_bits_le(
+                TestConstraintSystem::<Scalar>::one(),
+                &alloc_bits(cs.namespace(|| name), &bytes_to_bits_le(bytes)),
+            )
+        };
+        let z = from_bytes("z", z);
+        let r = from_bytes("r", r);
+        let s = from_bytes("s", s);
+        verify_ecdsa(cs.namespace(|| "verify"), &public_key, &z, &r, &s)
+            .map(|()| cs.is_satisfied())
+            .unwrap_or(false)
+    }
+
+    #[test]
+    fn test_point_arithmetic() {
+        // 2G and 3G.
+        let g2 = (
+            reversed(hex!(
+                "c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5"
+            )),
+            reversed(hex!(
+                "1ae168fea63dc339a3c58419466ceaeef7f632653266d0e1236431a950cfe52a"
+            )),
+        );
+        let g3 = (
+            reversed(hex!(
+                "f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9"
+            )),
+            reversed(hex!(
+                "388f7b0f632de8140fe337e62a37f3566500a99934c2231b6cb9fd7584b8e672"
+            )),
+        );
+
+        let mut cs = TestConstraintSystem::<Scalar>::new();
+        let g = AffinePoint::witness(cs.namespace(|| "g"), Some((to_bytes(G.0), to_bytes(G.1))))
+            .unwrap();
+        let doubled = g.double(cs.namespace(|| "double")).unwrap();
+        assert_eq!(doubled.get_value().unwrap(), g2);
+        let sum = doubled.add(cs.namespace(|| "add"), &g).unwrap();
+        assert_eq!(sum.get_value().unwrap(), g3);
+        assert!(cs.is_satisfied());
+
+        // (2, y) is not on the curve.
+        let mut cs = TestConstraintSystem::<Scalar>::new();
+        let mut two = [0; 32];
+        two[0] = 2;
+        AffinePoint::witness(cs.namespace(|| "point"), Some((two, to_bytes(G.1)))).unwrap();
+        assert!(!cs.is_satisfied());
+
+        // G + G cannot use the addition formula.
+        let mut cs = TestConstraintSystem::<Scalar>::new();
+        let g = AffinePoint::witness(cs.namespace(|| "g"), Some((to_bytes(G.0), to_bytes(G.1))))
+            .unwrap();
+        let other = AffinePoint::generator(TestConstraintSystem::<Scalar>::one());
+        assert!(g.add(cs.namespace(|| "add"), &other).is_err());
+    }
+
+    #[test]
+    fn test_ecdsa() {
+        // >>> d = 0xc9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721
+        // >>> k = 0x1a2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d5e6f708192a3b4c5d6e7f809
+        // >>> z = int.from_bytes(hashlib.sha256(b"bellman").digest(), "big")
+        // >>> Q = d * G
+        // >>> r = (k * G).x % n
+        // >>> s = pow(k, -1, n) * (z + r * d) % n
+        let public_key = (
+            reversed(hex!(
+                "2c8c31fc9f990c6b55e3865a184a4ce50e09481f2eaeb3e60ec1cea13a6ae645"
+            )),
+            reversed(hex!(
+                "64b95e4fdb6948c0386e189b006a29f686769b011704275e4459822dc3328085"
+            )),
+        );
+        let z = reversed(hex!(
+            "48d36d820ccc1788d3ccb4b12dc547853426233334acf2ea2ab224c4f91c9297"
+        ));
+        let r = reversed(hex!(
+            "867698c8917c53c16bd7f77ed96a43757da51ef5bdee51e7d48353714cfbcc19"
+        ));
+        let s = reversed(hex!(
+            "3790b7175055da73484b83e602ea0c7985cf4da3385e2f8f285d6f9bf12ab104"
+        ));
+
+        let check = |z: &[u8; 32], r: &[u8; 32], s: &[u8; 32]| verify(public_key, z, r, s);
+
+        assert!(check(&z, &r, &s));
+
+        // A different message.
+        let mut other = z;
+        other[0] ^= 1;
+        assert!(!check(&other, &r, &s));
+
+        // A tampered s.
+        let mut other = s;
+        other[0] ^= 1;
+        assert!(!check(&z, &r, &other));
+
+        // r and s must be nonzero.
+        assert!(!check(&z, &[0; 32], &s));
+        assert!(!check(&z, &r, &[0; 32]));
+
+        // r must be less than n.
+        assert!(!check(&z, &to_bytes(N), &s));
+
+        // A different public key.
+        assert!(!verify((to_bytes(G.0), to_bytes(G.1)), &z, &r, &s));
+    }
+
+    #[test]
+    fn test_ecdsa_non_canonical_s() {
+        // A signature with s = 1, for which s + n fits in 256 bits and is
+        // rejected only by the range check on s.
+        //
+        // >>> k = 0x1a2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d5e6f708192a3b4c5d6e7f809
+        // >>> z = int.from_bytes(hashlib.sha256(b"bellman").digest(), "big")
+        // >>> r = (k * G).x % n
+        // >>> d = (k - z) * pow(r, -1, n) % n
+        // >>> Q = d * G
+        let public_key = (
+            reversed(hex!(
+                "d63d21ebc77330f08845fc10a71d3ca536c6b9faaf47024178955fc5109a0db2"
+            )),
+            reversed(hex!(
+                "4c4f95bb2a6ae00638a6bab99c3a2f8ca300032e36d7335b3d012eb6b7d63f47"
+            )),
+        );
+        let z = reversed(hex!(
+            "48d36d820ccc1788d3ccb4b12dc547853426233334acf2ea2ab224c4f91c9297"
+        ));
+        let r = reversed(hex!(
+            "867698c8917c53c16bd7f77ed96a43757da51ef5bdee51e7d48353714cfbcc19"
+        ));
+
+        let mut s = [0; 32];
+        s[0] = 1;
+        assert!(verify(public_key, &z, &r, &s));
+
+        let mut s = to_bytes(N);
+        s[0] += 1;
+        assert!(!verify(public_key, &z, &r, &s));
+    }
+
+    #[test]
+    fn test_ecdsa_generator_public_key() {
+        // The public keys G and -G, for which the table of the multiplication
+        // contains G + G and G - G before offsetting.
+        //
+        // >>> k = 0x1a2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d5e6f708192a3b4c5d6e7f809
+        // >>> z = int.from_bytes(hashlib.sha256(b"bellman").digest(), "big")
+        // >>> r = (k * G).x % n
+        // >>> s = pow(k, -1, n) * (z + r * d) % n  # for d = 1 and d = n - 1
+        let z = reversed(hex!(
+            "48d36d820ccc1788d3ccb4b12dc547853426233334acf2ea2ab224c4f91c9297"
+        ));
+        let r = reversed(hex!(
+            "867698c8917c53c16bd7f77ed96a43757da51ef5bdee51e7d48353714cfbcc19"
+        ));
+
+        let s = reversed(hex!(
+            "ba71f10dba646d2b8c8f31ce31face94f2dbce93f67e142d9ad616db659b7635"
+        ));
+        assert!(verify((to_bytes(G.0), to_bytes(G.1)), &z, &r, &s));
+
+        let negated = reversed(hex!(
+            "b7c52588d95c3b9aa25b0403f1eef75702e84bb7597aabe663b82f6f04ef2777"
+        ));
+        let s = reversed(hex!(
+            "b24c7f65a995f5c24331ed7bfd68c26be3980b070fd77186d679b44cabee7235"
+        ));
+        assert!(verify((to_bytes(G.0), negated), &z, &r, &s));
+    }
+}
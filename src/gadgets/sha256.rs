@@ -3,10 +3,11 @@
 //!
 //! [SHA-256]: https://tools.ietf.org/html/rfc6234
 
-use super::boolean::Boolean;
+use super::boolean::{AllocatedBit, Boolean};
 use super::multieq::MultiEq;
+use super::num::AllocatedNum;
 use super::uint32::UInt32;
-use crate::{ConstraintSystem, SynthesisError};
+use crate::{ConstraintSystem, LinearCombination, SynthesisError};
 use ff::PrimeField;
 
 #[allow(clippy::unreadable_literal)]
@@ -51,34 +52,428 @@ where
 {
     assert!(input.len() % 8 == 0);
 
-    let mut padded = input.to_vec();
-    let plen = padded.len() as u64;
-    // append a single '1' bit
-    padded.push(Boolean::constant(true));
-    // append K '0' bits, where K is the minimum number >= 0 such that L + 1 + K + 64 is a multiple of 512
-    while (padded.len() + 64) % 512 != 0 {
-        padded.push(Boolean::constant(false));
+    let mut hasher = Sha256::new();
+    hasher.update(&mut cs, input)?;
+    hasher.finalize(cs)
+}
+
+/// An incremental SHA-256 hasher.
+///
+/// Input is buffered until a full block is available, so the message can be
+/// supplied in several parts. The hasher can also start from a midstate, so
+/// that a known prefix of the message is compressed outside the circuit.
+#[derive(Clone)]
+pub struct Sha256 {
+    state: Vec<UInt32>,
+    buffer: Vec<Boolean>,
+    // The length of the message so far, in bits
+    length: u64,
+    // The number of blocks compressed in the circuit, for namespacing
+    blocks: usize,
+}
+
+impl Default for Sha256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sha256 {
+    /// Creates a hasher for a new message.
+    pub fn new() -> Self {
+        Sha256 {
+            state: get_sha256_iv(),
+            buffer: vec![],
+            length: 0,
+            blocks: 0,
+        }
+    }
+
+    /// Creates a hasher that resumes from `state`, the hash value after
+    /// compressing the first `length` bytes of the message.
+    ///
+    /// `length` must be a multiple of the 64-byte block size. The state may
+    /// be constant or allocated, for instance to keep the prefix private.
+    pub fn from_midstate(state: &[UInt32], length: u64) -> Self {
+        assert_eq!(state.len(), 8);
+        assert!(length % 64 == 0);
+
+        Sha256 {
+            state: state.to_vec(),
+            buffer: vec![],
+            length: length * 8,
+            blocks: 0,
+        }
+    }
+
+    /// Creates a hasher for a message starting with the constant `prefix`.
+    ///
+    /// The full blocks of the prefix are compressed natively, and the rest is
+    /// buffered as constant bits.
+    pub fn with_prefix(prefix: &[u8]) -> Self {
+        let full = prefix.len() - prefix.len() % 64;
+
+        let mut state = IV;
+        for block in prefix[..full].chunks(64) {
+            compress_native(&mut state, block);
+        }
+
+        let mut hasher = Sha256::from_midstate(
+            &state
+                .iter()
+                .map(|&v| UInt32::constant(v))
+                .collect::<Vec<_>>(),
+            full as u64,
+        );
+        hasher.buffer = prefix[full..]
+            .iter()
+            .flat_map(|&byte| {
+                (0..8)
+                    .rev()
+                    .map(move |i| Boolean::constant((byte >> i) & 1 == 1))
+            })
+            .collect();
+        hasher.length += hasher.buffer.len() as u64;
+        hasher
+    }
+
+    /// Appends `input` to the message, compressing any full blocks.
+    pub fn update<Scalar, CS>(
+        &mut self,
+        mut cs: CS,
+        input: &[Boolean],
+    ) -> Result<(), SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        assert!(input.len() % 8 == 0);
+
+        self.length += input.len() as u64;
+        self.buffer.extend_from_slice(input);
+
+        while self.buffer.len() >= 512 {
+            let block: Vec<_> = self.buffer.drain(..512).collect();
+            self.compress(&mut cs, &block)?;
+        }
+
+        Ok(())
+    }
+
+    /// Pads the message and returns its hash.
+    pub fn finalize<Scalar, CS>(mut self, mut cs: CS) -> Result<Vec<Boolean>, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        let mut padded = std::mem::take(&mut self.buffer);
+        let plen = self.length;
+        // append a single '1' bit
+        padded.push(Boolean::constant(true));
+        // append K '0' bits, where K is the minimum number >= 0 such that L + 1 + K + 64 is a multiple of 512
+        while (padded.len() + 64) % 512 != 0 {
+            padded.push(Boolean::constant(false));
+        }
+        // append L as a 64-bit big-endian integer, making the total post-processed length a multiple of 512 bits
+        for b in (0..64).rev().map(|i| (plen >> i) & 1 == 1) {
+            padded.push(Boolean::constant(b));
+        }
+        assert!(padded.len() % 512 == 0);
+
+        for block in padded.chunks(512) {
+            self.compress(&mut cs, block)?;
+        }
+
+        Ok(self
+            .state
+            .into_iter()
+            .flat_map(|e| e.into_bits_be())
+            .collect())
+    }
+
+    fn compress<Scalar, CS>(&mut self, mut cs: CS, block: &[Boolean]) -> Result<(), SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        self.state = sha256_compression_function(
+            cs.namespace(|| format!("block {}", self.blocks)),
+            block,
+            &self.state,
+        )?;
+        self.blocks += 1;
+
+        Ok(())
     }
-    // append L as a 64-bit big-endian integer, making the total post-processed length a multiple of 512 bits
-    for b in (0..64).rev().map(|i| (plen >> i) & 1 == 1) {
-        padded.push(Boolean::constant(b));
+}
+
+/// Computes the SHA-256 hash of the first `length` bytes of `input`, where
+/// `length` is a witness of at most `input.len() / 8`.
+///
+/// The message is padded in the circuit, and every block that the padded
+/// message could span is compressed, so the cost depends only on the length
+/// of `input`. The hash value after the last block of the padded message is
+/// then selected as the output. The bits of `input` after the message are
+/// ignored.
+pub fn sha256_var_len<Scalar, CS>(
+    mut cs: CS,
+    input: &[Boolean],
+    length: &AllocatedNum<Scalar>,
+) -> Result<Vec<Boolean>, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    assert!(input.len() % 8 == 0);
+
+    let max_len = input.len() / 8;
+    // The blocks spanned by the longest message, with its padding
+    let num_blocks = (max_len + 9 + 63) / 64;
+    // The index of the last block of a message of `len` bytes
+    let last_block = |len: usize| (len + 8) / 64;
+    // The lengths of the messages whose last block is `block`
+    let lengths_ending_in =
+        |block: usize| (64 * block).saturating_sub(8)..=(64 * block + 55).min(max_len);
+
+    // `None` if the length is not known, and `Some(None)` if it is too long
+    let len_value = length
+        .get_value()
+        .map(|l| (0..=max_len).find(|&i| Scalar::from(i as u64) == l));
+
+    // Decompose the length into a one-hot vector e, where e[i] is set if the
+    // message is i bytes long. Everything else is linear in e.
+    let e = (0..=max_len)
+        .map(|i| {
+            AllocatedBit::alloc(
+                cs.namespace(|| format!("length is {}", i)),
+                len_value.map(|l| l == Some(i)),
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    cs.enforce(
+        || "length is one-hot",
+        |lc| e.iter().fold(lc, |lc, e| lc + e.get_variable()),
+        |lc| lc + CS::one(),
+        |lc| lc + CS::one(),
+    );
+    cs.enforce(
+        || "length decomposition",
+        |lc| {
+            e.iter().enumerate().fold(lc, |lc, (i, e)| {
+                lc + (Scalar::from(i as u64), e.get_variable())
+            })
+        },
+        |lc| lc + CS::one(),
+        |lc| lc + length.get_variable(),
+    );
+
+    // longer[i] is set if the message is longer than i bytes, so that
+    // longer[i] = longer[i - 1] - e[i] with longer[-1] = 1.
+    let mut longer = Vec::with_capacity(max_len);
+    let mut prev = CS::one();
+    for (i, e) in e.iter().enumerate().take(max_len) {
+        let bit = cs.alloc(
+            || format!("longer than {}", i),
+            || {
+                len_value
+                    .map(|l| {
+                        if l.map_or(false, |l| l > i) {
+                            Scalar::one()
+                        } else {
+                            Scalar::zero()
+                        }
+                    })
+                    .ok_or(SynthesisError::AssignmentMissing)
+            },
+        )?;
+        cs.enforce(
+            || format!("longer than {} is running sum", i),
+            |lc| lc + prev - e.get_variable(),
+            |lc| lc + CS::one(),
+            |lc| lc + bit,
+        );
+        longer.push(bit);
+        prev = bit;
+    }
+
+    // The padded message, for computing the witness
+    let input_value = input
+        .iter()
+        .map(|b| b.get_value())
+        .collect::<Option<Vec<_>>>();
+    let padded_value = len_value.zip(input_value).map(|(len, input)| {
+        let mut padded = vec![false; num_blocks * 512];
+        if let Some(len) = len {
+            padded[..len * 8].copy_from_slice(&input[..len * 8]);
+            padded[len * 8] = true;
+            let end = (last_block(len) + 1) * 512;
+            for (i, bit) in (end - 64..end).zip((0..64).rev()) {
+                padded[i] = ((len as u64 * 8) >> bit) & 1 == 1;
+            }
+        }
+        padded
+    });
+
+    let mut padded = Vec::with_capacity(num_blocks * 512);
+    for n in 0..num_blocks * 512 {
+        let cs = &mut cs.namespace(|| format!("padded bit {}", n));
+        let (i, k) = (n / 8, n % 8);
+        let offset = i % 64;
+
+        // The lengths for which this bit is part of the padding and set
+        let mut tail = vec![];
+        if k == 0 && i <= max_len {
+            // append a single '1' bit
+            tail.push(i);
+        }
+        if offset >= 56 {
+            // append L as a 64-bit big-endian integer in the last block
+            let bit = 63 - ((offset - 56) * 8 + k);
+            tail.extend(lengths_ending_in(i / 64).filter(|&l| ((l as u64 * 8) >> bit) & 1 == 1));
+        }
+
+        let value = padded_value.as_ref().map(|p| p[n]);
+        if i >= max_len {
+            padded.push(sum_into_boolean(
+                cs.namespace(|| "padding"),
+                &e,
+                &tail,
+                value,
+            )?);
+        } else {
+            // The bit is input[n] if the message is longer than i bytes, and
+            // padding otherwise.
+            let bit = AllocatedBit::alloc(cs.namespace(|| "bit"), value)?;
+            cs.enforce(
+                || "bit is message or padding",
+                |lc| lc + longer[i],
+                |_| input[n].lc(CS::one(), Scalar::one()),
+                |lc| lc + bit.get_variable() - &sum_bits(&e, &tail),
+            );
+            padded.push(Boolean::from(bit));
+        }
     }
-    assert!(padded.len() % 512 == 0);
 
     let mut cur = get_sha256_iv();
-    for (i, block) in padded.chunks(512).enumerate() {
-        cur = sha256_compression_function(cs.namespace(|| format!("block {}", i)), block, &cur)?;
+    let mut out: Vec<Boolean> = vec![Boolean::constant(false); 256];
+    for (j, block) in padded.chunks(512).enumerate() {
+        let cs = &mut cs.namespace(|| format!("block {}", j));
+        cur = sha256_compression_function(cs.namespace(|| "compression"), block, &cur)?;
+
+        // Accumulate the hash value if this is the last block. At most one
+        // block is selected, so the xor acts as an or.
+        let indices: Vec<_> = lengths_ending_in(j).collect();
+        let is_last = sum_into_boolean(
+            cs.namespace(|| "is last"),
+            &e,
+            &indices,
+            len_value.map(|l| l.map(last_block) == Some(j)),
+        )?;
+        for (b, (out, bit)) in out
+            .iter_mut()
+            .zip(cur.iter().flat_map(|e| e.clone().into_bits_be()))
+            .enumerate()
+        {
+            let cs = &mut cs.namespace(|| format!("output bit {}", b));
+            let selected = Boolean::and(cs.namespace(|| "select"), &is_last, &bit)?;
+            *out = Boolean::xor(cs.namespace(|| "accumulate"), out, &selected)?;
+        }
+    }
+
+    Ok(out)
+}
+
+/// Returns the sum of the bits of `e` at `indices`.
+fn sum_bits<Scalar: PrimeField>(
+    e: &[AllocatedBit],
+    indices: &[usize],
+) -> LinearCombination<Scalar> {
+    indices
+        .iter()
+        .fold(LinearCombination::zero(), |lc, &i| lc + e[i].get_variable())
+}
+
+/// Converts a sum of mutually exclusive bits of `e` into a `Boolean`.
+fn sum_into_boolean<Scalar, CS>(
+    mut cs: CS,
+    e: &[AllocatedBit],
+    indices: &[usize],
+    value: Option<bool>,
+) -> Result<Boolean, SynthesisError>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    Ok(match indices {
+        [] => Boolean::constant(false),
+        [i] => Boolean::from(e[*i].clone()),
+        _ => {
+            let bit = AllocatedBit::alloc(cs.namespace(|| "bit"), value)?;
+            cs.enforce(
+                || "bit is sum",
+                |_| sum_bits(e, indices),
+                |lc| lc + CS::one(),
+                |lc| lc + bit.get_variable(),
+            );
+            Boolean::from(bit)
+        }
+    })
+}
+
+/// Compresses a 64-byte block into `state` natively.
+fn compress_native(state: &mut [u32; 8], block: &[u8]) {
+    assert_eq!(block.len(), 64);
+
+    let mut w = [0u32; 64];
+    for (w, bytes) in w.iter_mut().zip(block.chunks(4)) {
+        *w = u32::from_be_bytes(bytes.try_into().unwrap());
+    }
+    for i in 16..64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[i - 7])
+            .wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for i in 0..64 {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let temp1 = h
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(ROUND_CONSTANTS[i])
+            .wrapping_add(w[i]);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let temp2 = s0.wrapping_add(maj);
+
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(temp1);
+        d = c;
+        c = b;
+        b = a;
+        a = temp1.wrapping_add(temp2);
     }
 
-    Ok(cur.into_iter().flat_map(|e| e.into_bits_be()).collect())
+    for (s, v) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *s = s.wrapping_add(v);
+    }
 }
 
 fn get_sha256_iv() -> Vec<UInt32> {
     IV.iter().map(|&v| UInt32::constant(v)).collect()
 }
 
+/// Compresses a 512-bit block of big-endian input bits into the hash value
+/// `current_hash_value`, returning the new hash value.
 #[allow(clippy::many_single_char_names)]
-fn sha256_compression_function<Scalar, CS>(
+pub fn sha256_compression_function<Scalar, CS>(
     cs: CS,
     input: &[Boolean],
     current_hash_value: &[UInt32],
@@ -273,7 +668,8 @@ where
 mod test {
     use super::*;
     use crate::gadgets::boolean::AllocatedBit;
-    use crate::gadgets::test::TestConstraintSystem;
+    use crate::gadgets::multipack::bytes_to_bits;
+    use crate::gadgets::test::{alloc_bits, bit_values, TestConstraintSystem};
     use bls12_381::Scalar;
     use hex_literal::hex;
     use rand_core::{RngCore, SeedableRng};
@@ -385,4 +781,151 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_incremental() {
+        use sha2::Digest;
+
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x3d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        for parts in [
+            [0, 0, 0],
+            [1, 2, 3],
+            [63, 1, 64],
+            [64, 0, 55],
+            [10, 100, 30],
+        ] {
+            let data: Vec<u8> = (0..parts.iter().sum::<usize>())
+                .map(|_| rng.next_u32() as u8)
+                .collect();
+
+            let mut cs = TestConstraintSystem::<Scalar>::new();
+            let input_bits = alloc_bits(cs.namespace(|| "input"), &bytes_to_bits(&data));
+
+            let mut hasher = Sha256::new();
+            let mut rest = &input_bits[..];
+            for (i, len) in parts.iter().enumerate() {
+                let (part, tail) = rest.split_at(len * 8);
+                hasher
+                    .update(cs.namespace(|| format!("update {}", i)), part)
+                    .unwrap();
+                rest = tail;
+            }
+            let r = hasher.finalize(cs.namespace(|| "finalize")).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(bit_values(&r), bytes_to_bits(&sha2::Sha256::digest(&data)));
+
+            // The hasher costs the same as hashing the message at once.
+            let mut expected_cs = TestConstraintSystem::<Scalar>::new();
+            let input_bits = alloc_bits(expected_cs.namespace(|| "input"), &bytes_to_bits(&data));
+            sha256(expected_cs.namespace(|| "sha256"), &input_bits).unwrap();
+            assert_eq!(cs.num_constraints(), expected_cs.num_constraints());
+        }
+    }
+
+    #[test]
+    fn test_midstate() {
+        use sha2::Digest;
+
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x3d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        for (prefix_len, input_len) in [(0, 32), (64, 0), (100, 32), (128, 64), (200, 1)] {
+            let prefix: Vec<u8> = (0..prefix_len).map(|_| rng.next_u32() as u8).collect();
+            let data: Vec<u8> = (0..input_len).map(|_| rng.next_u32() as u8).collect();
+            let expected = sha2::Sha256::digest([&prefix[..], &data[..]].concat());
+
+            let mut cs = TestConstraintSystem::<Scalar>::new();
+            let input_bits = alloc_bits(cs.namespace(|| "input"), &bytes_to_bits(&data));
+            let mut hasher = Sha256::with_prefix(&prefix);
+            hasher
+                .update(cs.namespace(|| "update"), &input_bits)
+                .unwrap();
+            let r = hasher.finalize(cs.namespace(|| "finalize")).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(bit_values(&r), bytes_to_bits(&expected));
+
+            // Start from an allocated midstate of the full blocks of the
+            // prefix, hashing the rest of it in the circuit.
+            let full = prefix_len - prefix_len % 64;
+            let mut state = IV;
+            for block in prefix[..full].chunks(64) {
+                compress_native(&mut state, block);
+            }
+
+            let mut cs = TestConstraintSystem::<Scalar>::new();
+            let midstate = state
+                .iter()
+                .enumerate()
+                .map(|(i, &v)| UInt32::alloc(cs.namespace(|| format!("state {}", i)), Some(v)))
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            let rest = alloc_bits(cs.namespace(|| "rest"), &bytes_to_bits(&prefix[full..]));
+            let input_bits = alloc_bits(cs.namespace(|| "input"), &bytes_to_bits(&data));
+            let mut hasher = Sha256::from_midstate(&midstate, full as u64);
+            hasher
+                .update(cs.namespace(|| "update rest"), &rest)
+                .unwrap();
+            hasher
+                .update(cs.namespace(|| "update"), &input_bits)
+                .unwrap();
+            let r = hasher.finalize(cs.namespace(|| "finalize")).unwrap();
+
+            assert!(cs.is_satisfied());
+            assert_eq!(bit_values(&r), bytes_to_bits(&expected));
+        }
+    }
+
+    #[test]
+    fn test_var_len() {
+        use sha2::Digest;
+
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x3d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+
+        for (max_len, lengths) in [
+            (0, &[0][..]),
+            (3, &[0, 3]),
+            (64, &[0, 1, 55, 56, 63, 64]),
+            (130, &[0, 54, 55, 56, 119, 120, 128, 130]),
+            (300, &[0, 119, 183, 184, 247, 248, 300]),
+        ] {
+            let data: Vec<u8> = (0..max_len).map(|_| rng.next_u32() as u8).collect();
+
+            for &len in lengths {
+                let mut cs = TestConstraintSystem::<Scalar>::new();
+                let input_bits = alloc_bits(cs.namespace(|| "input"), &bytes_to_bits(&data));
+                let length =
+                    AllocatedNum::alloc(cs.namespace(|| "length"), || Ok(Scalar::from(len as u64)))
+                        .unwrap();
+
+                let r = sha256_var_len(cs.namespace(|| "sha256"), &input_bits, &length).unwrap();
+
+                assert!(cs.is_satisfied());
+                assert_eq!(
+                    bit_values(&r),
+                    bytes_to_bits(&sha2::Sha256::digest(&data[..len]))
+                );
+            }
+
+            // The length can't exceed the size of the buffer.
+            let mut cs = TestConstraintSystem::<Scalar>::new();
+            let input_bits = alloc_bits(cs.namespace(|| "input"), &bytes_to_bits(&data));
+            let length = AllocatedNum::alloc(cs.namespace(|| "length"), || {
+                Ok(Scalar::from(max_len as u64 + 1))
+            })
+            .unwrap();
+            sha256_var_len(cs.namespace(|| "sha256"), &input_bits, &length).unwrap();
+            assert!(!cs.is_satisfied());
+        }
+    }
 }
@@ -2,14 +2,15 @@
 
 use ff::PrimeField;
 
+#[cfg(test)]
+use super::boolean::{AllocatedBit, Boolean};
+use crate::lc::proc_lc;
 use crate::{ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
 
 use std::collections::HashMap;
 use std::fmt::Write;
 
 use byteorder::{BigEndian, ByteOrder};
-use std::cmp::Ordering;
-use std::collections::BTreeMap;
 
 use blake2s_simd::{Params as Blake2sParams, State as Blake2sState};
 
@@ -36,58 +37,6 @@ pub struct TestConstraintSystem<Scalar: PrimeField> {
     aux: Vec<(Scalar, String)>,
 }
 
-#[derive(Clone, Copy)]
-struct OrderedVariable(Variable);
-
-impl Eq for OrderedVariable {}
-impl PartialEq for OrderedVariable {
-    fn eq(&self, other: &OrderedVariable) -> bool {
-        match (self.0.get_unchecked(), other.0.get_unchecked()) {
-            (Index::Input(ref a), Index::Input(ref b)) => a == b,
-            (Index::Aux(ref a), Index::Aux(ref b)) => a == b,
-            _ => false,
-        }
-    }
-}
-impl PartialOrd for OrderedVariable {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-impl Ord for OrderedVariable {
-    fn cmp(&self, other: &Self) -> Ordering {
-        match (self.0.get_unchecked(), other.0.get_unchecked()) {
-            (Index::Input(ref a), Index::Input(ref b)) => a.cmp(b),
-            (Index::Aux(ref a), Index::Aux(ref b)) => a.cmp(b),
-            (Index::Input(_), Index::Aux(_)) => Ordering::Less,
-            (Index::Aux(_), Index::Input(_)) => Ordering::Greater,
-        }
-    }
-}
-
-fn proc_lc<Scalar: PrimeField>(terms: &[(Variable, Scalar)]) -> BTreeMap<OrderedVariable, Scalar> {
-    let mut map = BTreeMap::new();
-    for &(var, coeff) in terms {
-        map.entry(OrderedVariable(var))
-            .or_insert_with(Scalar::zero)
-            .add_assign(&coeff);
-    }
-
-    // Remove terms that have a zero coefficient to normalize
-    let mut to_remove = vec![];
-    for (var, coeff) in map.iter() {
-        if coeff.is_zero_vartime() {
-            to_remove.push(*var);
-        }
-    }
-
-    for var in to_remove {
-        map.remove(&var);
-    }
-
-    map
-}
-
 fn hash_lc<Scalar: PrimeField>(terms: &[(Variable, Scalar)], h: &mut Blake2sState) {
     let map = proc_lc::<Scalar>(terms);
 
@@ -425,6 +374,34 @@ impl<Scalar: PrimeField> ConstraintSystem<Scalar> for TestConstraintSystem<Scala
     }
 }
 
+/// Allocates a witnessed boolean for each of `bits`, for feeding test vectors
+/// to gadgets. Byte strings can be converted with [`bytes_to_bits`] or
+/// [`bytes_to_bits_le`], depending on the bit order the gadget expects.
+///
+/// [`bytes_to_bits`]: crate::gadgets::multipack::bytes_to_bits
+/// [`bytes_to_bits_le`]: crate::gadgets::multipack::bytes_to_bits_le
+#[cfg(test)]
+pub(crate) fn alloc_bits<Scalar, CS>(mut cs: CS, bits: &[bool]) -> Vec<Boolean>
+where
+    Scalar: PrimeField,
+    CS: ConstraintSystem<Scalar>,
+{
+    bits.iter()
+        .enumerate()
+        .map(|(i, &bit)| {
+            AllocatedBit::alloc(cs.namespace(|| format!("bit {}", i)), Some(bit))
+                .unwrap()
+                .into()
+        })
+        .collect()
+}
+
+/// Returns the values of `bits`, which must all be known.
+#[cfg(test)]
+pub(crate) fn bit_values(bits: &[Boolean]) -> Vec<bool> {
+    bits.iter().map(|b| b.get_value().unwrap()).collect()
+}
+
 #[test]
 fn test_cs() {
     use bls12_381::Scalar;
@@ -0,0 +1,910 @@
+//! Circuit representations of fixed-width unsigned integers.
+//!
+//! [`UInt8`], [`UInt16`] and [`UInt64`] (and `UInt<u32>`, which unlike
+//! [`UInt32`] is not tied to SHA-256 and BLAKE2s) share the implementation of
+//! [`UInt`], which is generic over the native integer type.
+//!
+//! [`UInt32`]: super::uint32::UInt32
+
+use std::fmt;
+
+use ff::PrimeField;
+
+use crate::{ConstraintSystem, LinearCombination, SynthesisError};
+
+use super::boolean::{AllocatedBit, Boolean};
+use super::multieq::MultiEq;
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for u8 {}
+    impl Sealed for u16 {}
+    impl Sealed for u32 {}
+    impl Sealed for u64 {}
+}
+
+/// A native unsigned integer type that can be represented by [`UInt`].
+///
+/// This trait is sealed, and implemented for `u8`, `u16`, `u32` and `u64`.
+pub trait UIntValue: private::Sealed + Copy + Eq + Ord + fmt::Debug {
+    /// The width of the type in bits.
+    const BITS: usize;
+
+    /// Converts the value into a `u128`.
+    fn to_u128(self) -> u128;
+
+    /// Converts the low `BITS` bits of `value` into a value of this type.
+    fn truncate(value: u128) -> Self;
+}
+
+macro_rules! impl_uint_value {
+    ($($t:ty),*) => {
+        $(
+            impl UIntValue for $t {
+                const BITS: usize = <$t>::BITS as usize;
+
+                fn to_u128(self) -> u128 {
+                    u128::from(self)
+                }
+
+                fn truncate(value: u128) -> Self {
+                    value as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_uint_value!(u8, u16, u32, u64);
+
+/// Represents an interpretation of `T::BITS` `Boolean` objects as an unsigned
+/// integer of type `T`.
+#[derive(Clone)]
+pub struct UInt<T: UIntValue> {
+    // Least significant bit first
+    bits: Vec<Boolean>,
+    value: Option<T>,
+}
+
+/// Circuit representation of a [`u8`].
+pub type UInt8 = UInt<u8>;
+
+/// Circuit representation of a [`u16`].
+pub type UInt16 = UInt<u16>;
+
+/// Circuit representation of a [`u64`].
+pub type UInt64 = UInt<u64>;
+
+/// Converts `value` into a field element.
+fn field_from_u128<Scalar: PrimeField>(value: u128) -> Scalar {
+    let hi = Scalar::from((value >> 64) as u64);
+    let lo = Scalar::from(value as u64);
+    hi * Scalar::from(1 << 32).square() + lo
+}
+
+/// Returns the number of bits needed to represent `value`.
+fn bit_length(value: u128) -> usize {
+    (128 - value.leading_zeros()) as usize
+}
+
+impl<T: UIntValue> UInt<T> {
+    /// Construct a constant `UInt` from a native value.
+    pub fn constant(value: T) -> Self {
+        let v = value.to_u128();
+
+        UInt {
+            bits: (0..T::BITS)
+                .map(|i| Boolean::constant((v >> i) & 1 == 1))
+                .collect(),
+            value: Some(value),
+        }
+    }
+
+    /// Allocate a `UInt` in the constraint system.
+    pub fn alloc<Scalar, CS>(mut cs: CS, value: Option<T>) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        let bits = (0..T::BITS)
+            .map(|i| {
+                Ok(Boolean::from(AllocatedBit::alloc(
+                    cs.namespace(|| format!("allocated bit {}", i)),
+                    value.map(|v| (v.to_u128() >> i) & 1 == 1),
+                )?))
+            })
+            .collect::<Result<Vec<_>, SynthesisError>>()?;
+
+        Ok(UInt { bits, value })
+    }
+
+    /// Returns the value of this integer, if it is known.
+    pub fn get_value(&self) -> Option<T> {
+        self.value
+    }
+
+    /// Returns the bits of this integer, least significant first.
+    pub fn bits(&self) -> &[Boolean] {
+        &self.bits
+    }
+
+    /// Turns this `UInt` into its bits, least significant first.
+    pub fn into_bits(self) -> Vec<Boolean> {
+        self.bits
+    }
+
+    /// Turns this `UInt` into its bits, most significant first.
+    pub fn into_bits_be(self) -> Vec<Boolean> {
+        let mut ret = self.bits;
+        ret.reverse();
+        ret
+    }
+
+    /// Converts `T::BITS` bits, least significant first, into a `UInt`.
+    pub fn from_bits(bits: &[Boolean]) -> Self {
+        assert_eq!(bits.len(), T::BITS);
+
+        let value = bits
+            .iter()
+            .rev()
+            .try_fold(0u128, |acc, b| {
+                b.get_value().map(|b| (acc << 1) | b as u128)
+            })
+            .map(T::truncate);
+
+        UInt {
+            bits: bits.to_vec(),
+            value,
+        }
+    }
+
+    /// Converts `T::BITS` bits, most significant first, into a `UInt`.
+    pub fn from_bits_be(bits: &[Boolean]) -> Self {
+        let mut bits = bits.to_vec();
+        bits.reverse();
+        Self::from_bits(&bits)
+    }
+
+    /// Returns a linear combination equal to this integer.
+    fn lc<Scalar: PrimeField, CS: ConstraintSystem<Scalar>>(&self) -> LinearCombination<Scalar> {
+        let mut lc = LinearCombination::zero();
+        let mut coeff = Scalar::one();
+        for bit in &self.bits {
+            lc = lc + &bit.lc(CS::one(), coeff);
+            coeff = coeff.double();
+        }
+        lc
+    }
+
+    fn is_constant(&self) -> bool {
+        self.bits.iter().all(Boolean::is_constant)
+    }
+
+    /// Allocates the low `num_bits` bits of `value`, returning them along with
+    /// a linear combination equal to their sum.
+    fn alloc_bits<Scalar, CS>(
+        mut cs: CS,
+        num_bits: usize,
+        value: Option<u128>,
+    ) -> Result<(Vec<Boolean>, LinearCombination<Scalar>), SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        let mut bits = Vec::with_capacity(num_bits);
+        let mut lc = LinearCombination::zero();
+        let mut coeff = Scalar::one();
+        for i in 0..num_bits {
+            let bit = AllocatedBit::alloc(
+                cs.namespace(|| format!("result bit {}", i)),
+                value.map(|v| (v >> i) & 1 == 1),
+            )?;
+            lc = lc + (coeff, bit.get_variable());
+            bits.push(Boolean::from(bit));
+            coeff = coeff.double();
+        }
+
+        Ok((bits, lc))
+    }
+
+    pub fn rotl(&self, by: usize) -> Self {
+        self.rotr(T::BITS - by % T::BITS)
+    }
+
+    pub fn rotr(&self, by: usize) -> Self {
+        let by = by % T::BITS;
+
+        let bits = self
+            .bits
+            .iter()
+            .skip(by)
+            .chain(self.bits.iter())
+            .take(T::BITS)
+            .cloned()
+            .collect();
+
+        UInt {
+            bits,
+            value: self.value.map(|v| {
+                let v = v.to_u128();
+                T::truncate((v >> by) | (v << (T::BITS - by)))
+            }),
+        }
+    }
+
+    /// Shifts this integer left by `by` bits, which must be less than
+    /// `T::BITS`.
+    pub fn shl(&self, by: usize) -> Self {
+        assert!(by < T::BITS);
+
+        let fill = Boolean::constant(false);
+
+        let bits = Some(&fill)
+            .into_iter()
+            .cycle()
+            .take(by) // The low bits are zeros
+            .chain(self.bits.iter())
+            .take(T::BITS) // Drop the bits that are shifted out
+            .cloned()
+            .collect();
+
+        UInt {
+            bits,
+            value: self.value.map(|v| T::truncate(v.to_u128() << by)),
+        }
+    }
+
+    /// Shifts this integer right by `by` bits, which must be less than
+    /// `T::BITS`.
+    pub fn shr(&self, by: usize) -> Self {
+        assert!(by < T::BITS);
+
+        let fill = Boolean::constant(false);
+
+        let bits = self
+            .bits
+            .iter() // The bits are least significant first
+            .skip(by) // Skip the bits that will be lost during the shift
+            .chain(Some(&fill).into_iter().cycle()) // Rest will be zeros
+            .take(T::BITS)
+            .cloned()
+            .collect();
+
+        UInt {
+            bits,
+            value: self.value.map(|v| T::truncate(v.to_u128() >> by)),
+        }
+    }
+
+    /// Applies `circuit_fn` to each pair of bits of this integer and `other`.
+    fn bitwise<Scalar, CS, F, U>(
+        &self,
+        mut cs: CS,
+        other: &Self,
+        value_fn: F,
+        circuit_fn: U,
+    ) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+        F: Fn(u128, u128) -> u128,
+        U: Fn(&mut CS, usize, &Boolean, &Boolean) -> Result<Boolean, SynthesisError>,
+    {
+        let value = match (self.value, other.value) {
+            (Some(a), Some(b)) => Some(T::truncate(value_fn(a.to_u128(), b.to_u128()))),
+            _ => None,
+        };
+
+        let bits = self
+            .bits
+            .iter()
+            .zip(other.bits.iter())
+            .enumerate()
+            .map(|(i, (a, b))| circuit_fn(&mut cs, i, a, b))
+            .collect::<Result<_, _>>()?;
+
+        Ok(UInt { bits, value })
+    }
+
+    /// XOR this `UInt` with another `UInt`
+    pub fn xor<Scalar, CS>(&self, cs: CS, other: &Self) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        self.bitwise(
+            cs,
+            other,
+            |a, b| a ^ b,
+            |cs, i, a, b| Boolean::xor(cs.namespace(|| format!("xor of bit {}", i)), a, b),
+        )
+    }
+
+    /// AND this `UInt` with another `UInt`
+    pub fn and<Scalar, CS>(&self, cs: CS, other: &Self) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        self.bitwise(
+            cs,
+            other,
+            |a, b| a & b,
+            |cs, i, a, b| Boolean::and(cs.namespace(|| format!("and of bit {}", i)), a, b),
+        )
+    }
+
+    /// OR this `UInt` with another `UInt`
+    pub fn or<Scalar, CS>(&self, cs: CS, other: &Self) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        self.bitwise(
+            cs,
+            other,
+            |a, b| a | b,
+            |cs, i, a, b| {
+                // a | b = !(!a & !b)
+                Boolean::and(
+                    cs.namespace(|| format!("or of bit {}", i)),
+                    &a.not(),
+                    &b.not(),
+                )
+                .map(|nor| nor.not())
+            },
+        )
+    }
+
+    /// Returns the bitwise complement of this `UInt`, which costs no
+    /// constraints.
+    pub fn not(&self) -> Self {
+        UInt {
+            bits: self.bits.iter().map(Boolean::not).collect(),
+            value: self.value.map(|v| T::truncate(!v.to_u128())),
+        }
+    }
+
+    fn triop<Scalar, CS, F, U>(
+        mut cs: CS,
+        a: &Self,
+        b: &Self,
+        c: &Self,
+        tri_fn: F,
+        circuit_fn: U,
+    ) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+        F: Fn(u128, u128, u128) -> u128,
+        U: Fn(&mut CS, usize, &Boolean, &Boolean, &Boolean) -> Result<Boolean, SynthesisError>,
+    {
+        let value = match (a.value, b.value, c.value) {
+            (Some(a), Some(b), Some(c)) => {
+                Some(T::truncate(tri_fn(a.to_u128(), b.to_u128(), c.to_u128())))
+            }
+            _ => None,
+        };
+
+        let bits = a
+            .bits
+            .iter()
+            .zip(b.bits.iter())
+            .zip(c.bits.iter())
+            .enumerate()
+            .map(|(i, ((a, b), c))| circuit_fn(&mut cs, i, a, b, c))
+            .collect::<Result<_, _>>()?;
+
+        Ok(UInt { bits, value })
+    }
+
+    /// Compute the `maj` value (a and b) xor (a and c) xor (b and c)
+    /// of the SHA-2 hash functions.
+    pub fn sha2_maj<Scalar, CS>(
+        cs: CS,
+        a: &Self,
+        b: &Self,
+        c: &Self,
+    ) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        Self::triop(
+            cs,
+            a,
+            b,
+            c,
+            |a, b, c| (a & b) ^ (a & c) ^ (b & c),
+            |cs, i, a, b, c| Boolean::sha256_maj(cs.namespace(|| format!("maj {}", i)), a, b, c),
+        )
+    }
+
+    /// Compute the `ch` value `(a and b) xor ((not a) and c)`
+    /// of the SHA-2 hash functions.
+    pub fn sha2_ch<Scalar, CS>(cs: CS, a: &Self, b: &Self, c: &Self) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        Self::triop(
+            cs,
+            a,
+            b,
+            c,
+            |a, b, c| (a & b) ^ ((!a) & c),
+            |cs, i, a, b, c| Boolean::sha256_ch(cs.namespace(|| format!("ch {}", i)), a, b, c),
+        )
+    }
+
+    /// Perform modular addition of this `UInt` and another `UInt`.
+    pub fn add<Scalar, CS>(&self, cs: CS, other: &Self) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        Self::addmany(cs, &[self.clone(), other.clone()])
+    }
+
+    /// Perform modular addition of several `UInt` objects.
+    ///
+    /// This costs one constraint, plus a boolean constraint for each bit of
+    /// the full sum. The full sum must fit in the capacity of the field.
+    pub fn addmany<Scalar, CS>(cs: CS, operands: &[Self]) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        Self::addmany_with(cs, operands, |cs, _, lc, result_lc| {
+            cs.enforce(|| "sum", |_| lc, |lc| lc + CS::one(), |_| result_lc);
+        })
+    }
+
+    /// Perform modular addition of several `UInt` objects, packing the
+    /// equality of the sum and result into the constraints of a [`MultiEq`]
+    /// along with other additions.
+    pub fn addmany_multieq<Scalar, CS, M>(cs: M, operands: &[Self]) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+        M: ConstraintSystem<Scalar, Root = MultiEq<Scalar, CS>>,
+    {
+        Self::addmany_with(cs, operands, |cs, num_bits, lc, result_lc| {
+            cs.get_root().enforce_equal(num_bits, &lc, &result_lc);
+        })
+    }
+
+    fn addmany_with<Scalar, CS, E>(
+        mut cs: CS,
+        operands: &[Self],
+        enforce_equal: E,
+    ) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+        E: FnOnce(&mut CS, usize, LinearCombination<Scalar>, LinearCombination<Scalar>),
+    {
+        assert!(!operands.is_empty());
+
+        // Compute the maximum value of the sum so we allocate enough bits for
+        // the result
+        let max_value = (operands.len() as u128)
+            .checked_mul((1 << T::BITS) - 1)
+            .expect("too many operands");
+        let num_bits = bit_length(max_value);
+        assert!(num_bits <= Scalar::CAPACITY as usize);
+
+        let result_value = operands
+            .iter()
+            .try_fold(0u128, |acc, op| op.value.map(|v| acc + v.to_u128()));
+        let modular_value = result_value.map(T::truncate);
+
+        if let (true, Some(result)) = (operands.iter().all(Self::is_constant), modular_value) {
+            // We can just return a constant, rather than
+            // unpacking the result into allocated bits.
+            return Ok(Self::constant(result));
+        }
+
+        let lc = operands
+            .iter()
+            .fold(LinearCombination::zero(), |lc, op| lc + &op.lc::<_, CS>());
+        let (mut bits, result_lc) = Self::alloc_bits(&mut cs, num_bits, result_value)?;
+
+        // Enforce equality between the sum and result
+        enforce_equal(&mut cs, num_bits, lc, result_lc);
+
+        // Discard carry bits that we don't care about
+        bits.truncate(T::BITS);
+
+        Ok(UInt {
+            bits,
+            value: modular_value,
+        })
+    }
+
+    /// Perform modular multiplication of this `UInt` and another `UInt`.
+    ///
+    /// This costs one constraint, plus a boolean constraint for each bit of
+    /// the full product.
+    pub fn mul<Scalar, CS>(&self, mut cs: CS, other: &Self) -> Result<Self, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        let num_bits = 2 * T::BITS;
+        assert!(num_bits <= Scalar::CAPACITY as usize);
+
+        let result_value = match (self.value, other.value) {
+            (Some(a), Some(b)) => Some(a.to_u128() * b.to_u128()),
+            _ => None,
+        };
+        let modular_value = result_value.map(T::truncate);
+
+        if let (true, Some(result)) = (self.is_constant() && other.is_constant(), modular_value) {
+            return Ok(Self::constant(result));
+        }
+
+        let (mut bits, result_lc) = Self::alloc_bits(&mut cs, num_bits, result_value)?;
+
+        cs.enforce(
+            || "product",
+            |_| self.lc::<_, CS>(),
+            |_| other.lc::<_, CS>(),
+            |_| result_lc,
+        );
+
+        // Discard the high half of the product
+        bits.truncate(T::BITS);
+
+        Ok(UInt {
+            bits,
+            value: modular_value,
+        })
+    }
+
+    /// Returns whether this `UInt` is equal to another `UInt`.
+    pub fn is_eq<Scalar, CS>(&self, mut cs: CS, other: &Self) -> Result<Boolean, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        let value = match (self.value, other.value) {
+            (Some(a), Some(b)) => Some(a == b),
+            _ => None,
+        };
+
+        if let (true, Some(value)) = (self.is_constant() && other.is_constant(), value) {
+            return Ok(Boolean::constant(value));
+        }
+
+        // The difference is zero exactly when the integers are equal, which
+        // we test by requiring an inverse of any non-zero difference.
+        let diff = |lc: LinearCombination<Scalar>| lc + &self.lc::<_, CS>() - &other.lc::<_, CS>();
+        let diff_value = match (self.value, other.value) {
+            (Some(a), Some(b)) => Some(
+                field_from_u128::<Scalar>(a.to_u128()) - field_from_u128::<Scalar>(b.to_u128()),
+            ),
+            _ => None,
+        };
+
+        let equal = AllocatedBit::alloc(cs.namespace(|| "equal"), value)?;
+        let inv = cs.alloc(
+            || "inverse of difference",
+            || {
+                let diff = diff_value.ok_or(SynthesisError::AssignmentMissing)?;
+                Ok(diff.invert().unwrap_or_else(Scalar::zero))
+            },
+        )?;
+
+        // diff * inv = 1 - equal
+        cs.enforce(
+            || "inverse",
+            diff,
+            |lc| lc + inv,
+            |lc| lc + CS::one() - equal.get_variable(),
+        );
+
+        // diff * equal = 0
+        cs.enforce(
+            || "zero if equal",
+            diff,
+            |lc| lc + equal.get_variable(),
+            |lc| lc,
+        );
+
+        Ok(Boolean::from(equal))
+    }
+
+    /// Returns whether this `UInt` is less than another `UInt`.
+    ///
+    /// This costs one constraint, plus `T::BITS + 1` boolean constraints.
+    pub fn is_lt<Scalar, CS>(&self, mut cs: CS, other: &Self) -> Result<Boolean, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        let num_bits = T::BITS + 1;
+        assert!(num_bits <= Scalar::CAPACITY as usize);
+
+        // 2^BITS + self - other has its top bit set exactly when self >= other.
+        let offset = 1u128 << T::BITS;
+        let value = match (self.value, other.value) {
+            (Some(a), Some(b)) => Some(offset + a.to_u128() - b.to_u128()),
+            _ => None,
+        };
+
+        if let (true, Some(value)) = (self.is_constant() && other.is_constant(), value) {
+            return Ok(Boolean::constant(value < offset));
+        }
+
+        let (bits, result_lc) = Self::alloc_bits(&mut cs, num_bits, value)?;
+
+        cs.enforce(
+            || "difference",
+            |lc| {
+                lc + (field_from_u128(offset), CS::one()) + &self.lc::<_, CS>()
+                    - &other.lc::<_, CS>()
+            },
+            |lc| lc + CS::one(),
+            |_| result_lc,
+        );
+
+        Ok(bits[T::BITS].not())
+    }
+
+    /// Returns whether this `UInt` is less than or equal to another `UInt`.
+    pub fn is_le<Scalar, CS>(&self, cs: CS, other: &Self) -> Result<Boolean, SynthesisError>
+    where
+        Scalar: PrimeField,
+        CS: ConstraintSystem<Scalar>,
+    {
+        other.is_lt(cs, self).map(|gt| gt.not())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{UInt, UInt16, UInt64, UInt8, UIntValue};
+    use crate::gadgets::boolean::Boolean;
+    use crate::gadgets::test::*;
+    use crate::ConstraintSystem;
+    use bls12_381::Scalar;
+    use ff::Field;
+    use rand_core::{RngCore, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+
+    fn rng() -> XorShiftRng {
+        XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ])
+    }
+
+    fn random<T: UIntValue>(rng: &mut XorShiftRng) -> T {
+        T::truncate(u128::from(rng.next_u64()))
+    }
+
+    fn check_value<T: UIntValue>(x: &UInt<T>, expected: T) {
+        assert_eq!(x.get_value(), Some(expected));
+        let expected = expected.to_u128();
+        for (i, bit) in x.bits().iter().enumerate() {
+            assert_eq!(bit.get_value(), Some((expected >> i) & 1 == 1));
+        }
+    }
+
+    fn check_bitwise<T: UIntValue>() {
+        let mut rng = rng();
+
+        for _ in 0..20 {
+            let mut cs = TestConstraintSystem::<Scalar>::new();
+
+            let (a, b, c): (T, T, T) = (random(&mut rng), random(&mut rng), random(&mut rng));
+            let a_bit = UInt::alloc(cs.namespace(|| "a"), Some(a)).unwrap();
+            let b_bit = UInt::constant(b);
+            let c_bit = UInt::alloc(cs.namespace(|| "c"), Some(c)).unwrap();
+
+            let xor = a_bit.xor(cs.namespace(|| "xor"), &b_bit).unwrap();
+            let and = a_bit.and(cs.namespace(|| "and"), &c_bit).unwrap();
+            let or = b_bit.or(cs.namespace(|| "or"), &c_bit).unwrap();
+            let not = a_bit.not();
+
+            assert!(cs.is_satisfied());
+
+            let (a, b, c) = (a.to_u128(), b.to_u128(), c.to_u128());
+            check_value(&xor, T::truncate(a ^ b));
+            check_value(&and, T::truncate(a & c));
+            check_value(&or, T::truncate(b | c));
+            check_value(&not, T::truncate(!a));
+        }
+    }
+
+    #[test]
+    fn test_uint_bitwise() {
+        check_bitwise::<u8>();
+        check_bitwise::<u16>();
+        check_bitwise::<u32>();
+        check_bitwise::<u64>();
+    }
+
+    #[test]
+    fn test_uint_rotations_and_shifts() {
+        let mut rng = rng();
+
+        for _ in 0..20 {
+            let v = rng.next_u64();
+            let x = UInt64::constant(v);
+
+            for i in 0..64 {
+                check_value(&x.rotr(i), v.rotate_right(i as u32));
+                check_value(&x.rotl(i), v.rotate_left(i as u32));
+                check_value(&x.shr(i), v >> i);
+                check_value(&x.shl(i), v << i);
+            }
+
+            let v = v as u8;
+            let x = UInt8::constant(v);
+            for i in 0..8 {
+                check_value(&x.rotr(i), v.rotate_right(i as u32));
+                check_value(&x.rotl(i), v.rotate_left(i as u32));
+                check_value(&x.shr(i), v >> i);
+                check_value(&x.shl(i), v << i);
+            }
+        }
+    }
+
+    #[test]
+    fn test_uint_bits_roundtrip() {
+        let mut rng = rng();
+
+        for _ in 0..100 {
+            let v = rng.next_u32() as u16;
+            let bits = (0..16)
+                .map(|i| Boolean::constant((v >> i) & 1 == 1))
+                .collect::<Vec<_>>();
+
+            check_value(&UInt16::from_bits(&bits), v);
+            let mut be = bits.clone();
+            be.reverse();
+            check_value(&UInt16::from_bits_be(&be), v);
+            check_value(&UInt16::from_bits(&UInt16::constant(v).into_bits()), v);
+            check_value(
+                &UInt16::from_bits_be(&UInt16::constant(v).into_bits_be()),
+                v,
+            );
+        }
+    }
+
+    #[test]
+    fn test_uint64_addmany() {
+        let mut rng = rng();
+
+        for _ in 0..100 {
+            let mut cs = TestConstraintSystem::<Scalar>::new();
+
+            let values: Vec<u64> = (0..20).map(|_| rng.next_u64()).collect();
+            let operands = values
+                .iter()
+                .enumerate()
+                .map(|(i, &v)| {
+                    if i % 3 == 0 {
+                        UInt64::constant(v)
+                    } else {
+                        UInt64::alloc(cs.namespace(|| format!("operand {}", i)), Some(v)).unwrap()
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            let r = UInt64::addmany(cs.namespace(|| "addition"), &operands).unwrap();
+            let expected = values.iter().fold(0u64, |acc, v| acc.wrapping_add(*v));
+
+            assert!(cs.is_satisfied());
+            check_value(&r, expected);
+
+            // Flip a bit and see if the addition constraint still works
+            if cs.get("addition/result bit 0/boolean").is_zero().into() {
+                cs.set("addition/result bit 0/boolean", Field::one());
+            } else {
+                cs.set("addition/result bit 0/boolean", Field::zero());
+            }
+
+            assert!(!cs.is_satisfied());
+        }
+    }
+
+    #[test]
+    fn test_uint_add_constants() {
+        let mut cs = TestConstraintSystem::<Scalar>::new();
+
+        let a = UInt8::constant(200);
+        let b = UInt8::constant(100);
+        let r = a.add(cs.namespace(|| "add"), &b).unwrap();
+
+        check_value(&r, 44);
+        assert!(r.bits().iter().all(Boolean::is_constant));
+        assert_eq!(cs.num_constraints(), 0);
+    }
+
+    #[test]
+    fn test_uint_mul() {
+        let mut rng = rng();
+
+        for _ in 0..100 {
+            let mut cs = TestConstraintSystem::<Scalar>::new();
+
+            let (a, b) = (rng.next_u64(), rng.next_u64());
+            let a_bit = UInt64::alloc(cs.namespace(|| "a"), Some(a)).unwrap();
+            let b_bit = UInt64::alloc(cs.namespace(|| "b"), Some(b)).unwrap();
+            let r = a_bit.mul(cs.namespace(|| "mul"), &b_bit).unwrap();
+
+            assert!(cs.is_satisfied());
+            check_value(&r, a.wrapping_mul(b));
+
+            // A different low bit of the product is rejected.
+            if cs.get("mul/result bit 3/boolean").is_zero().into() {
+                cs.set("mul/result bit 3/boolean", Field::one());
+            } else {
+                cs.set("mul/result bit 3/boolean", Field::zero());
+            }
+            assert!(!cs.is_satisfied());
+
+            let (a, b) = (a as u16, b as u16);
+            let a_bit = UInt16::alloc(cs.namespace(|| "a16"), Some(a)).unwrap();
+            let r = a_bit
+                .mul(cs.namespace(|| "mul16"), &UInt16::constant(b))
+                .unwrap();
+            check_value(&r, a.wrapping_mul(b));
+        }
+    }
+
+    #[test]
+    fn test_uint_comparisons() {
+        let mut rng = rng();
+
+        let values = [0u8, 1, 127, 128, 254, 255];
+        for &a in &values {
+            for &b in &values {
+                for (a_alloc, b_alloc) in [(true, true), (true, false), (false, false)] {
+                    let mut cs = TestConstraintSystem::<Scalar>::new();
+
+                    let alloc = |cs: &mut TestConstraintSystem<Scalar>, name, v, alloc| {
+                        if alloc {
+                            UInt8::alloc(cs.namespace(|| name), Some(v)).unwrap()
+                        } else {
+                            UInt8::constant(v)
+                        }
+                    };
+                    let a_bit = alloc(&mut cs, "a", a, a_alloc);
+                    let b_bit = alloc(&mut cs, "b", b, b_alloc);
+
+                    let eq = a_bit.is_eq(cs.namespace(|| "eq"), &b_bit).unwrap();
+                    let lt = a_bit.is_lt(cs.namespace(|| "lt"), &b_bit).unwrap();
+                    let le = a_bit.is_le(cs.namespace(|| "le"), &b_bit).unwrap();
+
+                    assert!(cs.is_satisfied());
+                    assert_eq!(eq.get_value(), Some(a == b));
+                    assert_eq!(lt.get_value(), Some(a < b));
+                    assert_eq!(le.get_value(), Some(a <= b));
+                }
+            }
+        }
+
+        for _ in 0..50 {
+            let mut cs = TestConstraintSystem::<Scalar>::new();
+
+            let (a, b) = (rng.next_u64(), rng.next_u64());
+            let a_bit = UInt64::alloc(cs.namespace(|| "a"), Some(a)).unwrap();
+            let b_bit = UInt64::alloc(cs.namespace(|| "b"), Some(b)).unwrap();
+
+            let eq = a_bit.is_eq(cs.namespace(|| "eq"), &b_bit).unwrap();
+            let lt = a_bit.is_lt(cs.namespace(|| "lt"), &b_bit).unwrap();
+            assert!(cs.is_satisfied());
+            assert_eq!(eq.get_value(), Some(false));
+            assert_eq!(lt.get_value(), Some(a < b));
+
+            // Claiming that distinct values are equal is rejected.
+            cs.set("eq/equal/boolean", Field::one());
+            assert!(!cs.is_satisfied());
+        }
+    }
+}
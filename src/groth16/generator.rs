@@ -10,9 +10,9 @@ use super::{Parameters, VerifyingKey};
 
 use crate::{Circuit, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
 
-use crate::domain::{EvaluationDomain, Scalar};
+use crate::domain::{DomainConfig, EvaluationDomain, Scalar};
 
-use crate::multicore::Worker;
+use crate::multicore::{Phase, Worker};
 
 /// Generates a random common reference string for
 /// a circuit.
@@ -38,6 +38,65 @@ where
     generate_parameters::<E, C>(circuit, g1, g2, alpha, beta, gamma, delta, tau)
 }
 
+/// Generates a random common reference string for a circuit like
+/// [`generate_random_parameters`], running the computation on `worker`.
+pub fn generate_random_parameters_with_worker<E, C, R>(
+    circuit: C,
+    worker: &Worker,
+    mut rng: &mut R,
+) -> Result<Parameters<E>, SynthesisError>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+    C: Circuit<E::Fr>,
+    R: RngCore,
+{
+    let g1 = E::G1::random(&mut rng);
+    let g2 = E::G2::random(&mut rng);
+    let alpha = E::Fr::random(&mut rng);
+    let beta = E::Fr::random(&mut rng);
+    let gamma = E::Fr::random(&mut rng);
+    let delta = E::Fr::random(&mut rng);
+    let tau = E::Fr::random(&mut rng);
+
+    generate_parameters_with_worker::<E, C>(circuit, g1, g2, alpha, beta, gamma, delta, tau, worker)
+}
+
+/// Generates a random common reference string for a circuit like
+/// [`generate_random_parameters_with_worker`], over the evaluation domain
+/// chosen by `domain`.
+///
+/// Proofs for the resulting parameters must be created with the same
+/// [`DomainConfig`], through [`ProverOptions::domain`].
+///
+/// [`ProverOptions::domain`]: super::ProverOptions::domain
+pub fn generate_random_parameters_with_config<E, C, R>(
+    circuit: C,
+    worker: &Worker,
+    domain: DomainConfig<E::Fr>,
+    mut rng: &mut R,
+) -> Result<Parameters<E>, SynthesisError>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+    C: Circuit<E::Fr>,
+    R: RngCore,
+{
+    let g1 = E::G1::random(&mut rng);
+    let g2 = E::G2::random(&mut rng);
+    let alpha = E::Fr::random(&mut rng);
+    let beta = E::Fr::random(&mut rng);
+    let gamma = E::Fr::random(&mut rng);
+    let delta = E::Fr::random(&mut rng);
+    let tau = E::Fr::random(&mut rng);
+
+    generate_parameters_with_config::<E, C>(
+        circuit, g1, g2, alpha, beta, gamma, delta, tau, worker, domain,
+    )
+}
+
 /// This is our assembly structure that we'll use to synthesize the
 /// circuit into a QAP.
 struct KeypairAssembly<Scalar: PrimeField> {
@@ -166,6 +225,79 @@ pub fn generate_parameters<E, C>(
     delta: E::Fr,
     tau: E::Fr,
 ) -> Result<Parameters<E>, SynthesisError>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+    C: Circuit<E::Fr>,
+{
+    generate_parameters_with_worker::<E, C>(
+        circuit,
+        g1,
+        g2,
+        alpha,
+        beta,
+        gamma,
+        delta,
+        tau,
+        &Worker::new(),
+    )
+}
+
+/// Create parameters for a circuit, given some toxic waste, running the
+/// computation on `worker`.
+#[allow(clippy::too_many_arguments)]
+pub fn generate_parameters_with_worker<E, C>(
+    circuit: C,
+    g1: E::G1,
+    g2: E::G2,
+    alpha: E::Fr,
+    beta: E::Fr,
+    gamma: E::Fr,
+    delta: E::Fr,
+    tau: E::Fr,
+    worker: &Worker,
+) -> Result<Parameters<E>, SynthesisError>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+    C: Circuit<E::Fr>,
+{
+    generate_parameters_with_config::<E, C>(
+        circuit,
+        g1,
+        g2,
+        alpha,
+        beta,
+        gamma,
+        delta,
+        tau,
+        worker,
+        DomainConfig::default(),
+    )
+}
+
+/// Create parameters for a circuit, given some toxic waste, running the
+/// computation on `worker` over the evaluation domain chosen by `domain`.
+///
+/// Proofs for the resulting parameters must be created with the same
+/// [`DomainConfig`], through [`ProverOptions::domain`].
+///
+/// [`ProverOptions::domain`]: super::ProverOptions::domain
+#[allow(clippy::too_many_arguments)]
+pub fn generate_parameters_with_config<E, C>(
+    circuit: C,
+    g1: E::G1,
+    g2: E::G2,
+    alpha: E::Fr,
+    beta: E::Fr,
+    gamma: E::Fr,
+    delta: E::Fr,
+    tau: E::Fr,
+    worker: &Worker,
+    domain: DomainConfig<E::Fr>,
+) -> Result<Parameters<E>, SynthesisError>
 where
     E: Engine,
     E::G1: WnafGroup,
@@ -188,6 +320,7 @@ where
     assembly.alloc_input(|| "", || Ok(E::Fr::one()))?;
 
     // Synthesize the circuit.
+    worker.checkpoint(Phase::Synthesis)?;
     circuit.synthesize(&mut assembly)?;
 
     // Input constraints to ensure full density of IC query
@@ -198,7 +331,7 @@ where
 
     // Create bases for blind evaluation of polynomials at tau
     let powers_of_tau = vec![Scalar::<E::Fr>(E::Fr::zero()); assembly.num_constraints];
-    let mut powers_of_tau = EvaluationDomain::from_coeffs(powers_of_tau)?;
+    let mut powers_of_tau = EvaluationDomain::from_coeffs_with_config(powers_of_tau, domain)?;
 
     // Compute G1 window table
     let mut g1_wnaf = Wnaf::new();
@@ -237,7 +370,7 @@ where
         }
     }?;
 
-    let worker = Worker::new();
+    worker.checkpoint(Phase::Evaluation)?;
 
     let mut h = vec![E::G1Affine::identity(); powers_of_tau.as_ref().len() - 1];
     {
@@ -247,6 +380,10 @@ where
             worker.scope(powers_of_tau.len(), |scope, chunk| {
                 for (i, powers_of_tau) in powers_of_tau.chunks_mut(chunk).enumerate() {
                     scope.spawn(move |_scope| {
+                        if worker.is_cancelled() {
+                            return;
+                        }
+
                         let mut current_tau_power = tau.pow_vartime(&[(i * chunk) as u64]);
 
                         for p in powers_of_tau {
@@ -271,6 +408,10 @@ where
                 let mut g1_wnaf = g1_wnaf.shared();
 
                 scope.spawn(move |_scope| {
+                    if worker.is_cancelled() {
+                        return;
+                    }
+
                     // Set values of the H query to g1^{(tau^i * t(tau)) / delta}
                     let h_proj: Vec<_> = p[..h.len()]
                         .iter()
@@ -292,7 +433,8 @@ where
     }
 
     // Use inverse FFT to convert powers of tau to Lagrange coefficients
-    powers_of_tau.ifft(&worker);
+    worker.checkpoint(Phase::Fft)?;
+    powers_of_tau.ifft(worker);
     let powers_of_tau = powers_of_tau.into_coeffs();
 
     let mut a = vec![E::G1Affine::identity(); assembly.num_inputs + assembly.num_aux];
@@ -354,6 +496,10 @@ where
                 let mut g2_wnaf = g2_wnaf.shared();
 
                 scope.spawn(move |_scope| {
+                    if worker.is_cancelled() {
+                        return;
+                    }
+
                     let mut a_proj = vec![E::G1::identity(); a.len()];
                     let mut b_g1_proj = vec![E::G1::identity(); b_g1.len()];
                     let mut b_g2_proj = vec![E::G2::identity(); b_g2.len()];
@@ -421,6 +567,7 @@ where
     }
 
     // Evaluate for inputs.
+    worker.checkpoint(Phase::Evaluation)?;
     eval::<E>(
         &g1_wnaf,
         &g2_wnaf,
@@ -435,7 +582,7 @@ where
         &gamma_inverse,
         &alpha,
         &beta,
-        &worker,
+        worker,
     );
 
     // Evaluate for auxiliary variables.
@@ -453,8 +600,9 @@ where
         &delta_inverse,
         &alpha,
         &beta,
-        &worker,
+        worker,
     );
+    worker.check_cancelled()?;
 
     // Don't allow any elements be unconstrained, so that
     // the L query is always fully dense.
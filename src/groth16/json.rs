@@ -0,0 +1,517 @@
+//! Import and export of Groth16 proofs and verifying keys in the JSON formats
+//! used by [snarkjs] and [circom].
+//!
+//! snarkjs stores every coordinate as a decimal string of its affine
+//! coordinates, with a trailing projective `z` coordinate:
+//!
+//! - G1 elements are `["x", "y", "1"]`.
+//! - G2 elements are `[["x.c0", "x.c1"], ["y.c0", "y.c1"], ["1", "0"]]`.
+//!
+//! Points are converted to and from coordinates through their
+//! [`UncompressedEncoding`], which is assumed to follow the big-endian
+//! encoding (with the three most significant bits reserved for flags) used by
+//! the [`bls12_381`] crate.
+//!
+//! snarkjs verifying keys do not contain `beta_g1` or `delta_g1`, which are
+//! only needed for proving. Keys read with [`VerifyingKey::read_json`] have
+//! those elements set to the identity, and can only be used for verification.
+//!
+//! [snarkjs]: https://github.com/iden3/snarkjs
+//! [circom]: https://github.com/iden3/circom
+//! [`bls12_381`]: https://docs.rs/bls12_381
+
+use std::io::{self, Read, Write};
+
+use ff::PrimeField;
+use group::{prime::PrimeCurveAffine, UncompressedEncoding};
+use pairing::Engine;
+use serde_json::{json, Value};
+
+use super::{Proof, VerifyingKey};
+
+/// Flag bits stored in the most significant bits of an uncompressed encoding.
+const FLAG_MASK: u8 = 0b1110_0000;
+const INFINITY_FLAG: u8 = 0b0100_0000;
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// Formats a big-endian unsigned integer as a decimal string.
+fn be_bytes_to_decimal(bytes: &[u8]) -> String {
+    let mut num = bytes.to_vec();
+    let mut digits = vec![];
+
+    while num.iter().any(|b| *b != 0) {
+        // Divide `num` by 10 in place, collecting the remainder.
+        let mut rem = 0u32;
+        for b in num.iter_mut() {
+            let cur = (rem << 8) | u32::from(*b);
+            *b = (cur / 10) as u8;
+            rem = cur % 10;
+        }
+        digits.push(b'0' + rem as u8);
+    }
+
+    if digits.is_empty() {
+        digits.push(b'0');
+    }
+    digits.reverse();
+
+    String::from_utf8(digits).expect("digits are ASCII")
+}
+
+/// Parses a decimal string into a big-endian unsigned integer of exactly
+/// `len` bytes.
+fn decimal_to_be_bytes(s: &str, len: usize) -> io::Result<Vec<u8>> {
+    if s.is_empty() {
+        return Err(invalid_data("empty decimal string"));
+    }
+
+    let mut num = vec![0u8; len];
+    for c in s.chars() {
+        let digit = c
+            .to_digit(10)
+            .ok_or_else(|| invalid_data(format!("invalid decimal digit '{}'", c)))?;
+
+        // num = num * 10 + digit
+        let mut carry = digit;
+        for b in num.iter_mut().rev() {
+            let cur = u32::from(*b) * 10 + carry;
+            *b = cur as u8;
+            carry = cur >> 8;
+        }
+        if carry != 0 {
+            return Err(invalid_data("decimal value is too large"));
+        }
+    }
+
+    Ok(num)
+}
+
+/// Returns the snarkjs curve name for a base field element of `len` bytes.
+fn curve_name(len: usize) -> io::Result<&'static str> {
+    match len {
+        32 => Ok("bn128"),
+        48 => Ok("bls12381"),
+        _ => Err(invalid_data(format!(
+            "no snarkjs curve has {}-byte coordinates",
+            len
+        ))),
+    }
+}
+
+/// Splits the uncompressed encoding of a point into its big-endian base field
+/// elements, or returns `None` for the point at infinity.
+fn encoding_to_coordinates<G: UncompressedEncoding>(p: &G, num: usize) -> Option<Vec<Vec<u8>>> {
+    let uncompressed = p.to_uncompressed();
+    let mut bytes = uncompressed.as_ref().to_vec();
+
+    if bytes[0] & INFINITY_FLAG != 0 {
+        return None;
+    }
+    bytes[0] &= !FLAG_MASK;
+
+    let len = bytes.len() / num;
+    Some(bytes.chunks(len).map(|c| c.to_vec()).collect())
+}
+
+/// Builds a point from its big-endian base field elements, or the point at
+/// infinity if `coordinates` is `None`.
+fn coordinates_to_point<G: UncompressedEncoding>(
+    coordinates: Option<Vec<Vec<u8>>>,
+) -> io::Result<G> {
+    let mut repr = G::Uncompressed::default();
+
+    match coordinates {
+        Some(coordinates) => {
+            let mut bytes = coordinates.concat();
+            if bytes.len() != repr.as_ref().len() {
+                return Err(invalid_data("unexpected coordinate length"));
+            }
+            if bytes[0] & FLAG_MASK != 0 {
+                return Err(invalid_data("coordinate is too large"));
+            }
+            bytes[0] &= !FLAG_MASK;
+            repr.as_mut().copy_from_slice(&bytes);
+        }
+        None => repr.as_mut()[0] = INFINITY_FLAG,
+    }
+
+    Option::from(G::from_uncompressed(&repr)).ok_or_else(|| invalid_data("invalid point"))
+}
+
+fn g1_to_json<G: UncompressedEncoding>(p: &G) -> Value {
+    match encoding_to_coordinates(p, 2) {
+        Some(c) => json!([be_bytes_to_decimal(&c[0]), be_bytes_to_decimal(&c[1]), "1"]),
+        None => json!(["0", "1", "0"]),
+    }
+}
+
+fn g2_to_json<G: UncompressedEncoding>(p: &G) -> Value {
+    // The uncompressed encoding orders each coordinate as (c1, c0), whereas
+    // snarkjs uses (c0, c1).
+    match encoding_to_coordinates(p, 4) {
+        Some(c) => json!([
+            [be_bytes_to_decimal(&c[1]), be_bytes_to_decimal(&c[0])],
+            [be_bytes_to_decimal(&c[3]), be_bytes_to_decimal(&c[2])],
+            ["1", "0"]
+        ]),
+        None => json!([["0", "0"], ["1", "0"], ["0", "0"]]),
+    }
+}
+
+fn json_str(v: &Value) -> io::Result<&str> {
+    v.as_str()
+        .ok_or_else(|| invalid_data("expected a decimal string"))
+}
+
+fn json_array(v: &Value, len: usize) -> io::Result<&[Value]> {
+    match v.as_array() {
+        Some(a) if a.len() == len => Ok(a),
+        _ => Err(invalid_data(format!("expected an array of length {}", len))),
+    }
+}
+
+fn json_field<'a>(v: &'a Value, name: &str) -> io::Result<&'a Value> {
+    v.get(name)
+        .ok_or_else(|| invalid_data(format!("missing field \"{}\"", name)))
+}
+
+/// Returns whether a projective `z` coordinate is zero.
+fn is_zero_decimal(s: &str) -> bool {
+    s.chars().all(|c| c == '0')
+}
+
+fn g1_from_json<G: UncompressedEncoding>(v: &Value) -> io::Result<G> {
+    let p = json_array(v, 3)?;
+    let len = G::Uncompressed::default().as_ref().len() / 2;
+
+    if is_zero_decimal(json_str(&p[2])?) {
+        return coordinates_to_point(None);
+    }
+    if json_str(&p[2])? != "1" {
+        return Err(invalid_data("G1 point is not in affine form"));
+    }
+
+    coordinates_to_point(Some(vec![
+        decimal_to_be_bytes(json_str(&p[0])?, len)?,
+        decimal_to_be_bytes(json_str(&p[1])?, len)?,
+    ]))
+}
+
+fn g2_from_json<G: UncompressedEncoding>(v: &Value) -> io::Result<G> {
+    let p = json_array(v, 3)?;
+    let len = G::Uncompressed::default().as_ref().len() / 4;
+
+    let x = json_array(&p[0], 2)?;
+    let y = json_array(&p[1], 2)?;
+    let z = json_array(&p[2], 2)?;
+
+    if is_zero_decimal(json_str(&z[0])?) && is_zero_decimal(json_str(&z[1])?) {
+        return coordinates_to_point(None);
+    }
+    if json_str(&z[0])? != "1" || !is_zero_decimal(json_str(&z[1])?) {
+        return Err(invalid_data("G2 point is not in affine form"));
+    }
+
+    coordinates_to_point(Some(vec![
+        decimal_to_be_bytes(json_str(&x[1])?, len)?,
+        decimal_to_be_bytes(json_str(&x[0])?, len)?,
+        decimal_to_be_bytes(json_str(&y[1])?, len)?,
+        decimal_to_be_bytes(json_str(&y[0])?, len)?,
+    ]))
+}
+
+fn check_protocol(v: &Value) -> io::Result<()> {
+    match v.get("protocol").and_then(Value::as_str) {
+        None | Some("groth16") => Ok(()),
+        Some(p) => Err(invalid_data(format!("unsupported protocol \"{}\"", p))),
+    }
+}
+
+fn g1_coordinate_len<E: Engine>() -> usize {
+    <E::G1Affine as UncompressedEncoding>::Uncompressed::default()
+        .as_ref()
+        .len()
+        / 2
+}
+
+impl<E: Engine> Proof<E> {
+    /// Writes this proof in the snarkjs `proof.json` format.
+    ///
+    /// Fails if the curve of `E` is not one that snarkjs supports.
+    pub fn write_json<W: Write>(&self, writer: W) -> io::Result<()> {
+        let proof = json!({
+            "pi_a": g1_to_json(&self.a),
+            "pi_b": g2_to_json(&self.b),
+            "pi_c": g1_to_json(&self.c),
+            "protocol": "groth16",
+            "curve": curve_name(g1_coordinate_len::<E>())?,
+        });
+
+        serde_json::to_writer_pretty(writer, &proof).map_err(io::Error::from)
+    }
+
+    /// Reads a proof in the snarkjs `proof.json` format.
+    pub fn read_json<R: Read>(reader: R) -> io::Result<Self> {
+        let v: Value = serde_json::from_reader(reader)?;
+        check_protocol(&v)?;
+
+        let a: E::G1Affine = g1_from_json(json_field(&v, "pi_a")?)?;
+        let b: E::G2Affine = g2_from_json(json_field(&v, "pi_b")?)?;
+        let c: E::G1Affine = g1_from_json(json_field(&v, "pi_c")?)?;
+
+        if bool::from(a.is_identity() | b.is_identity() | c.is_identity()) {
+            return Err(invalid_data("point at infinity"));
+        }
+
+        Ok(Proof { a, b, c })
+    }
+}
+
+impl<E: Engine> VerifyingKey<E> {
+    /// Writes this verifying key in the snarkjs `verification_key.json`
+    /// format.
+    ///
+    /// Fails if the curve of `E` is not one that snarkjs supports.
+    pub fn write_json<W: Write>(&self, writer: W) -> io::Result<()> {
+        let vk = json!({
+            "protocol": "groth16",
+            "curve": curve_name(g1_coordinate_len::<E>())?,
+            "nPublic": self.ic.len().saturating_sub(1),
+            "vk_alpha_1": g1_to_json(&self.alpha_g1),
+            "vk_beta_2": g2_to_json(&self.beta_g2),
+            "vk_gamma_2": g2_to_json(&self.gamma_g2),
+            "vk_delta_2": g2_to_json(&self.delta_g2),
+            "IC": self.ic.iter().map(g1_to_json).collect::<Vec<_>>(),
+        });
+
+        serde_json::to_writer_pretty(writer, &vk).map_err(io::Error::from)
+    }
+
+    /// Reads a verifying key in the snarkjs `verification_key.json` format.
+    ///
+    /// `beta_g1` and `delta_g1` are set to the identity, as snarkjs does not
+    /// export them.
+    pub fn read_json<R: Read>(reader: R) -> io::Result<Self> {
+        let v: Value = serde_json::from_reader(reader)?;
+        check_protocol(&v)?;
+
+        let ic = json_field(&v, "IC")?
+            .as_array()
+            .ok_or_else(|| invalid_data("expected \"IC\" to be an array"))?
+            .iter()
+            .map(|p| {
+                g1_from_json::<E::G1Affine>(p).and_then(|p| {
+                    if p.is_identity().into() {
+                        Err(invalid_data("point at infinity"))
+                    } else {
+                        Ok(p)
+                    }
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        if let Some(n) = v.get("nPublic").and_then(Value::as_u64) {
+            if n as usize + 1 != ic.len() {
+                return Err(invalid_data("\"nPublic\" does not match \"IC\""));
+            }
+        }
+
+        Ok(VerifyingKey {
+            alpha_g1: g1_from_json(json_field(&v, "vk_alpha_1")?)?,
+            beta_g1: E::G1Affine::identity(),
+            beta_g2: g2_from_json(json_field(&v, "vk_beta_2")?)?,
+            gamma_g2: g2_from_json(json_field(&v, "vk_gamma_2")?)?,
+            delta_g1: E::G1Affine::identity(),
+            delta_g2: g2_from_json(json_field(&v, "vk_delta_2")?)?,
+            ic,
+        })
+    }
+}
+
+/// Writes public inputs in the snarkjs `public.json` format.
+///
+/// Field elements are assumed to have a little-endian [`PrimeField::Repr`].
+pub fn write_public_inputs<S: PrimeField, W: Write>(inputs: &[S], writer: W) -> io::Result<()> {
+    let inputs = inputs
+        .iter()
+        .map(|s| {
+            let mut repr = s.to_repr().as_ref().to_vec();
+            repr.reverse();
+            be_bytes_to_decimal(&repr)
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::to_writer_pretty(writer, &inputs).map_err(io::Error::from)
+}
+
+/// Reads public inputs in the snarkjs `public.json` format.
+///
+/// Field elements are assumed to have a little-endian [`PrimeField::Repr`].
+pub fn read_public_inputs<S: PrimeField, R: Read>(reader: R) -> io::Result<Vec<S>> {
+    let v: Value = serde_json::from_reader(reader)?;
+
+    v.as_array()
+        .ok_or_else(|| invalid_data("expected an array of public inputs"))?
+        .iter()
+        .map(|s| {
+            let mut repr = S::Repr::default();
+            let mut bytes = decimal_to_be_bytes(json_str(s)?, repr.as_ref().len())?;
+            bytes.reverse();
+            repr.as_mut().copy_from_slice(&bytes);
+
+            Option::from(S::from_repr(repr))
+                .ok_or_else(|| invalid_data("public input is not in the field"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::groth16::{
+        create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
+    };
+    use crate::{Circuit, ConstraintSystem, SynthesisError};
+
+    use bls12_381::{Bls12, G1Affine, G2Affine, Scalar};
+    use ff::Field;
+    use rand::thread_rng;
+
+    struct MulCircuit {
+        a: Option<Scalar>,
+        b: Option<Scalar>,
+    }
+
+    impl Circuit<Scalar> for MulCircuit {
+        fn synthesize<CS: ConstraintSystem<Scalar>>(
+            self,
+            cs: &mut CS,
+        ) -> Result<(), SynthesisError> {
+            let a = cs.alloc(|| "a", || self.a.ok_or(SynthesisError::AssignmentMissing))?;
+            let b = cs.alloc(|| "b", || self.b.ok_or(SynthesisError::AssignmentMissing))?;
+            let c = cs.alloc_input(
+                || "c",
+                || Ok(self.a.ok_or(SynthesisError::AssignmentMissing)? * self.b.unwrap()),
+            )?;
+
+            cs.enforce(|| "a*b=c", |lc| lc + a, |lc| lc + b, |lc| lc + c);
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn decimal_conversion() {
+        assert_eq!(be_bytes_to_decimal(&[0, 0]), "0");
+        assert_eq!(be_bytes_to_decimal(&[0x01, 0x00]), "256");
+        assert_eq!(be_bytes_to_decimal(&[0xff; 8]), u64::MAX.to_string());
+
+        assert_eq!(decimal_to_be_bytes("256", 2).unwrap(), vec![0x01, 0x00]);
+        assert_eq!(
+            decimal_to_be_bytes(&u64::MAX.to_string(), 8).unwrap(),
+            vec![0xff; 8]
+        );
+        assert!(decimal_to_be_bytes("65536", 2).is_err());
+        assert!(decimal_to_be_bytes("12a", 2).is_err());
+    }
+
+    #[test]
+    fn curve_names() {
+        assert_eq!(curve_name(32).unwrap(), "bn128");
+        assert_eq!(curve_name(48).unwrap(), "bls12381");
+        assert!(curve_name(64).is_err());
+    }
+
+    #[test]
+    fn generator_coordinates() {
+        let g1 = g1_to_json(&G1Affine::generator());
+        assert_eq!(
+            g1,
+            json!([
+                "3685416753713387016781088315183077757961620795782546409894578378688607592378376318836054947676345821548104185464507",
+                "1339506544944476473020471379941921221584933875938349620426543736416511423956333506472724655353366534992391756441569",
+                "1"
+            ])
+        );
+        assert_eq!(
+            g1_from_json::<G1Affine>(&g1).unwrap(),
+            G1Affine::generator()
+        );
+
+        let g2 = g2_to_json(&G2Affine::generator());
+        assert_eq!(
+            g2[0][0],
+            "352701069587466618187139116011060144890029952792775240219908644239793785735715026873347600343865175952761926303160"
+        );
+        assert_eq!(
+            g2_from_json::<G2Affine>(&g2).unwrap(),
+            G2Affine::generator()
+        );
+
+        assert_eq!(
+            g1_from_json::<G1Affine>(&g1_to_json(&G1Affine::identity())).unwrap(),
+            G1Affine::identity()
+        );
+        assert_eq!(
+            g2_from_json::<G2Affine>(&g2_to_json(&G2Affine::identity())).unwrap(),
+            G2Affine::identity()
+        );
+    }
+
+    #[test]
+    fn proof_and_vk_roundtrip() {
+        let mut rng = thread_rng();
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+
+        let mut vk_json = vec![];
+        params.vk.write_json(&mut vk_json).unwrap();
+        let vk = VerifyingKey::<Bls12>::read_json(&vk_json[..]).unwrap();
+        assert_eq!(vk.alpha_g1, params.vk.alpha_g1);
+        assert_eq!(vk.beta_g2, params.vk.beta_g2);
+        assert_eq!(vk.gamma_g2, params.vk.gamma_g2);
+        assert_eq!(vk.delta_g2, params.vk.delta_g2);
+        assert_eq!(vk.ic, params.vk.ic);
+
+        let a = Scalar::random(&mut rng);
+        let b = Scalar::random(&mut rng);
+        let proof = create_random_proof(
+            MulCircuit {
+                a: Some(a),
+                b: Some(b),
+            },
+            &params,
+            &mut rng,
+        )
+        .unwrap();
+
+        let mut proof_json = vec![];
+        proof.write_json(&mut proof_json).unwrap();
+        let de_proof = Proof::<Bls12>::read_json(&proof_json[..]).unwrap();
+        assert!(proof == de_proof);
+
+        let mut inputs_json = vec![];
+        write_public_inputs(&[a * b], &mut inputs_json).unwrap();
+        let inputs = read_public_inputs::<Scalar, _>(&inputs_json[..]).unwrap();
+        assert_eq!(inputs, vec![a * b]);
+
+        let pvk = prepare_verifying_key(&vk);
+        assert!(verify_proof(&pvk, &de_proof, &inputs).is_ok());
+        assert!(verify_proof(&pvk, &de_proof, &[a]).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_json() {
+        assert!(Proof::<Bls12>::read_json(&b"{}"[..]).is_err());
+        assert!(Proof::<Bls12>::read_json(&br#"{"protocol": "plonk"}"#[..]).is_err());
+
+        // (1, 1) is not on the curve.
+        let not_on_curve = json!(["1", "1", "1"]);
+        assert!(g1_from_json::<G1Affine>(&not_on_curve).is_err());
+    }
+}
@@ -2,9 +2,13 @@
 //!
 //! [Groth16]: https://eprint.iacr.org/2016/260
 
+use ff::PrimeFieldBits;
 use group::{prime::PrimeCurveAffine, GroupEncoding, UncompressedEncoding};
 use pairing::{Engine, MultiMillerLoop};
 
+use crate::domain::{DomainConfig, DomainPrecomputation};
+use crate::multicore::Worker;
+use crate::multiexp::PrecomputedBases;
 use crate::SynthesisError;
 
 use crate::multiexp::SourceBuilder;
@@ -16,6 +20,8 @@ use std::sync::Arc;
 mod tests;
 
 mod generator;
+#[cfg(feature = "json")]
+pub mod json;
 mod prover;
 mod verifier;
 
@@ -255,7 +261,46 @@ impl<E: Engine> PartialEq for Parameters<E> {
     }
 }
 
+/// Precomputed multiples of the `h` and `l` queries of some [`Parameters`],
+/// which can be shared by all proofs using those parameters through
+/// [`create_proof_with_queries`].
+pub struct PrecomputedQueries<E: Engine> {
+    pub h: PrecomputedBases<E::G1>,
+    pub l: PrecomputedBases<E::G1>,
+}
+
 impl<E: Engine> Parameters<E> {
+    /// Precomputes the multiples of the `h` and `l` queries for a multiexp
+    /// window size of `window` bits, for use with [`create_proof_with_queries`].
+    ///
+    /// Each base is stored along with one multiple per window, so the tables
+    /// take about `256 / window` times as much memory as the queries.
+    pub fn precompute_queries(
+        &self,
+        worker: &Worker,
+        window: u32,
+    ) -> Result<Arc<PrecomputedQueries<E>>, SynthesisError>
+    where
+        E::Fr: PrimeFieldBits,
+    {
+        Ok(Arc::new(PrecomputedQueries {
+            h: PrecomputedBases::new(worker, &self.h, window)?,
+            l: PrecomputedBases::new(worker, &self.l, window)?,
+        }))
+    }
+
+    /// Precomputes the FFT tables for the evaluation domain of the circuit, for
+    /// use with [`create_proof_precomputed`]. `config` must be the
+    /// [`DomainConfig`] the parameters were generated with.
+    pub fn domain_precomputation(
+        &self,
+        worker: &Worker,
+        config: DomainConfig<E::Fr>,
+    ) -> Result<Arc<DomainPrecomputation<E::Fr>>, SynthesisError> {
+        // The domain has one more element than the h query.
+        DomainPrecomputation::new(worker, self.h.len() + 1, config).map(Arc::new)
+    }
+
     pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
         self.vk.write(&mut writer)?;
 
@@ -477,6 +522,7 @@ impl<'a, E: Engine> ParameterSource<E> for &'a Parameters<E> {
 #[cfg(test)]
 mod test_with_bls12_381 {
     use super::*;
+    use crate::domain::SmallSubgroup;
     use crate::{Circuit, ConstraintSystem, SynthesisError};
 
     use bls12_381::{Bls12, Scalar};
@@ -566,4 +612,392 @@ mod test_with_bls12_381 {
             assert!(verify_proof(&pvk, &proof, &[a]).is_err());
         }
     }
+
+    #[test]
+    fn precomputed_queries() {
+        // x^2 = y, with x^4 exposed so that the h query is not trivial.
+        struct Squaring<Scalar: PrimeField>(Option<Scalar>);
+
+        impl<Scalar: PrimeField> Circuit<Scalar> for Squaring<Scalar> {
+            fn synthesize<CS: ConstraintSystem<Scalar>>(
+                self,
+                cs: &mut CS,
+            ) -> Result<(), SynthesisError> {
+                let x = cs.alloc(|| "x", || self.0.ok_or(SynthesisError::AssignmentMissing))?;
+                let x2_value = self.0.map(|x| x.square());
+                let x2 = cs.alloc(
+                    || "x2",
+                    || x2_value.ok_or(SynthesisError::AssignmentMissing),
+                )?;
+                let x4 = cs.alloc_input(
+                    || "x4",
+                    || {
+                        x2_value
+                            .map(|x2| x2.square())
+                            .ok_or(SynthesisError::AssignmentMissing)
+                    },
+                )?;
+                cs.enforce(|| "x2", |lc| lc + x, |lc| lc + x, |lc| lc + x2);
+                cs.enforce(|| "x4", |lc| lc + x2, |lc| lc + x2, |lc| lc + x4);
+
+                Ok(())
+            }
+        }
+
+        let mut rng = thread_rng();
+        let worker = Worker::new();
+        let params = generate_random_parameters::<Bls12, _, _>(Squaring(None), &mut rng).unwrap();
+        let pvk = prepare_verifying_key::<Bls12>(&params.vk);
+
+        let x = Scalar::random(&mut rng);
+        let x4 = x.square().square();
+        let (r, s) = (Scalar::random(&mut rng), Scalar::random(&mut rng));
+        let proof = create_proof(Squaring(Some(x)), &params, r, s).unwrap();
+
+        for window in [2, 4, 7] {
+            let queries = params.precompute_queries(&worker, window).unwrap();
+            assert!(
+                proof
+                    == create_proof_with_queries(Squaring(Some(x)), &params, &queries, r, s)
+                        .unwrap()
+            );
+
+            let proof =
+                create_random_proof_with_queries(Squaring(Some(x)), &params, &queries, &mut rng)
+                    .unwrap();
+            assert!(verify_proof(&pvk, &proof, &[x4]).is_ok());
+        }
+    }
+
+    #[test]
+    fn precomputed_domain() {
+        /// Proves knowledge of `x` such that `x^(2^20) = out`.
+        struct Squarings(Option<Scalar>);
+
+        impl Circuit<Scalar> for Squarings {
+            fn synthesize<CS: ConstraintSystem<Scalar>>(
+                self,
+                cs: &mut CS,
+            ) -> Result<(), SynthesisError> {
+                let mut value = self.0;
+                let mut x = cs.alloc(|| "x", || value.ok_or(SynthesisError::AssignmentMissing))?;
+                for i in 0..20 {
+                    value = value.map(|x| x.square());
+                    let square = if i == 19 {
+                        cs.alloc_input(|| "out", || value.ok_or(SynthesisError::AssignmentMissing))?
+                    } else {
+                        cs.alloc(
+                            || "square",
+                            || value.ok_or(SynthesisError::AssignmentMissing),
+                        )?
+                    };
+                    cs.enforce(|| "square", |lc| lc + x, |lc| lc + x, |lc| lc + square);
+                    x = square;
+                }
+
+                Ok(())
+            }
+        }
+
+        let mut rng = thread_rng();
+        let worker = Worker::new();
+        let params = generate_random_parameters::<Bls12, _, _>(Squarings(None), &mut rng).unwrap();
+        let pvk = prepare_verifying_key::<Bls12>(&params.vk);
+        let precomputed = params
+            .domain_precomputation(&worker, DomainConfig::default())
+            .unwrap();
+        assert_eq!(precomputed.size(), 32);
+
+        let x = Scalar::random(&mut rng);
+        let out = (0..20).fold(x, |x, _| x.square());
+        let (r, s) = (Scalar::random(&mut rng), Scalar::random(&mut rng));
+        let proof =
+            create_proof_precomputed(Squarings(Some(x)), &params, &precomputed, r, s).unwrap();
+        assert!(proof == create_proof(Squarings(Some(x)), &params, r, s).unwrap());
+        assert!(verify_proof(&pvk, &proof, &[out]).is_ok());
+
+        let proof =
+            create_random_proof_precomputed(Squarings(Some(x)), &params, &precomputed, &mut rng)
+                .unwrap();
+        assert!(verify_proof(&pvk, &proof, &[out]).is_ok());
+
+        // The tables must be for the domain of the circuit.
+        let other =
+            Arc::new(DomainPrecomputation::new(&worker, 64, DomainConfig::default()).unwrap());
+        assert!(matches!(
+            create_proof_precomputed(Squarings(Some(x)), &params, &other, r, s),
+            Err(SynthesisError::IoError(_))
+        ));
+
+        // A mixed-radix domain fits the 21 constraints in 24 rows.
+        let sqrt = (-Scalar::from(3)).sqrt().unwrap();
+        let root = (sqrt - Scalar::one()) * Scalar::from(2).invert().unwrap();
+        let mixed = DomainConfig {
+            coset_shift: Scalar::from(5),
+            subgroup: Some(SmallSubgroup::new(3, 1, root).unwrap()),
+        };
+        let params = generate_random_parameters_with_config::<Bls12, _, _>(
+            Squarings(None),
+            &worker,
+            mixed,
+            &mut rng,
+        )
+        .unwrap();
+        let pvk = prepare_verifying_key::<Bls12>(&params.vk);
+        let precomputed = params.domain_precomputation(&worker, mixed).unwrap();
+        assert_eq!(precomputed.size(), 24);
+
+        let options = ProverOptions {
+            domain: mixed,
+            ..Default::default()
+        };
+        let proof = create_proof_with_options(Squarings(Some(x)), &params, &options, r, s).unwrap();
+        assert!(verify_proof(&pvk, &proof, &[out]).is_ok());
+        assert!(
+            proof
+                == create_proof_precomputed(Squarings(Some(x)), &params, &precomputed, r, s)
+                    .unwrap()
+        );
+        assert!(create_proof(Squarings(Some(x)), &params, r, s).is_err());
+    }
+
+    #[cfg(feature = "multicore")]
+    #[test]
+    fn dedicated_worker() {
+        /// Proves knowledge of `a` and `b` such that `a * b = c`.
+        struct Product(Option<(Scalar, Scalar)>);
+
+        impl Circuit<Scalar> for Product {
+            fn synthesize<CS: ConstraintSystem<Scalar>>(
+                self,
+                cs: &mut CS,
+            ) -> Result<(), SynthesisError> {
+                let values = self.0;
+                let a = cs.alloc(
+                    || "a",
+                    || values.map(|v| v.0).ok_or(SynthesisError::AssignmentMissing),
+                )?;
+                let b = cs.alloc(
+                    || "b",
+                    || values.map(|v| v.1).ok_or(SynthesisError::AssignmentMissing),
+                )?;
+                let c = cs.alloc_input(
+                    || "c",
+                    || {
+                        values
+                            .map(|(a, b)| a * b)
+                            .ok_or(SynthesisError::AssignmentMissing)
+                    },
+                )?;
+                cs.enforce(|| "a*b=c", |lc| lc + a, |lc| lc + b, |lc| lc + c);
+
+                Ok(())
+            }
+        }
+
+        let mut rng = thread_rng();
+        let worker = Worker::with_num_threads(2).unwrap();
+        assert_eq!(worker.num_threads(), 2);
+
+        // The results don't depend on the pool that computes them.
+        let g1 = <bls12_381::G1Projective as group::Group>::random(&mut rng);
+        let g2 = <bls12_381::G2Projective as group::Group>::random(&mut rng);
+        let toxic: Vec<_> = (0..5).map(|_| Scalar::random(&mut rng)).collect();
+        let params = generate_parameters::<Bls12, _>(
+            Product(None),
+            g1,
+            g2,
+            toxic[0],
+            toxic[1],
+            toxic[2],
+            toxic[3],
+            toxic[4],
+        )
+        .unwrap();
+        let other = generate_parameters_with_worker::<Bls12, _>(
+            Product(None),
+            g1,
+            g2,
+            toxic[0],
+            toxic[1],
+            toxic[2],
+            toxic[3],
+            toxic[4],
+            &worker,
+        )
+        .unwrap();
+        assert!(params == other);
+
+        let options = ProverOptions {
+            worker: worker.clone(),
+            precomputed: Some(
+                params
+                    .domain_precomputation(&worker, DomainConfig::default())
+                    .unwrap(),
+            ),
+            ..Default::default()
+        };
+        let (a, b) = (Scalar::random(&mut rng), Scalar::random(&mut rng));
+        let (r, s) = (Scalar::random(&mut rng), Scalar::random(&mut rng));
+        let proof =
+            create_proof_with_options(Product(Some((a, b))), &params, &options, r, s).unwrap();
+        assert!(proof == create_proof(Product(Some((a, b))), &params, r, s).unwrap());
+
+        let options_without_tables = ProverOptions {
+            worker: worker.clone(),
+            ..Default::default()
+        };
+        let params =
+            generate_random_parameters_with_worker::<Bls12, _, _>(Product(None), &worker, &mut rng)
+                .unwrap();
+        let items: Vec<_> = (0..10)
+            .map(|_| {
+                let (a, b) = (Scalar::random(&mut rng), Scalar::random(&mut rng));
+                let proof = create_random_proof_with_options(
+                    Product(Some((a, b))),
+                    &params,
+                    &options_without_tables,
+                    &mut rng,
+                )
+                .unwrap();
+                (proof, vec![a * b])
+            })
+            .collect();
+        let mut batch = batch::Verifier::new();
+        for item in items.iter().cloned() {
+            batch.queue(item);
+        }
+        assert!(batch
+            .verify_multicore_with_worker(&params.vk, &worker)
+            .is_ok());
+
+        // The proof from the earlier parameters is rejected.
+        let mut batch = batch::Verifier::new();
+        for item in items.into_iter().chain(Some((proof, vec![a * b]))) {
+            batch.queue(item);
+        }
+        assert!(batch
+            .verify_multicore_with_worker(&params.vk, &worker)
+            .is_err());
+    }
+
+    #[test]
+    fn cancellation() {
+        use crate::multicore::{CancellationToken, Phase};
+        use std::sync::Mutex;
+
+        /// Proves knowledge of `x` such that `x^3 = out`.
+        struct Cube(Option<Scalar>);
+
+        impl Circuit<Scalar> for Cube {
+            fn synthesize<CS: ConstraintSystem<Scalar>>(
+                self,
+                cs: &mut CS,
+            ) -> Result<(), SynthesisError> {
+                let x_val = self.0;
+                let x = cs.alloc(|| "x", || x_val.ok_or(SynthesisError::AssignmentMissing))?;
+                let x2_val = x_val.map(|x| x.square());
+                let x2 = cs.alloc(|| "x^2", || x2_val.ok_or(SynthesisError::AssignmentMissing))?;
+                let out = cs.alloc_input(
+                    || "out",
+                    || {
+                        x_val
+                            .map(|x| x.cube())
+                            .ok_or(SynthesisError::AssignmentMissing)
+                    },
+                )?;
+                cs.enforce(|| "x^2", |lc| lc + x, |lc| lc + x, |lc| lc + x2);
+                cs.enforce(|| "x^3", |lc| lc + x2, |lc| lc + x, |lc| lc + out);
+
+                Ok(())
+            }
+        }
+
+        let mut rng = thread_rng();
+        let phases = Arc::new(Mutex::new(vec![]));
+        let worker = {
+            let phases = phases.clone();
+            Worker::new().with_progress(move |phase| phases.lock().unwrap().push(phase))
+        };
+
+        let params =
+            generate_random_parameters_with_worker::<Bls12, _, _>(Cube(None), &worker, &mut rng)
+                .unwrap();
+        assert_eq!(
+            phases.lock().unwrap().drain(..).collect::<Vec<_>>(),
+            [
+                Phase::Synthesis,
+                Phase::Evaluation,
+                Phase::Fft,
+                Phase::Evaluation
+            ]
+        );
+
+        let x = Scalar::random(&mut rng);
+        let options = ProverOptions {
+            worker,
+            ..Default::default()
+        };
+        let proof =
+            create_random_proof_with_options(Cube(Some(x)), &params, &options, &mut rng).unwrap();
+        assert!(verify_proof(&prepare_verifying_key(&params.vk), &proof, &[x.cube()]).is_ok());
+        assert_eq!(
+            phases.lock().unwrap().drain(..).collect::<Vec<_>>(),
+            [Phase::Synthesis, Phase::Fft, Phase::Multiexp]
+        );
+
+        // A cancelled worker stops before doing any work.
+        let token = CancellationToken::new();
+        let worker = Worker::new().with_cancellation(token.clone());
+        assert!(!worker.is_cancelled());
+        token.cancel();
+        assert!(worker.is_cancelled());
+        assert!(matches!(
+            generate_random_parameters_with_worker::<Bls12, _, _>(Cube(None), &worker, &mut rng),
+            Err(SynthesisError::Cancelled)
+        ));
+        let options = ProverOptions {
+            worker,
+            ..Default::default()
+        };
+        assert!(matches!(
+            create_random_proof_with_options(Cube(Some(x)), &params, &options, &mut rng),
+            Err(SynthesisError::Cancelled)
+        ));
+
+        // Cancelling during a phase stops the tasks of that phase.
+        for cancel_at in [Phase::Evaluation, Phase::Fft, Phase::Multiexp] {
+            let token = CancellationToken::new();
+            let worker = {
+                let token = token.clone();
+                Worker::new()
+                    .with_cancellation(token.clone())
+                    .with_progress(move |phase| {
+                        if phase == cancel_at {
+                            token.cancel();
+                        }
+                    })
+            };
+            if cancel_at == Phase::Evaluation {
+                assert!(matches!(
+                    generate_random_parameters_with_worker::<Bls12, _, _>(
+                        Cube(None),
+                        &worker,
+                        &mut rng
+                    ),
+                    Err(SynthesisError::Cancelled)
+                ));
+            } else {
+                let options = ProverOptions {
+                    worker,
+                    ..Default::default()
+                };
+                assert!(matches!(
+                    create_random_proof_with_options(Cube(Some(x)), &params, &options, &mut rng),
+                    Err(SynthesisError::Cancelled)
+                ));
+            }
+            assert!(token.is_cancelled());
+        }
+    }
 }
@@ -6,15 +6,15 @@ use ff::{Field, PrimeField, PrimeFieldBits};
 use group::{prime::PrimeCurveAffine, Curve};
 use pairing::Engine;
 
-use super::{ParameterSource, Proof};
+use super::{ParameterSource, PrecomputedQueries, Proof};
 
 use crate::{Circuit, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
 
-use crate::domain::{EvaluationDomain, Scalar};
+use crate::domain::{DomainConfig, DomainPrecomputation, EvaluationDomain, Scalar};
 
-use crate::multiexp::{multiexp, DensityTracker, FullDensity};
+use crate::multiexp::{multiexp, multiexp_precomputed, DensityTracker, FullDensity};
 
-use crate::multicore::Worker;
+use crate::multicore::{Phase, Worker};
 
 fn eval<S: PrimeField>(
     lc: &LinearCombination<S>,
@@ -157,9 +157,114 @@ impl<S: PrimeField> ConstraintSystem<S> for ProvingAssignment<S> {
     }
 }
 
+/// Options for [`create_proof_with_options`] and
+/// [`create_random_proof_with_options`].
+#[derive(Clone)]
+pub struct ProverOptions<E: Engine> {
+    /// The worker that runs the FFTs and multiexps of the prover. Its
+    /// cancellation token and progress callback apply to the proof.
+    pub worker: Worker,
+    /// FFT tables for the evaluation domain of the circuit, as computed by
+    /// [`Parameters::domain_precomputation`], which can be shared by all proofs
+    /// for that circuit.
+    ///
+    /// [`Parameters::domain_precomputation`]: super::Parameters::domain_precomputation
+    pub precomputed: Option<Arc<DomainPrecomputation<E::Fr>>>,
+    /// Precomputed multiples of the `h` and `l` queries, as computed by
+    /// [`Parameters::precompute_queries`] for the parameters used to create
+    /// the proof. The multiexps for those queries then read their bases from
+    /// these tables instead of the parameters.
+    ///
+    /// [`Parameters::precompute_queries`]: super::Parameters::precompute_queries
+    pub queries: Option<Arc<PrecomputedQueries<E>>>,
+    /// The configuration of the evaluation domain, which must be the one the
+    /// parameters were generated with. It is ignored if `precomputed` is set,
+    /// since the precomputed tables carry their own configuration.
+    pub domain: DomainConfig<E::Fr>,
+}
+
+impl<E: Engine> Default for ProverOptions<E> {
+    fn default() -> Self {
+        ProverOptions {
+            worker: Worker::new(),
+            precomputed: None,
+            queries: None,
+            domain: DomainConfig::default(),
+        }
+    }
+}
+
 pub fn create_random_proof<E, C, R, P: ParameterSource<E>>(
     circuit: C,
     params: P,
+    rng: &mut R,
+) -> Result<Proof<E>, SynthesisError>
+where
+    E: Engine,
+    E::Fr: PrimeFieldBits,
+    C: Circuit<E::Fr>,
+    R: RngCore,
+{
+    create_random_proof_with_options(circuit, params, &ProverOptions::default(), rng)
+}
+
+/// Creates a proof like [`create_random_proof`], reading the bases of the `h`
+/// and `l` queries from `queries`, which must have been computed by
+/// [`Parameters::precompute_queries`] for `params`.
+///
+/// [`Parameters::precompute_queries`]: super::Parameters::precompute_queries
+pub fn create_random_proof_with_queries<E, C, R, P: ParameterSource<E>>(
+    circuit: C,
+    params: P,
+    queries: &Arc<PrecomputedQueries<E>>,
+    rng: &mut R,
+) -> Result<Proof<E>, SynthesisError>
+where
+    E: Engine,
+    E::Fr: PrimeFieldBits,
+    C: Circuit<E::Fr>,
+    R: RngCore,
+{
+    let options = ProverOptions {
+        queries: Some(queries.clone()),
+        ..Default::default()
+    };
+    create_random_proof_with_options(circuit, params, &options, rng)
+}
+
+/// Creates a random proof like [`create_random_proof`], reusing the FFT tables
+/// in `precomputed`, which must be for the evaluation domain of the circuit.
+///
+/// The tables for a circuit's domain are computed by
+/// [`Parameters::domain_precomputation`], and can be shared by all proofs for
+/// that circuit.
+///
+/// [`Parameters::domain_precomputation`]: super::Parameters::domain_precomputation
+pub fn create_random_proof_precomputed<E, C, R, P: ParameterSource<E>>(
+    circuit: C,
+    params: P,
+    precomputed: &Arc<DomainPrecomputation<E::Fr>>,
+    rng: &mut R,
+) -> Result<Proof<E>, SynthesisError>
+where
+    E: Engine,
+    E::Fr: PrimeFieldBits,
+    C: Circuit<E::Fr>,
+    R: RngCore,
+{
+    let options = ProverOptions {
+        precomputed: Some(precomputed.clone()),
+        ..Default::default()
+    };
+    create_random_proof_with_options(circuit, params, &options, rng)
+}
+
+/// Creates a random proof like [`create_random_proof`], with the given
+/// options.
+pub fn create_random_proof_with_options<E, C, R, P: ParameterSource<E>>(
+    circuit: C,
+    params: P,
+    options: &ProverOptions<E>,
     mut rng: &mut R,
 ) -> Result<Proof<E>, SynthesisError>
 where
@@ -171,13 +276,74 @@ where
     let r = E::Fr::random(&mut rng);
     let s = E::Fr::random(&mut rng);
 
-    create_proof::<E, C, P>(circuit, params, r, s)
+    create_proof_with_options::<E, C, P>(circuit, params, options, r, s)
 }
 
-#[allow(clippy::many_single_char_names)]
 pub fn create_proof<E, C, P: ParameterSource<E>>(
+    circuit: C,
+    params: P,
+    r: E::Fr,
+    s: E::Fr,
+) -> Result<Proof<E>, SynthesisError>
+where
+    E: Engine,
+    E::Fr: PrimeFieldBits,
+    C: Circuit<E::Fr>,
+{
+    create_proof_with_options(circuit, params, &ProverOptions::default(), r, s)
+}
+
+/// Creates a proof like [`create_proof`], reading the bases of the `h` and `l`
+/// queries from `queries`, which must have been computed by
+/// [`Parameters::precompute_queries`] for `params`.
+///
+/// [`Parameters::precompute_queries`]: super::Parameters::precompute_queries
+pub fn create_proof_with_queries<E, C, P: ParameterSource<E>>(
+    circuit: C,
+    params: P,
+    queries: &Arc<PrecomputedQueries<E>>,
+    r: E::Fr,
+    s: E::Fr,
+) -> Result<Proof<E>, SynthesisError>
+where
+    E: Engine,
+    E::Fr: PrimeFieldBits,
+    C: Circuit<E::Fr>,
+{
+    let options = ProverOptions {
+        queries: Some(queries.clone()),
+        ..Default::default()
+    };
+    create_proof_with_options(circuit, params, &options, r, s)
+}
+
+/// Creates a proof like [`create_proof`], reusing the FFT tables in
+/// `precomputed`, which must be for the evaluation domain of the circuit.
+pub fn create_proof_precomputed<E, C, P: ParameterSource<E>>(
+    circuit: C,
+    params: P,
+    precomputed: &Arc<DomainPrecomputation<E::Fr>>,
+    r: E::Fr,
+    s: E::Fr,
+) -> Result<Proof<E>, SynthesisError>
+where
+    E: Engine,
+    E::Fr: PrimeFieldBits,
+    C: Circuit<E::Fr>,
+{
+    let options = ProverOptions {
+        precomputed: Some(precomputed.clone()),
+        ..Default::default()
+    };
+    create_proof_with_options(circuit, params, &options, r, s)
+}
+
+/// Creates a proof like [`create_proof`], with the given options.
+#[allow(clippy::many_single_char_names)]
+pub fn create_proof_with_options<E, C, P: ParameterSource<E>>(
     circuit: C,
     mut params: P,
+    options: &ProverOptions<E>,
     r: E::Fr,
     s: E::Fr,
 ) -> Result<Proof<E>, SynthesisError>
@@ -197,42 +363,53 @@ where
         aux_assignment: vec![],
     };
 
+    let worker = &options.worker;
+
     prover.alloc_input(|| "", || Ok(E::Fr::one()))?;
 
+    worker.checkpoint(Phase::Synthesis)?;
     circuit.synthesize(&mut prover)?;
 
     for i in 0..prover.input_assignment.len() {
         prover.enforce(|| "", |lc| lc + Variable(Index::Input(i)), |lc| lc, |lc| lc);
     }
 
-    let worker = Worker::new();
-
     let vk = params.get_vk(prover.input_assignment.len())?;
 
+    worker.checkpoint(Phase::Fft)?;
     let h = {
-        let mut a = EvaluationDomain::from_coeffs(prover.a)?;
-        let mut b = EvaluationDomain::from_coeffs(prover.b)?;
-        let mut c = EvaluationDomain::from_coeffs(prover.c)?;
-        a.ifft(&worker);
-        a.coset_fft(&worker);
-        b.ifft(&worker);
-        b.coset_fft(&worker);
-        c.ifft(&worker);
-        c.coset_fft(&worker);
-
-        a.mul_assign(&worker, &b);
+        let domain = |coeffs| match &options.precomputed {
+            Some(precomputed) => EvaluationDomain::from_coeffs_precomputed(coeffs, precomputed),
+            None => EvaluationDomain::from_coeffs_with_config(coeffs, options.domain),
+        };
+        let mut a = domain(prover.a)?;
+        let mut b = domain(prover.b)?;
+        let mut c = domain(prover.c)?;
+        a.ifft(worker);
+        a.coset_fft(worker);
+        b.ifft(worker);
+        b.coset_fft(worker);
+        c.ifft(worker);
+        c.coset_fft(worker);
+
+        a.mul_assign(worker, &b);
         drop(b);
-        a.sub_assign(&worker, &c);
+        a.sub_assign(worker, &c);
         drop(c);
-        a.divide_by_z_on_coset(&worker);
-        a.icoset_fft(&worker);
+        a.divide_by_z_on_coset(worker);
+        a.icoset_fft(worker);
         let mut a = a.into_coeffs();
         let a_len = a.len() - 1;
         a.truncate(a_len);
+
+        worker.checkpoint(Phase::Multiexp)?;
         // TODO: parallelize if it's even helpful
         let a = Arc::new(a.into_iter().map(|s| s.0.into()).collect::<Vec<_>>());
 
-        multiexp(&worker, params.get_h(a.len())?, FullDensity, a)
+        match &options.queries {
+            Some(queries) => multiexp_precomputed(worker, &queries.h, FullDensity, a),
+            None => multiexp(worker, params.get_h(a.len())?, FullDensity, a),
+        }
     };
 
     // TODO: parallelize if it's even helpful
@@ -251,12 +428,17 @@ where
             .collect::<Vec<_>>(),
     );
 
-    let l = multiexp(
-        &worker,
-        params.get_l(aux_assignment.len())?,
-        FullDensity,
-        aux_assignment.clone(),
-    );
+    let l = match &options.queries {
+        Some(queries) => {
+            multiexp_precomputed(worker, &queries.l, FullDensity, aux_assignment.clone())
+        }
+        None => multiexp(
+            worker,
+            params.get_l(aux_assignment.len())?,
+            FullDensity,
+            aux_assignment.clone(),
+        ),
+    };
 
     let a_aux_density_total = prover.a_aux_density.get_total_density();
 
@@ -264,13 +446,13 @@ where
         params.get_a(input_assignment.len(), a_aux_density_total)?;
 
     let a_inputs = multiexp(
-        &worker,
+        worker,
         a_inputs_source,
         FullDensity,
         input_assignment.clone(),
     );
     let a_aux = multiexp(
-        &worker,
+        worker,
         a_aux_source,
         Arc::new(prover.a_aux_density),
         aux_assignment.clone(),
@@ -285,13 +467,13 @@ where
         params.get_b_g1(b_input_density_total, b_aux_density_total)?;
 
     let b_g1_inputs = multiexp(
-        &worker,
+        worker,
         b_g1_inputs_source,
         b_input_density.clone(),
         input_assignment.clone(),
     );
     let b_g1_aux = multiexp(
-        &worker,
+        worker,
         b_g1_aux_source,
         b_aux_density.clone(),
         aux_assignment.clone(),
@@ -301,12 +483,12 @@ where
         params.get_b_g2(b_input_density_total, b_aux_density_total)?;
 
     let b_g2_inputs = multiexp(
-        &worker,
+        worker,
         b_g2_inputs_source,
         b_input_density,
         input_assignment,
     );
-    let b_g2_aux = multiexp(&worker, b_g2_aux_source, b_aux_density, aux_assignment);
+    let b_g2_aux = multiexp(worker, b_g2_aux_source, b_aux_density, aux_assignment);
 
     if bool::from(vk.delta_g1.is_identity() | vk.delta_g2.is_identity()) {
         // If this element is zero, someone is trying to perform a
@@ -28,6 +28,9 @@ use rand_core::OsRng;
 #[cfg(feature = "multicore")]
 use rayon::{iter::ParallelIterator, prelude::ParallelSlice};
 
+#[cfg(feature = "multicore")]
+use crate::multicore::Worker;
+
 use crate::{
     groth16::{PreparedVerifyingKey, Proof, VerifyingKey},
     VerificationError,
@@ -180,8 +183,22 @@ where
     /// This performs the bulk of internal arithmetic over the global rayon
     /// threadpool.
     #[cfg(feature = "multicore")]
-    #[allow(non_snake_case)]
     pub fn verify_multicore(self, vk: &VerifyingKey<E>) -> Result<(), VerificationError> {
+        self.verify_multicore_with_worker(vk, &Worker::new())
+    }
+
+    /// Perform batch verification with a particular `VerifyingKey`, returning
+    /// `Ok(())` if all proofs were verified and `VerificationError` otherwise.
+    ///
+    /// This performs the bulk of internal arithmetic over the thread pool of
+    /// `worker`.
+    #[cfg(feature = "multicore")]
+    #[allow(non_snake_case)]
+    pub fn verify_multicore_with_worker(
+        self,
+        vk: &VerifyingKey<E>,
+        worker: &Worker,
+    ) -> Result<(), VerificationError> {
         if self
             .items
             .iter()
@@ -210,53 +227,54 @@ where
 
         let ic_len = vk.ic.len();
 
-        let acc = self
-            .items
-            // This chunk size was obtained heuristically.
-            .par_chunks(8)
-            .map(|items| {
-                let mut acc = Accumulator::<E>::new(ic_len);
-                let mut ml_terms: Vec<(E::G1Affine, E::G2Prepared)> = vec![];
-                let z = loop {
-                    let z = E::Fr::random(&mut OsRng);
-                    if !z.is_zero_vartime() {
-                        break z;
-                    }
-                };
-                let mut cur_z = z;
-                for Item { proof, inputs } in items {
-                    acc.gammas[0] += &cur_z;
-                    for (a_i, acc_gamma_i) in
-                        Iterator::zip(inputs.iter(), acc.gammas.iter_mut().skip(1))
-                    {
-                        *acc_gamma_i += &(cur_z * a_i);
-                    }
-                    acc.delta += proof.c * cur_z;
-                    acc.y += &cur_z;
-                    ml_terms.push(((proof.a * cur_z).into(), (-proof.b).into()));
-
-                    cur_z *= z;
-                }
-                let ml_terms = ml_terms.iter().map(|(a, b)| (a, b)).collect::<Vec<_>>();
-                acc.ml_result = Some(E::multi_miller_loop(&ml_terms[..]));
-                acc
-            })
-            .reduce(
-                || Accumulator::<E>::new(ic_len),
-                |mut a, b| {
-                    for (a, b) in a.gammas.iter_mut().zip(b.gammas.into_iter()) {
-                        *a += b;
-                    }
-                    a.delta += b.delta;
-                    a.y += b.y;
-                    a.ml_result = match (a.ml_result, b.ml_result) {
-                        (Some(a), Some(b)) => Some(a + b),
-                        (Some(a), None) | (None, Some(a)) => Some(a),
-                        (None, None) => None,
+        let acc = worker.install(|| {
+            self.items
+                // This chunk size was obtained heuristically.
+                .par_chunks(8)
+                .map(|items| {
+                    let mut acc = Accumulator::<E>::new(ic_len);
+                    let mut ml_terms: Vec<(E::G1Affine, E::G2Prepared)> = vec![];
+                    let z = loop {
+                        let z = E::Fr::random(&mut OsRng);
+                        if !z.is_zero_vartime() {
+                            break z;
+                        }
                     };
-                    a
-                },
-            );
+                    let mut cur_z = z;
+                    for Item { proof, inputs } in items {
+                        acc.gammas[0] += &cur_z;
+                        for (a_i, acc_gamma_i) in
+                            Iterator::zip(inputs.iter(), acc.gammas.iter_mut().skip(1))
+                        {
+                            *acc_gamma_i += &(cur_z * a_i);
+                        }
+                        acc.delta += proof.c * cur_z;
+                        acc.y += &cur_z;
+                        ml_terms.push(((proof.a * cur_z).into(), (-proof.b).into()));
+
+                        cur_z *= z;
+                    }
+                    let ml_terms = ml_terms.iter().map(|(a, b)| (a, b)).collect::<Vec<_>>();
+                    acc.ml_result = Some(E::multi_miller_loop(&ml_terms[..]));
+                    acc
+                })
+                .reduce(
+                    || Accumulator::<E>::new(ic_len),
+                    |mut a, b| {
+                        for (a, b) in a.gammas.iter_mut().zip(b.gammas.into_iter()) {
+                            *a += b;
+                        }
+                        a.delta += b.delta;
+                        a.y += b.y;
+                        a.ml_result = match (a.ml_result, b.ml_result) {
+                            (Some(a), Some(b)) => Some(a + b),
+                            (Some(a), None) | (None, Some(a)) => Some(a),
+                            (None, None) => None,
+                        };
+                        a
+                    },
+                )
+        });
 
         match acc.ml_result {
             None => Ok(()),
@@ -0,0 +1,748 @@
+//! The [KZG] polynomial commitment scheme.
+//!
+//! A structured reference string ([`Srs`]) holds the powers of a secret `tau`
+//! in both source groups of a pairing. A polynomial `p` of degree at most
+//! [`Srs::max_degree`] is committed to as `[p(tau)]_1`, which is computed with
+//! [`multiexp`]. An opening proves the value of `p` at a point, or at several
+//! points at once, with a single group element that is checked with pairings.
+//!
+//! ```
+//! use bellman::{kzg::Srs, multicore::Worker};
+//! use bls12_381::{Bls12, Scalar};
+//! use rand_core::OsRng;
+//!
+//! let pool = Worker::new();
+//! let srs = Srs::<Bls12>::generate_random(&pool, 16, 2, &mut OsRng);
+//!
+//! // p(X) = 1 + 2X + 3X^2
+//! let poly = [1, 2, 3].map(Scalar::from);
+//! let commitment = srs.commit(&pool, &poly)?;
+//! let (value, proof) = srs.open(&pool, &poly, Scalar::from(2))?;
+//! assert_eq!(value, Scalar::from(17));
+//! assert!(srs.verify(&commitment, Scalar::from(2), value, &proof).is_ok());
+//! # Ok::<(), bellman::SynthesisError>(())
+//! ```
+//!
+//! [KZG]: https://www.iacr.org/archive/asiacrypt2010/6477178/6477178.pdf
+
+use std::io::{self, Read, Write};
+use std::ops::{AddAssign, MulAssign, Neg, SubAssign};
+use std::sync::Arc;
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use ff::{Field, PrimeFieldBits};
+use group::{
+    prime::{PrimeCurve, PrimeCurveAffine},
+    Curve, Group, UncompressedEncoding, Wnaf, WnafGroup,
+};
+use pairing::{Engine, MillerLoopResult, MultiMillerLoop};
+use rand_core::RngCore;
+
+use crate::domain::{self, EvaluationDomain};
+use crate::multicore::Worker;
+use crate::multiexp::{multiexp, Exponent, FullDensity};
+use crate::poly::DensePolynomial;
+use crate::{SynthesisError, VerificationError};
+
+/// A structured reference string for committing to polynomials of degree at
+/// most [`Srs::max_degree`], and opening them at up to [`Srs::max_points`]
+/// points at once.
+#[derive(Clone, Debug)]
+pub struct Srs<E: Engine> {
+    // [tau^i]_1 for 0 <= i <= max_degree
+    g1_powers: Arc<Vec<E::G1Affine>>,
+    // [tau^i]_2 for 0 <= i <= max_points
+    g2_powers: Arc<Vec<E::G2Affine>>,
+}
+
+impl<E: Engine> PartialEq for Srs<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.g1_powers == other.g1_powers && self.g2_powers == other.g2_powers
+    }
+}
+
+impl<E> Srs<E>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+{
+    /// Performs a local setup with a random `tau`, which is discarded.
+    ///
+    /// Whoever runs the setup is trusted to have forgotten `tau`; anyone who
+    /// knows it can open commitments to arbitrary values.
+    pub fn generate_random<R: RngCore>(
+        pool: &Worker,
+        max_degree: usize,
+        max_points: usize,
+        mut rng: R,
+    ) -> Self {
+        Self::generate(pool, max_degree, max_points, E::Fr::random(&mut rng))
+    }
+
+    /// Performs a local setup with the given `tau`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_points` is zero.
+    pub fn generate(pool: &Worker, max_degree: usize, max_points: usize, tau: E::Fr) -> Self {
+        assert!(max_points > 0, "an SRS must support opening at one point");
+
+        Srs {
+            g1_powers: Arc::new(powers_of_tau(pool, E::G1::generator(), tau, max_degree + 1)),
+            g2_powers: Arc::new(powers_of_tau(pool, E::G2::generator(), tau, max_points + 1)),
+        }
+    }
+}
+
+fn powers_of_tau<G: WnafGroup + PrimeCurve>(
+    pool: &Worker,
+    generator: G,
+    tau: G::Scalar,
+    n: usize,
+) -> Vec<G::Affine> {
+    let mut wnaf = Wnaf::new();
+    let wnaf = wnaf.base(generator, n);
+
+    let mut powers = vec![G::Affine::identity(); n];
+    pool.scope(n, |scope, chunk| {
+        for (i, powers) in powers.chunks_mut(chunk).enumerate() {
+            let mut wnaf = wnaf.shared();
+
+            scope.spawn(move |_scope| {
+                let mut power = tau.pow_vartime([(i * chunk) as u64]);
+                let projective: Vec<_> = (0..powers.len())
+                    .map(|_| {
+                        let p = wnaf.scalar(&power);
+                        power.mul_assign(&tau);
+                        p
+                    })
+                    .collect();
+
+                G::batch_normalize(&projective, powers);
+            });
+        }
+    });
+
+    powers
+}
+
+impl<E: Engine> Srs<E>
+where
+    E::Fr: PrimeFieldBits,
+{
+    /// Returns the maximum degree of a committed polynomial.
+    pub fn max_degree(&self) -> usize {
+        self.g1_powers.len() - 1
+    }
+
+    /// Returns the maximum number of points for [`Srs::open_batch`].
+    pub fn max_points(&self) -> usize {
+        self.g2_powers.len() - 1
+    }
+
+    /// Returns `[tau^i]_1` for `0 <= i <= max_degree`.
+    pub fn g1_powers(&self) -> &[E::G1Affine] {
+        &self.g1_powers
+    }
+
+    /// Returns `[tau^i]_2` for `0 <= i <= max_points`.
+    pub fn g2_powers(&self) -> &[E::G2Affine] {
+        &self.g2_powers
+    }
+
+    /// Commits to the polynomial with coefficients `poly`, lowest degree
+    /// first.
+    pub fn commit(&self, pool: &Worker, poly: &[E::Fr]) -> Result<E::G1Affine, SynthesisError> {
+        Ok(msm::<E::G1>(pool, &self.g1_powers, poly)?.to_affine())
+    }
+
+    /// Evaluates `poly` at `point`, and returns the value along with a proof
+    /// of the evaluation.
+    pub fn open(
+        &self,
+        pool: &Worker,
+        poly: &[E::Fr],
+        point: E::Fr,
+    ) -> Result<(E::Fr, E::G1Affine), SynthesisError> {
+        // p(X) - p(z) = q(X) * (X - z)
+        let (quotient, value) = DensePolynomial::new(poly.to_vec()).divide_by_linear(point);
+
+        Ok((value, self.commit(pool, quotient.coeffs())?))
+    }
+
+    /// Evaluates `poly` at each of the distinct `points`, and returns the
+    /// values along with a single proof of all the evaluations.
+    pub fn open_batch(
+        &self,
+        pool: &Worker,
+        poly: &[E::Fr],
+        points: &[E::Fr],
+    ) -> Result<(Vec<E::Fr>, E::G1Affine), SynthesisError> {
+        if points.len() > self.max_points() {
+            return Err(SynthesisError::PolynomialDegreeTooLarge);
+        }
+
+        // p(X) - I(X) = q(X) * Z(X), where Z vanishes on the points and I
+        // interpolates p on them.
+        let poly = DensePolynomial::new(poly.to_vec());
+        let (quotient, _) = poly
+            .divide(&DensePolynomial::vanishing(points))
+            .expect("vanishing polynomials are monic");
+        let values = points.iter().map(|point| poly.evaluate(point)).collect();
+
+        Ok((values, self.commit(pool, quotient.coeffs())?))
+    }
+
+    /// Computes the commitments to the Lagrange basis polynomials of the
+    /// radix-2 [`EvaluationDomain`] with at least `size` elements.
+    pub fn lagrange_basis(
+        &self,
+        pool: &Worker,
+        size: usize,
+    ) -> Result<LagrangeBasis<E>, SynthesisError> {
+        let mut domain = EvaluationDomain::from_coeffs(vec![Projective(E::G1::identity()); size])?;
+        let n = domain.as_ref().len();
+        if n > self.g1_powers.len() {
+            return Err(SynthesisError::PolynomialDegreeTooLarge);
+        }
+
+        for (point, power) in domain.as_mut().iter_mut().zip(self.g1_powers.iter()) {
+            point.0 = power.to_curve();
+        }
+
+        // [L_i(tau)] = 1/n sum_j omega^{-ij} [tau^j]
+        domain.ifft(pool);
+        let projective: Vec<_> = domain.into_coeffs().into_iter().map(|p| p.0).collect();
+        let mut bases = vec![E::G1Affine::identity(); n];
+        E::G1::batch_normalize(&projective, &mut bases);
+
+        Ok(LagrangeBasis {
+            bases: Arc::new(bases),
+        })
+    }
+
+    /// Updates the SRS in place by multiplying `tau` with a random secret,
+    /// which is discarded, and returns a proof that the new SRS was derived
+    /// from the old one.
+    ///
+    /// The updated SRS is secure as long as either the original setup or this
+    /// update was performed honestly, so that an SRS can be shared by many
+    /// circuits and strengthened by anyone who uses it. Others can check the
+    /// update with [`Srs::verify_update`] and [`Srs::verify_powers`].
+    pub fn update<R: RngCore>(&mut self, pool: &Worker, mut rng: R) -> UpdateProof<E> {
+        let secret = E::Fr::random(&mut rng);
+        self.g1_powers = Arc::new(scale_powers::<E::G1>(pool, &self.g1_powers, secret));
+        self.g2_powers = Arc::new(scale_powers::<E::G2>(pool, &self.g2_powers, secret));
+
+        UpdateProof {
+            secret_g2: (E::G2Affine::generator() * secret).to_affine(),
+        }
+    }
+}
+
+/// A proof from [`Srs::update`] that an SRS was derived from a previous one by
+/// multiplying its `tau` with a secret.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UpdateProof<E: Engine> {
+    // [secret]_2
+    secret_g2: E::G2Affine,
+}
+
+impl<E: Engine> UpdateProof<E> {
+    /// Returns `[secret]_2`.
+    pub fn secret_g2(&self) -> &E::G2Affine {
+        &self.secret_g2
+    }
+}
+
+/// Returns `secret^i * powers[i]` for each `i`.
+fn scale_powers<G: PrimeCurve>(
+    pool: &Worker,
+    powers: &[G::Affine],
+    secret: G::Scalar,
+) -> Vec<G::Affine> {
+    let mut scaled = vec![G::Affine::identity(); powers.len()];
+    pool.scope(powers.len(), |scope, chunk| {
+        for (i, (scaled, powers)) in scaled
+            .chunks_mut(chunk)
+            .zip(powers.chunks(chunk))
+            .enumerate()
+        {
+            scope.spawn(move |_scope| {
+                let mut power = secret.pow_vartime([(i * chunk) as u64]);
+                let projective: Vec<_> = powers
+                    .iter()
+                    .map(|p| {
+                        let p = *p * power;
+                        power.mul_assign(&secret);
+                        p
+                    })
+                    .collect();
+
+                G::batch_normalize(&projective, scaled);
+            });
+        }
+    });
+
+    scaled
+}
+
+impl<E: MultiMillerLoop> Srs<E>
+where
+    E::Fr: PrimeFieldBits,
+{
+    /// Checks a proof from [`Srs::open`] that the polynomial committed to by
+    /// `commitment` evaluates to `value` at `point`.
+    pub fn verify(
+        &self,
+        commitment: &E::G1Affine,
+        point: E::Fr,
+        value: E::Fr,
+        proof: &E::G1Affine,
+    ) -> Result<(), VerificationError> {
+        // e(C - [v] + z * W, [1]) = e(W, [tau])
+        let mut lhs = commitment.to_curve();
+        lhs.sub_assign(&(self.g1_powers[0] * value));
+        lhs.add_assign(&(*proof * point));
+
+        self.check(&lhs.to_affine(), proof, &self.g2_powers[1])
+    }
+
+    /// Checks a proof from [`Srs::open_batch`] that the polynomial committed to
+    /// by `commitment` evaluates to `values` at `points`.
+    pub fn verify_batch(
+        &self,
+        pool: &Worker,
+        commitment: &E::G1Affine,
+        points: &[E::Fr],
+        values: &[E::Fr],
+        proof: &E::G1Affine,
+    ) -> Result<(), VerificationError> {
+        if points.len() != values.len() {
+            return Err(VerificationError::InvalidProof);
+        }
+        if points.len() > self.max_points() {
+            return Err(VerificationError::InvalidVerifyingKey);
+        }
+
+        // e(C - [I(tau)], [1]) = e(W, [Z(tau)])
+        let interpolation =
+            DensePolynomial::interpolate(points, values).ok_or(VerificationError::InvalidProof)?;
+        let interpolation = msm::<E::G1>(pool, &self.g1_powers, interpolation.coeffs())
+            .map_err(|_| VerificationError::InvalidVerifyingKey)?;
+        let vanishing = DensePolynomial::vanishing(points);
+        let vanishing = msm::<E::G2>(pool, &self.g2_powers, vanishing.coeffs())
+            .map_err(|_| VerificationError::InvalidVerifyingKey)?;
+
+        let lhs = commitment.to_curve() - interpolation;
+        self.check(&lhs.to_affine(), proof, &vanishing.to_affine())
+    }
+
+    /// Checks that the SRS consists of consecutive powers of a single `tau`,
+    /// starting from the generators, as it should after [`Srs::read`] or
+    /// [`Srs::update`].
+    ///
+    /// All the powers are checked at once with random weights drawn from
+    /// `rng`.
+    pub fn verify_powers<R: RngCore>(
+        &self,
+        pool: &Worker,
+        mut rng: R,
+    ) -> Result<(), VerificationError> {
+        if self.g1_powers.len() < 2
+            || self.g1_powers[0] != E::G1Affine::generator()
+            || self.g2_powers[0] != E::G2Affine::generator()
+        {
+            return Err(VerificationError::InvalidVerifyingKey);
+        }
+
+        // e(sum_i r_i [tau^i]_1, [tau]_2) = e(sum_i r_i [tau^{i+1}]_1, [1]_2)
+        // e([tau]_1, sum_j s_j [tau^j]_2) = e([1]_1, sum_j s_j [tau^{j+1}]_2)
+        let r: Vec<_> = (1..self.g1_powers.len())
+            .map(|_| E::Fr::random(&mut rng))
+            .collect();
+        let s: Vec<_> = (1..self.g2_powers.len())
+            .map(|_| E::Fr::random(&mut rng))
+            .collect();
+        let g1_shifted = Arc::new(self.g1_powers[1..].to_vec());
+        let g2_shifted = Arc::new(self.g2_powers[1..].to_vec());
+
+        let invalid = |_| VerificationError::InvalidVerifyingKey;
+        let g1 = msm::<E::G1>(pool, &self.g1_powers, &r).map_err(invalid)?;
+        let g1_shifted = msm::<E::G1>(pool, &g1_shifted, &r).map_err(invalid)?;
+        let g2 = msm::<E::G2>(pool, &self.g2_powers, &s).map_err(invalid)?;
+        let g2_shifted = msm::<E::G2>(pool, &g2_shifted, &s).map_err(invalid)?;
+
+        let result = E::multi_miller_loop(&[
+            (&g1.to_affine(), &self.g2_powers[1].into()),
+            (&(-g1_shifted).to_affine(), &self.g2_powers[0].into()),
+            (&self.g1_powers[1], &g2.to_affine().into()),
+            (&(-self.g1_powers[0]), &g2_shifted.to_affine().into()),
+        ])
+        .final_exponentiation();
+
+        if bool::from(result.is_identity()) {
+            Ok(())
+        } else {
+            Err(VerificationError::InvalidVerifyingKey)
+        }
+    }
+
+    /// Checks a proof from [`Srs::update`] that this SRS was derived from
+    /// `previous`.
+    ///
+    /// Only `[tau]_1` is compared with `previous`, so the rest of this SRS
+    /// should be checked with [`Srs::verify_powers`].
+    pub fn verify_update(
+        &self,
+        previous: &Srs<E>,
+        proof: &UpdateProof<E>,
+    ) -> Result<(), VerificationError> {
+        if self.g1_powers.len() < 2
+            || previous.g1_powers.len() < 2
+            || bool::from(proof.secret_g2.is_identity())
+        {
+            return Err(VerificationError::InvalidVerifyingKey);
+        }
+
+        // e([tau * secret]_1, [1]_2) = e([tau]_1, [secret]_2)
+        self.check(&self.g1_powers[1], &previous.g1_powers[1], &proof.secret_g2)
+            .map_err(|_| VerificationError::InvalidVerifyingKey)
+    }
+
+    // Checks that e(lhs, [1]) = e(proof, rhs).
+    fn check(
+        &self,
+        lhs: &E::G1Affine,
+        proof: &E::G1Affine,
+        rhs: &E::G2Affine,
+    ) -> Result<(), VerificationError> {
+        let result = E::multi_miller_loop(&[
+            (lhs, &self.g2_powers[0].into()),
+            (&proof.neg(), &(*rhs).into()),
+        ])
+        .final_exponentiation();
+
+        if bool::from(result.is_identity()) {
+            Ok(())
+        } else {
+            Err(VerificationError::InvalidProof)
+        }
+    }
+}
+
+impl<E: Engine> Srs<E> {
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_u32::<BigEndian>(self.g1_powers.len() as u32)?;
+        for g in &self.g1_powers[..] {
+            writer.write_all(g.to_uncompressed().as_ref())?;
+        }
+
+        writer.write_u32::<BigEndian>(self.g2_powers.len() as u32)?;
+        for g in &self.g2_powers[..] {
+            writer.write_all(g.to_uncompressed().as_ref())?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads an SRS written by [`Srs::write`].
+    ///
+    /// If `checked` is set, every point is checked to be in the correct
+    /// subgroup. The points are not checked to be consecutive powers of the
+    /// same `tau`.
+    pub fn read<R: Read>(mut reader: R, checked: bool) -> io::Result<Self> {
+        let g1_powers = read_points::<E::G1Affine, _>(&mut reader, checked, "invalid G1")?;
+        let g2_powers = read_points::<E::G2Affine, _>(&mut reader, checked, "invalid G2")?;
+
+        if g1_powers.is_empty() || g2_powers.len() < 2 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not enough powers of tau",
+            ));
+        }
+
+        Ok(Srs {
+            g1_powers: Arc::new(g1_powers),
+            g2_powers: Arc::new(g2_powers),
+        })
+    }
+}
+
+fn read_points<G: UncompressedEncoding + PrimeCurveAffine, R: Read>(
+    mut reader: R,
+    checked: bool,
+    invalid: &'static str,
+) -> io::Result<Vec<G>> {
+    let len = reader.read_u32::<BigEndian>()? as usize;
+
+    (0..len)
+        .map(|_| {
+            let mut repr = G::Uncompressed::default();
+            reader.read_exact(repr.as_mut())?;
+
+            let affine = if checked {
+                G::from_uncompressed(&repr)
+            } else {
+                G::from_uncompressed_unchecked(&repr)
+            };
+
+            match Option::<G>::from(affine) {
+                None => Err(io::Error::new(io::ErrorKind::InvalidData, invalid)),
+                Some(e) if e.is_identity().into() => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "point at infinity",
+                )),
+                Some(e) => Ok(e),
+            }
+        })
+        .collect()
+}
+
+/// Commitments to the Lagrange basis polynomials of an [`EvaluationDomain`],
+/// for committing to polynomials given by their evaluations over the domain.
+#[derive(Clone, Debug)]
+pub struct LagrangeBasis<E: Engine> {
+    bases: Arc<Vec<E::G1Affine>>,
+}
+
+impl<E: Engine> LagrangeBasis<E>
+where
+    E::Fr: PrimeFieldBits,
+{
+    /// Returns the size of the evaluation domain.
+    pub fn len(&self) -> usize {
+        self.bases.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bases.is_empty()
+    }
+
+    /// Returns the commitments to each Lagrange basis polynomial.
+    pub fn bases(&self) -> &[E::G1Affine] {
+        &self.bases
+    }
+
+    /// Commits to the polynomial with the given evaluations at the powers of
+    /// the domain's root of unity, which are zero past the end of
+    /// `evaluations`.
+    ///
+    /// The commitment is the same as [`Srs::commit`] for the polynomial's
+    /// coefficients.
+    pub fn commit(
+        &self,
+        pool: &Worker,
+        evaluations: &[E::Fr],
+    ) -> Result<E::G1Affine, SynthesisError> {
+        Ok(msm::<E::G1>(pool, &self.bases, evaluations)?.to_affine())
+    }
+}
+
+/// A curve point that can be transformed by an [`EvaluationDomain`].
+#[derive(Clone, Copy, PartialEq)]
+struct Projective<G>(G);
+
+impl<G: PrimeCurve> domain::Group<G::Scalar> for Projective<G> {
+    fn group_zero() -> Self {
+        Projective(G::identity())
+    }
+    fn group_mul_assign(&mut self, by: &G::Scalar) {
+        self.0.mul_assign(by);
+    }
+    fn group_add_assign(&mut self, other: &Self) {
+        self.0.add_assign(&other.0);
+    }
+    fn group_sub_assign(&mut self, other: &Self) {
+        self.0.sub_assign(&other.0);
+    }
+}
+
+fn msm<G: PrimeCurve>(
+    pool: &Worker,
+    bases: &Arc<Vec<G::Affine>>,
+    scalars: &[G::Scalar],
+) -> Result<G, SynthesisError>
+where
+    G::Scalar: PrimeFieldBits,
+{
+    if scalars.len() > bases.len() {
+        return Err(SynthesisError::PolynomialDegreeTooLarge);
+    }
+    if scalars.is_empty() {
+        return Ok(G::identity());
+    }
+
+    let exponents = scalars.iter().map(Exponent::from).collect();
+    multiexp(pool, (bases.clone(), 0), FullDensity, Arc::new(exponents)).wait()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bls12_381::{Bls12, Scalar};
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    use crate::domain::Scalar as DomainScalar;
+    use crate::poly::evaluate;
+
+    fn rng() -> XorShiftRng {
+        XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ])
+    }
+
+    fn random_poly(rng: &mut XorShiftRng, len: usize) -> Vec<Scalar> {
+        (0..len).map(|_| Scalar::random(&mut *rng)).collect()
+    }
+
+    #[test]
+    fn commit_open_verify() {
+        let mut rng = rng();
+        let pool = Worker::new();
+        let srs = Srs::<Bls12>::generate_random(&pool, 31, 4, &mut rng);
+        assert_eq!(srs.max_degree(), 31);
+        assert_eq!(srs.max_points(), 4);
+
+        let poly = random_poly(&mut rng, 32);
+        let commitment = srs.commit(&pool, &poly).unwrap();
+        let point = Scalar::random(&mut rng);
+
+        let (value, proof) = srs.open(&pool, &poly, point).unwrap();
+        assert_eq!(value, evaluate(&poly, &point));
+        assert!(srs.verify(&commitment, point, value, &proof).is_ok());
+        assert!(srs
+            .verify(&commitment, point, value + Scalar::one(), &proof)
+            .is_err());
+        assert!(srs
+            .verify(&commitment, point + Scalar::one(), value, &proof)
+            .is_err());
+
+        assert!(matches!(
+            srs.commit(&pool, &random_poly(&mut rng, 33)),
+            Err(SynthesisError::PolynomialDegreeTooLarge)
+        ));
+    }
+
+    #[test]
+    fn batch_open() {
+        let mut rng = rng();
+        let pool = Worker::new();
+        let srs = Srs::<Bls12>::generate_random(&pool, 15, 4, &mut rng);
+
+        let poly = random_poly(&mut rng, 16);
+        let commitment = srs.commit(&pool, &poly).unwrap();
+        let points = random_poly(&mut rng, 4);
+
+        let (values, proof) = srs.open_batch(&pool, &poly, &points).unwrap();
+        assert!(srs
+            .verify_batch(&pool, &commitment, &points, &values, &proof)
+            .is_ok());
+        assert!(srs
+            .verify_batch(&pool, &commitment, &points[..3], &values[..3], &proof)
+            .is_err());
+
+        let mut wrong = values.clone();
+        wrong[1] += Scalar::one();
+        assert!(srs
+            .verify_batch(&pool, &commitment, &points, &wrong, &proof)
+            .is_err());
+
+        // A single point is equivalent to a regular opening.
+        let (values, proof) = srs.open_batch(&pool, &poly, &points[..1]).unwrap();
+        assert!(srs
+            .verify(&commitment, points[0], values[0], &proof)
+            .is_ok());
+
+        assert!(srs
+            .open_batch(&pool, &poly, &random_poly(&mut rng, 5))
+            .is_err());
+    }
+
+    #[test]
+    fn lagrange_commitments() {
+        let mut rng = rng();
+        let pool = Worker::new();
+        let srs = Srs::<Bls12>::generate_random(&pool, 15, 1, &mut rng);
+
+        let basis = srs.lagrange_basis(&pool, 12).unwrap();
+        assert_eq!(basis.len(), 16);
+        assert!(srs.lagrange_basis(&pool, 17).is_err());
+
+        let evaluations = random_poly(&mut rng, 16);
+        let mut domain =
+            EvaluationDomain::from_coeffs(evaluations.iter().copied().map(DomainScalar).collect())
+                .unwrap();
+        domain.ifft(&pool);
+        let coeffs: Vec<_> = domain.into_coeffs().into_iter().map(|s| s.0).collect();
+
+        assert_eq!(
+            basis.commit(&pool, &evaluations).unwrap(),
+            srs.commit(&pool, &coeffs).unwrap()
+        );
+    }
+
+    #[test]
+    fn update_srs() {
+        let mut rng = rng();
+        let pool = Worker::new();
+        let srs = Srs::<Bls12>::generate_random(&pool, 7, 2, &mut rng);
+        assert!(srs.verify_powers(&pool, &mut rng).is_ok());
+
+        let mut updated = srs.clone();
+        let update = updated.update(&pool, &mut rng);
+        assert!(updated != srs);
+        assert!(updated.verify_powers(&pool, &mut rng).is_ok());
+        assert!(updated.verify_update(&srs, &update).is_ok());
+        assert!(srs.verify_update(&srs, &update).is_err());
+
+        let mut again = updated.clone();
+        let second = again.update(&pool, &mut rng);
+        assert!(again.verify_update(&updated, &second).is_ok());
+        assert!(again.verify_update(&srs, &second).is_err());
+        assert!(again.verify_update(&updated, &update).is_err());
+
+        let poly = random_poly(&mut rng, 8);
+        let point = Scalar::random(&mut rng);
+        let commitment = updated.commit(&pool, &poly).unwrap();
+        let (value, proof) = updated.open(&pool, &poly, point).unwrap();
+        assert!(updated.verify(&commitment, point, value, &proof).is_ok());
+        assert!(srs.verify(&commitment, point, value, &proof).is_err());
+
+        let mut g1_powers = updated.g1_powers.to_vec();
+        g1_powers.swap(2, 3);
+        let mut corrupted = updated.clone();
+        corrupted.g1_powers = Arc::new(g1_powers);
+        assert!(corrupted.verify_powers(&pool, &mut rng).is_err());
+
+        let mut corrupted = updated.clone();
+        corrupted.g2_powers = srs.g2_powers.clone();
+        assert!(corrupted.verify_powers(&pool, &mut rng).is_err());
+    }
+
+    #[test]
+    fn srs_serialization() {
+        let mut rng = rng();
+        let pool = Worker::new();
+        let srs = Srs::<Bls12>::generate_random(&pool, 7, 2, &mut rng);
+
+        let mut v = vec![];
+        srs.write(&mut v).unwrap();
+        assert_eq!(v.len(), 4 + 8 * 96 + 4 + 3 * 192);
+
+        let de = Srs::<Bls12>::read(&v[..], true).unwrap();
+        assert!(srs == de);
+        let de = Srs::<Bls12>::read(&v[..], false).unwrap();
+        assert!(srs == de);
+
+        // Truncated to a single G2 power.
+        let mut truncated = v[..4 + 8 * 96].to_vec();
+        truncated.extend_from_slice(&1u32.to_be_bytes());
+        truncated.extend_from_slice(&v[4 + 8 * 96 + 4..][..192]);
+        assert!(Srs::<Bls12>::read(&truncated[..], true).is_err());
+    }
+}
@@ -0,0 +1,67 @@
+//! Normal forms for linear combinations, shared by the constraint systems that
+//! compare or rewrite constraints.
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use ff::PrimeField;
+
+use crate::{Index, Variable};
+
+/// A [`Variable`] ordered with inputs before auxiliary variables, each by
+/// index.
+#[derive(Clone, Copy)]
+pub(crate) struct OrderedVariable(pub(crate) Variable);
+
+impl Eq for OrderedVariable {}
+impl PartialEq for OrderedVariable {
+    fn eq(&self, other: &OrderedVariable) -> bool {
+        match (self.0.get_unchecked(), other.0.get_unchecked()) {
+            (Index::Input(ref a), Index::Input(ref b)) => a == b,
+            (Index::Aux(ref a), Index::Aux(ref b)) => a == b,
+            _ => false,
+        }
+    }
+}
+impl PartialOrd for OrderedVariable {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for OrderedVariable {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.0.get_unchecked(), other.0.get_unchecked()) {
+            (Index::Input(ref a), Index::Input(ref b)) => a.cmp(b),
+            (Index::Aux(ref a), Index::Aux(ref b)) => a.cmp(b),
+            (Index::Input(_), Index::Aux(_)) => Ordering::Less,
+            (Index::Aux(_), Index::Input(_)) => Ordering::Greater,
+        }
+    }
+}
+
+/// Normalizes a linear combination by merging repeated variables and removing
+/// zero coefficients, ordering inputs before auxiliary variables.
+pub(crate) fn proc_lc<Scalar: PrimeField>(
+    terms: &[(Variable, Scalar)],
+) -> BTreeMap<OrderedVariable, Scalar> {
+    let mut map = BTreeMap::new();
+    for &(var, coeff) in terms {
+        map.entry(OrderedVariable(var))
+            .or_insert_with(Scalar::zero)
+            .add_assign(&coeff);
+    }
+
+    // Remove terms that have a zero coefficient to normalize
+    let mut to_remove = vec![];
+    for (var, coeff) in map.iter() {
+        if coeff.is_zero_vartime() {
+            to_remove.push(*var);
+        }
+    }
+
+    for var in to_remove {
+        map.remove(&var);
+    }
+
+    map
+}
@@ -137,12 +137,21 @@
 // Catch documentation errors caused by code changes.
 #![deny(rustdoc::broken_intra_doc_links)]
 
+pub mod circom;
+pub mod commitment;
 pub mod domain;
 pub mod gadgets;
 #[cfg(feature = "groth16")]
 pub mod groth16;
+#[cfg(feature = "pairing")]
+pub mod kzg;
+mod lc;
 pub mod multicore;
 pub mod multiexp;
+pub mod optimizer;
+#[cfg(feature = "plonk")]
+pub mod plonk;
+pub mod poly;
 
 use ff::PrimeField;
 
@@ -319,6 +328,8 @@ pub enum SynthesisError {
     IoError(io::Error),
     /// During CRS generation, we observed an unconstrained auxiliary variable
     UnconstrainedVariable,
+    /// The computation was cancelled through its worker's cancellation token
+    Cancelled,
 }
 
 impl From<io::Error> for SynthesisError {
@@ -341,6 +352,7 @@ impl fmt::Display for SynthesisError {
             SynthesisError::UnexpectedIdentity => "encountered an identity element in the CRS",
             SynthesisError::IoError(_) => "encountered an I/O error",
             SynthesisError::UnconstrainedVariable => "auxiliary variable was unconstrained",
+            SynthesisError::Cancelled => "the computation was cancelled",
         };
         if let SynthesisError::IoError(ref e) = *self {
             write!(f, "I/O error: ")?;
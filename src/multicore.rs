@@ -1,33 +1,204 @@
 //! An interface for dealing with the kinds of parallel computations involved in
 //! `bellman`. It's currently just a thin wrapper around [`rayon`] but may be
 //! extended in the future to allow for various parallelism strategies.
+//!
+//! By default, a [`Worker`] shares the global rayon thread pool with the rest
+//! of the process. Applications that run several provers side by side can give
+//! each of them a dedicated pool with [`Worker::with_num_threads`] or
+//! [`Worker::with_pool`].
+//!
+//! A worker can also carry a [`CancellationToken`], which long-running
+//! computations such as proof creation check between their phases and inside
+//! their parallel tasks, and a callback that is told when each [`Phase`]
+//! begins.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::SynthesisError;
+
+/// A handle for cancelling the computations of the workers that hold it.
+///
+/// Clones of a token share its state, so one clone can be given to a
+/// [`Worker`] and another kept to cancel it from a different thread.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    /// Requests cancellation. Computations stop at the next point where they
+    /// check the token, and return [`SynthesisError::Cancelled`].
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// The phases of proof and parameter creation, reported to the progress
+/// callback of a [`Worker`] as they begin.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Phase {
+    /// The circuit is being synthesized.
+    Synthesis,
+    /// FFTs over the evaluation domain are being computed.
+    Fft,
+    /// The queries of the parameters are being evaluated.
+    Evaluation,
+    /// The multiexps of the proof are being computed.
+    Multiexp,
+}
+
+/// The cancellation token and progress callback of a worker.
+#[derive(Clone, Default)]
+struct Control {
+    cancellation: Option<CancellationToken>,
+    progress: Option<Arc<dyn Fn(Phase) + Send + Sync>>,
+}
+
+impl Worker {
+    /// Returns this worker with `token`, which cancels the computations that
+    /// are run with it.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Worker {
+        self.control.cancellation = Some(token);
+        self
+    }
+
+    /// Returns this worker with a callback that is called on the calling
+    /// thread of a computation when each of its phases begins.
+    pub fn with_progress<F>(mut self, progress: F) -> Worker
+    where
+        F: Fn(Phase) + Send + Sync + 'static,
+    {
+        self.control.progress = Some(Arc::new(progress));
+        self
+    }
+
+    /// Returns whether the computations of this worker have been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.control
+            .cancellation
+            .as_ref()
+            .map_or(false, CancellationToken::is_cancelled)
+    }
+
+    /// Reports the start of `phase`, or returns [`SynthesisError::Cancelled`]
+    /// if the computation has been cancelled.
+    pub(crate) fn checkpoint(&self, phase: Phase) -> Result<(), SynthesisError> {
+        self.check_cancelled()?;
+        if let Some(progress) = &self.control.progress {
+            progress(phase);
+        }
+        Ok(())
+    }
+
+    /// Returns [`SynthesisError::Cancelled`] if the computation has been
+    /// cancelled.
+    pub(crate) fn check_cancelled(&self) -> Result<(), SynthesisError> {
+        if self.is_cancelled() {
+            Err(SynthesisError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
 
 #[cfg(feature = "multicore")]
 mod implementation {
     use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::Control;
 
     use crossbeam_channel::{bounded, Receiver};
-    use lazy_static::lazy_static;
     use log::{error, trace};
-    use rayon::current_num_threads;
+    use rayon::{current_num_threads, ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
 
     static WORKER_SPAWN_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
-    lazy_static! {
-        // See Worker::compute below for a description of this.
-        static ref WORKER_SPAWN_MAX_COUNT: usize = current_num_threads() * 4;
+    /// A thread pool owned by one or more workers.
+    struct Pool {
+        threads: ThreadPool,
+        spawn_counter: AtomicUsize,
     }
 
+    /// Runs parallel computations, either on the global rayon thread pool or
+    /// on a dedicated one.
     #[derive(Clone, Default)]
-    pub struct Worker {}
+    pub struct Worker {
+        pool: Option<Arc<Pool>>,
+        pub(super) control: Control,
+    }
 
     impl Worker {
+        /// Returns a worker that uses the global rayon thread pool.
         pub fn new() -> Worker {
-            Worker {}
+            Worker {
+                pool: None,
+                control: Control::default(),
+            }
+        }
+
+        /// Returns a worker that runs its computations on `pool`, so that they
+        /// don't compete with other work on the global thread pool.
+        ///
+        /// Clones of the worker share the pool.
+        pub fn with_pool(pool: ThreadPool) -> Worker {
+            Worker {
+                pool: Some(Arc::new(Pool {
+                    threads: pool,
+                    spawn_counter: AtomicUsize::new(0),
+                })),
+                control: Control::default(),
+            }
+        }
+
+        /// Returns a worker with a dedicated pool of `num_threads` threads, or
+        /// as many threads as rayon would use by default if `num_threads` is
+        /// zero.
+        pub fn with_num_threads(num_threads: usize) -> Result<Worker, ThreadPoolBuildError> {
+            ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .build()
+                .map(Worker::with_pool)
+        }
+
+        /// The number of threads that computations are spread over.
+        pub fn num_threads(&self) -> usize {
+            match &self.pool {
+                Some(pool) => pool.threads.current_num_threads(),
+                None => current_num_threads(),
+            }
         }
 
         pub fn log_num_threads(&self) -> u32 {
-            log2_floor(current_num_threads())
+            log2_floor(self.num_threads())
+        }
+
+        /// Runs `op` within the thread pool of this worker, so that any rayon
+        /// parallel iterators that it uses run on that pool.
+        pub fn install<F, R>(&self, op: F) -> R
+        where
+            F: FnOnce() -> R + Send,
+            R: Send,
+        {
+            match &self.pool {
+                Some(pool) => pool.threads.install(op),
+                None => op(),
+            }
+        }
+
+        fn spawn_counter(&self) -> &AtomicUsize {
+            match &self.pool {
+                Some(pool) => &pool.spawn_counter,
+                None => &WORKER_SPAWN_COUNTER,
+            }
         }
 
         pub fn compute<F, R>(&self, f: F) -> Waiter<R>
@@ -44,7 +215,8 @@ mod implementation {
             // limits, we run the risk of memory exhaustion due to limited
             // stack space consumed by all of the pending closures to be
             // executed.
-            let previous_count = WORKER_SPAWN_COUNTER.fetch_add(1, Ordering::SeqCst);
+            let previous_count = self.spawn_counter().fetch_add(1, Ordering::SeqCst);
+            let num_threads = self.num_threads();
 
             // If the number of spawns requested has exceeded the number
             // of cores available for processing by some factor (the
@@ -53,23 +225,33 @@ mod implementation {
             // scope call (which blocks the current thread) to help clear
             // the growing work queue and minimize the chances of memory
             // exhaustion.
-            if previous_count > *WORKER_SPAWN_MAX_COUNT {
+            if previous_count > num_threads * 4 {
                 let thread_index = rayon::current_thread_index().unwrap_or(0);
-                rayon::scope(move |_| {
+                let worker = self.clone();
+                self.scope(0, move |_, _| {
                     trace!("[{}] switching to scope to help clear backlog [threads: current {}, requested {}]",
                         thread_index,
-                        current_num_threads(),
-                        WORKER_SPAWN_COUNTER.load(Ordering::SeqCst));
+                        num_threads,
+                        worker.spawn_counter().load(Ordering::SeqCst));
                     let res = f();
-                    sender.send(res).unwrap();
-                    WORKER_SPAWN_COUNTER.fetch_sub(1, Ordering::SeqCst);
+                    // The waiter may have been dropped after an earlier
+                    // computation failed.
+                    let _ = sender.send(res);
+                    worker.spawn_counter().fetch_sub(1, Ordering::SeqCst);
                 });
             } else {
-                rayon::spawn(move || {
+                let worker = self.clone();
+                let task = move || {
                     let res = f();
-                    sender.send(res).unwrap();
-                    WORKER_SPAWN_COUNTER.fetch_sub(1, Ordering::SeqCst);
-                });
+                    // The waiter may have been dropped after an earlier
+                    // computation failed.
+                    let _ = sender.send(res);
+                    worker.spawn_counter().fetch_sub(1, Ordering::SeqCst);
+                };
+                match &self.pool {
+                    Some(pool) => pool.threads.spawn(task),
+                    None => rayon::spawn(task),
+                }
             }
 
             Waiter { receiver }
@@ -80,14 +262,17 @@ mod implementation {
             F: FnOnce(&rayon::Scope<'a>, usize) -> R + Send,
             R: Send,
         {
-            let num_threads = current_num_threads();
+            let num_threads = self.num_threads();
             let chunk_size = if elements < num_threads {
                 1
             } else {
                 elements / num_threads
             };
 
-            rayon::scope(|scope| f(scope, chunk_size))
+            match &self.pool {
+                Some(pool) => pool.threads.scope(|scope| f(scope, chunk_size)),
+                None => rayon::scope(|scope| f(scope, chunk_size)),
+            }
         }
     }
 
@@ -140,22 +325,64 @@ mod implementation {
         assert_eq!(log2_floor(7), 2);
         assert_eq!(log2_floor(8), 3);
     }
+
+    #[test]
+    fn dedicated_pool() {
+        let worker = Worker::with_num_threads(3).unwrap();
+        assert_eq!(worker.num_threads(), 3);
+        assert_eq!(worker.log_num_threads(), 1);
+        assert_eq!(worker.clone().num_threads(), 3);
+
+        // Computations run on the threads of the pool.
+        let waiters: Vec<_> = (0..32)
+            .map(|i| worker.compute(move || (i, rayon::current_num_threads())))
+            .collect();
+        for (i, waiter) in waiters.into_iter().enumerate() {
+            assert_eq!(waiter.wait(), (i, 3));
+        }
+
+        let mut values = vec![0; 100];
+        worker.scope(values.len(), |scope, chunk| {
+            for (i, values) in values.chunks_mut(chunk).enumerate() {
+                scope.spawn(move |_| {
+                    assert_eq!(rayon::current_num_threads(), 3);
+                    for (j, value) in values.iter_mut().enumerate() {
+                        *value = i * chunk + j;
+                    }
+                });
+            }
+        });
+        assert!(values.iter().enumerate().all(|(i, &v)| i == v));
+        assert_eq!(worker.install(rayon::current_num_threads), 3);
+    }
 }
 
 #[cfg(not(feature = "multicore"))]
 mod implementation {
-    #[derive(Clone)]
-    pub struct Worker;
+    use super::Control;
+
+    #[derive(Clone, Default)]
+    pub struct Worker {
+        pub(super) control: Control,
+    }
 
     impl Worker {
         pub fn new() -> Worker {
-            Worker
+            Worker::default()
+        }
+
+        pub fn num_threads(&self) -> usize {
+            1
         }
 
         pub fn log_num_threads(&self) -> u32 {
             0
         }
 
+        pub fn install<F: FnOnce() -> R, R>(&self, op: F) -> R {
+            op()
+        }
+
         pub fn compute<F, R>(&self, f: F) -> Waiter<R>
         where
             F: FnOnce() -> R + Send + 'static,
@@ -1,10 +1,10 @@
 use super::multicore::{Waiter, Worker};
 use bitvec::vec::BitVec;
-use ff::{FieldBits, PrimeField, PrimeFieldBits};
+use ff::{Field, FieldBits, PrimeField, PrimeFieldBits};
 use group::prime::{PrimeCurve, PrimeCurveAffine};
 use std::io;
 use std::iter;
-use std::ops::AddAssign;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
 use std::sync::Arc;
 
 #[cfg(feature = "multicore")]
@@ -15,6 +15,8 @@ use crate::multicore::FakeParallelIterator;
 
 use super::SynthesisError;
 
+mod bls12;
+
 /// An object that builds a source of bases.
 pub trait SourceBuilder<G: PrimeCurveAffine>: Send + Sync + 'static + Clone {
     type Source: Source<G>;
@@ -39,6 +41,16 @@ pub trait AddAssignFromSource: PrimeCurve {
         AddAssign::<&<Self as PrimeCurve>::Affine>::add_assign(self, source.next()?);
         Ok(())
     }
+
+    /// Parses the element from the source and subtracts it. Fails if the point
+    /// is at infinity.
+    fn sub_assign_from_source<S: Source<<Self as PrimeCurve>::Affine>>(
+        &mut self,
+        source: &mut S,
+    ) -> Result<(), SynthesisError> {
+        SubAssign::<&<Self as PrimeCurve>::Affine>::sub_assign(self, source.next()?);
+        Ok(())
+    }
 }
 impl<G> AddAssignFromSource for G where G: PrimeCurve {}
 
@@ -159,7 +171,77 @@ impl DensityTracker {
 enum ChunkedExponent {
     Zero,
     One,
-    Chunks(Vec<u64>),
+    Chunks(Vec<i64>),
+}
+
+/// How exponents are split into digits when sorting bases into buckets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Digits {
+    /// Unsigned `c`-bit windows, each with `2^c - 1` buckets.
+    Unsigned,
+    /// Signed `c`-bit windows with digits in `(-2^(c-1), 2^(c-1)]`, each with
+    /// `2^(c-1)` buckets. Negative digits subtract their base from a bucket.
+    /// This may need one more window than [`Digits::Unsigned`].
+    Signed,
+}
+
+impl Digits {
+    /// Returns the number of `c`-bit windows needed for `num_bits`-bit
+    /// exponents.
+    fn num_windows(self, num_bits: u32, c: u32) -> u32 {
+        match self {
+            Digits::Unsigned => (num_bits + c - 1) / c,
+            // Leave room for the final carry.
+            Digits::Signed => (num_bits + c) / c,
+        }
+    }
+
+    fn num_buckets(self, c: u32) -> usize {
+        match self {
+            Digits::Unsigned => (1 << c) - 1,
+            Digits::Signed => 1 << (c - 1),
+        }
+    }
+}
+
+/// The largest window size that [`window_size`] will choose.
+const MAX_WINDOW_SIZE: u32 = 20;
+
+/// Chooses a window size for a multiexp over `num_exponents` exponents of
+/// `num_bits` bits.
+///
+/// Each window costs one addition per exponent, plus about two additions per
+/// bucket for the summation by parts. Windows are computed in parallel, so a
+/// window size is chosen to minimize the cost of the rounds that `num_threads`
+/// threads need to process all of the windows.
+pub fn window_size(digits: Digits, num_bits: u32, num_exponents: usize, num_threads: usize) -> u32 {
+    let num_threads = num_threads.max(1);
+
+    (2..=MAX_WINDOW_SIZE)
+        .min_by_key(|&c| {
+            let windows = digits.num_windows(num_bits, c) as usize;
+            let rounds = (windows + num_threads - 1) / num_threads;
+            rounds * (num_exponents + 2 * digits.num_buckets(c))
+        })
+        .unwrap()
+}
+
+/// Options for [`multiexp_with_config`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MultiexpConfig {
+    /// How exponents are split into digits.
+    pub digits: Digits,
+    /// The window size in bits, or `None` to choose one with [`window_size`].
+    pub window: Option<u32>,
+}
+
+impl Default for MultiexpConfig {
+    fn default() -> Self {
+        MultiexpConfig {
+            digits: Digits::Signed,
+            window: None,
+        }
+    }
 }
 
 /// An exponent
@@ -188,30 +270,300 @@ impl<F: PrimeFieldBits> From<F> for Exponent<F> {
 }
 
 impl<F: PrimeFieldBits> Exponent<F> {
-    fn chunks(&self, c: usize) -> ChunkedExponent {
+    fn chunks(&self, c: usize, digits: Digits) -> ChunkedExponent {
         match self {
             Self::Zero => ChunkedExponent::Zero,
             Self::One => ChunkedExponent::One,
-            Self::Bits(exp) => ChunkedExponent::Chunks(
-                exp.chunks(c)
-                    .map(|chunk| {
-                        chunk
-                            .iter()
-                            .by_vals()
-                            .enumerate()
-                            .fold(0u64, |acc, (i, b)| acc + ((b as u64) << i))
-                    })
-                    .collect(),
-            ),
+            Self::Bits(exp) => {
+                let chunks = exp.chunks(c).map(|chunk| {
+                    chunk
+                        .iter()
+                        .by_vals()
+                        .enumerate()
+                        .fold(0i64, |acc, (i, b)| acc + ((b as i64) << i))
+                });
+
+                ChunkedExponent::Chunks(match digits {
+                    Digits::Unsigned => chunks.collect(),
+                    Digits::Signed => {
+                        // Recode each chunk into (-2^(c-1), 2^(c-1)], carrying
+                        // into the next chunk.
+                        let half = 1 << (c - 1);
+                        let mut carry = 0;
+                        chunks
+                            .chain(iter::repeat(0))
+                            .take(digits.num_windows(F::NUM_BITS, c as u32) as usize)
+                            .map(|chunk| {
+                                let digit = chunk + carry;
+                                if digit > half {
+                                    carry = 1;
+                                    digit - (1 << c)
+                                } else {
+                                    carry = 0;
+                                    digit
+                                }
+                            })
+                            .collect()
+                    }
+                })
+            }
         }
     }
 }
 
-fn multiexp_inner<Q, D, G, S>(
+/// A strategy for accumulating bases into buckets.
+trait Buckets<G: PrimeCurve>: Sized {
+    fn new(len: usize) -> Self;
+
+    /// Adds the next base from the source to `bucket`, or subtracts it if
+    /// `negate` is set.
+    fn add_from_source<S: Source<G::Affine>>(
+        &mut self,
+        bucket: usize,
+        negate: bool,
+        source: &mut S,
+    ) -> Result<(), SynthesisError>;
+
+    /// Returns the sum of each bucket weighted by its index plus one.
+    fn weighted_sum(self) -> G;
+}
+
+/// Buckets accumulated in projective coordinates.
+struct ProjectiveBuckets<G>(Vec<G>);
+
+impl<G: PrimeCurve> Buckets<G> for ProjectiveBuckets<G> {
+    fn new(len: usize) -> Self {
+        ProjectiveBuckets(vec![G::identity(); len])
+    }
+
+    fn add_from_source<S: Source<G::Affine>>(
+        &mut self,
+        bucket: usize,
+        negate: bool,
+        source: &mut S,
+    ) -> Result<(), SynthesisError> {
+        if negate {
+            self.0[bucket].sub_assign_from_source(source)
+        } else {
+            self.0[bucket].add_assign_from_source(source)
+        }
+    }
+
+    fn weighted_sum(self) -> G {
+        // Summation by parts
+        // e.g. 3a + 2b + 1c = a +
+        //                    (a) + b +
+        //                    ((a) + b) + c
+        let mut acc = G::identity();
+        let mut running_sum = G::identity();
+        for exp in self.0.into_iter().rev() {
+            running_sum.add_assign(&exp);
+            acc.add_assign(&running_sum);
+        }
+
+        acc
+    }
+}
+
+/// The arithmetic in the base field of a curve that adding points in affine
+/// coordinates needs.
+///
+/// This is implemented for every [`Field`], and separately for the base fields
+/// of BLS12-381, which `bls12_381` does not expose.
+pub trait BaseField:
+    Copy
+    + PartialEq
+    + Send
+    + Sync
+    + 'static
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Neg<Output = Self>
+{
+    /// Returns one.
+    fn one() -> Self;
+
+    /// Returns the inverse of this element, or `None` if it is zero.
+    fn invert(&self) -> Option<Self>;
+}
+
+impl<F: Field> BaseField for F {
+    fn one() -> F {
+        F::one()
+    }
+
+    fn invert(&self) -> Option<F> {
+        Field::invert(self).into()
+    }
+}
+
+/// Affine points on a short Weierstrass curve `y^2 = x^3 + ax + b` with
+/// accessible coordinates, which allows [`multiexp_batch_affine`] to
+/// accumulate buckets in affine coordinates.
+///
+/// This is implemented for the affine points of the BLS12-381 groups.
+pub trait WeierstrassAffine: PrimeCurveAffine {
+    /// The base field of the curve.
+    type Base: BaseField;
+
+    /// Returns the coefficient `a` of the curve equation.
+    fn coeff_a() -> Self::Base;
+
+    /// Returns the coordinates of this point, or `None` if it is the identity.
+    fn to_xy(&self) -> Option<(Self::Base, Self::Base)>;
+
+    /// Returns the point with the given coordinates, which must be on the curve
+    /// and in the prime-order subgroup.
+    fn from_xy_unchecked(x: Self::Base, y: Self::Base) -> Self;
+}
+
+/// The maximum number of bucket additions that share an inversion.
+const BATCH_AFFINE_SIZE: usize = 256;
+
+type Coordinates<A> = (
+    <A as WeierstrassAffine>::Base,
+    <A as WeierstrassAffine>::Base,
+);
+
+/// Buckets accumulated in affine coordinates.
+///
+/// Each affine addition needs an inversion, so additions are queued until a
+/// batch of them can share a single inversion with Montgomery's trick. A
+/// batch holds at most one addition per bucket; any others wait for a later
+/// batch.
+struct AffineBuckets<A: WeierstrassAffine> {
+    buckets: Vec<Option<Coordinates<A>>>,
+    pending: Vec<(usize, Coordinates<A>)>,
+    in_batch: Vec<bool>,
+    deferred: Vec<(usize, Coordinates<A>)>,
+}
+
+impl<A: WeierstrassAffine> AffineBuckets<A> {
+    fn add(&mut self, bucket: usize, point: Coordinates<A>) {
+        if self.in_batch[bucket] {
+            self.deferred.push((bucket, point));
+        } else {
+            self.in_batch[bucket] = true;
+            self.pending.push((bucket, point));
+            if self.pending.len() == BATCH_AFFINE_SIZE {
+                self.apply_batch();
+            }
+        }
+    }
+
+    fn apply_batch(&mut self) {
+        let one = A::Base::one();
+
+        // The denominator of the slope of each addition. Points in the
+        // prime-order subgroup have odd order, so none of them has y = 0.
+        let mut inverses = self
+            .pending
+            .iter()
+            .map(|&(bucket, (x2, y2))| match self.buckets[bucket] {
+                Some((x1, y1)) if x1 == x2 && y1 == y2 => y1 + y1,
+                Some((x1, _)) if x1 != x2 => x2 - x1,
+                // The bucket is empty, or the points cancel.
+                _ => one,
+            })
+            .collect::<Vec<_>>();
+
+        // Montgomery's trick: invert the product of the denominators, and
+        // recover each inverse from the partial products.
+        let mut partial = Vec::with_capacity(inverses.len());
+        let mut acc = one;
+        for d in &inverses {
+            partial.push(acc);
+            acc = acc * *d;
+        }
+        let mut acc = acc.invert().unwrap();
+        for (d, partial) in inverses.iter_mut().zip(partial).rev() {
+            let inverse = acc * partial;
+            acc = acc * *d;
+            *d = inverse;
+        }
+
+        for (&(bucket, (x2, y2)), inverse) in self.pending.iter().zip(inverses) {
+            let sum = &mut self.buckets[bucket];
+            *sum = match *sum {
+                None => Some((x2, y2)),
+                Some((x1, y1)) => {
+                    let lambda = if x1 != x2 {
+                        (y2 - y1) * inverse
+                    } else if y1 == y2 {
+                        let x1_squared = x1 * x1;
+                        (x1_squared + x1_squared + x1_squared + A::coeff_a()) * inverse
+                    } else {
+                        self.in_batch[bucket] = false;
+                        *sum = None;
+                        continue;
+                    };
+
+                    let x3 = lambda * lambda - x1 - x2;
+                    Some((x3, lambda * (x1 - x3) - y1))
+                }
+            };
+            self.in_batch[bucket] = false;
+        }
+        self.pending.clear();
+    }
+}
+
+impl<G> Buckets<G> for AffineBuckets<G::Affine>
+where
+    G: PrimeCurve,
+    G::Affine: WeierstrassAffine,
+{
+    fn new(len: usize) -> Self {
+        AffineBuckets {
+            buckets: vec![None; len],
+            pending: Vec::with_capacity(BATCH_AFFINE_SIZE),
+            in_batch: vec![false; len],
+            deferred: vec![],
+        }
+    }
+
+    fn add_from_source<S: Source<G::Affine>>(
+        &mut self,
+        bucket: usize,
+        negate: bool,
+        source: &mut S,
+    ) -> Result<(), SynthesisError> {
+        // The source rejects the point at infinity.
+        let (x, y) = source.next()?.to_xy().unwrap();
+        self.add(bucket, (x, if negate { -y } else { y }));
+
+        Ok(())
+    }
+
+    fn weighted_sum(mut self) -> G {
+        while !self.pending.is_empty() || !self.deferred.is_empty() {
+            self.apply_batch();
+            for (bucket, point) in std::mem::take(&mut self.deferred) {
+                self.add(bucket, point);
+            }
+        }
+
+        ProjectiveBuckets(
+            self.buckets
+                .into_iter()
+                .map(|bucket| match bucket {
+                    Some((x, y)) => G::Affine::from_xy_unchecked(x, y).to_curve(),
+                    None => G::identity(),
+                })
+                .collect(),
+        )
+        .weighted_sum()
+    }
+}
+
+fn multiexp_inner<Q, D, G, S, B>(
+    pool: &Worker,
     bases: S,
     density_map: D,
     exponents: Arc<Vec<Exponent<G::Scalar>>>,
     c: u32,
+    digits: Digits,
 ) -> Result<G, SynthesisError>
 where
     for<'a> &'a Q: QueryDensity,
@@ -219,6 +571,7 @@ where
     G: PrimeCurve,
     G::Scalar: PrimeFieldBits,
     S: SourceBuilder<<G as PrimeCurve>::Affine>,
+    B: Buckets<G>,
 {
     // Perform this region of the multiexp
     let this = move |bases: S,
@@ -226,6 +579,8 @@ where
                      exponents: Arc<Vec<ChunkedExponent>>,
                      chunk: usize|
           -> Result<_, SynthesisError> {
+        pool.check_cancelled()?;
+
         // Accumulate the result
         let mut acc = G::identity();
 
@@ -233,7 +588,7 @@ where
         let mut bases = bases.build();
 
         // Create space for the buckets
-        let mut buckets = vec![G::identity(); (1 << c) - 1];
+        let mut buckets = B::new(digits.num_buckets(c));
 
         // only the first round uses this
         let handle_trivial = chunk == 0;
@@ -251,11 +606,11 @@ where
                         }
                     }
                     ChunkedExponent::Chunks(chunks) => {
-                        let exp = chunks[chunk];
+                        let exp = chunks.get(chunk).copied().unwrap_or(0);
 
                         if exp != 0 {
-                            (&mut buckets[(exp - 1) as usize])
-                                .add_assign_from_source(&mut bases)?;
+                            let bucket = (exp.unsigned_abs() - 1) as usize;
+                            buckets.add_from_source(bucket, exp < 0, &mut bases)?;
                         } else {
                             bases.skip(1)?;
                         }
@@ -264,32 +619,27 @@ where
             }
         }
 
-        // Summation by parts
-        // e.g. 3a + 2b + 1c = a +
-        //                    (a) + b +
-        //                    ((a) + b) + c
-        let mut running_sum = G::identity();
-        for exp in buckets.into_iter().rev() {
-            running_sum.add_assign(&exp);
-            acc.add_assign(&running_sum);
-        }
-
-        Ok(acc)
+        Ok(acc + buckets.weighted_sum())
     };
 
     // Split the exponents into chunks.
     let exponents = Arc::new(
         exponents
             .iter()
-            .map(|exp| exp.chunks(c as usize))
+            .map(|exp| exp.chunks(c as usize, digits))
             .collect::<Vec<_>>(),
     );
 
-    let parts = (0..G::Scalar::NUM_BITS)
+    let parts = (0..digits.num_windows(G::Scalar::NUM_BITS, c))
         .into_par_iter()
-        .step_by(c as usize)
-        .enumerate()
-        .map(|(chunk, _)| this(bases.clone(), density_map.clone(), exponents.clone(), chunk))
+        .map(|chunk| {
+            this(
+                bases.clone(),
+                density_map.clone(),
+                exponents.clone(),
+                chunk as usize,
+            )
+        })
         .collect::<Vec<Result<_, _>>>();
 
     parts
@@ -321,6 +671,37 @@ where
         (f64::from(exponents.len() as u32)).ln().ceil() as u32
     };
 
+    multiexp_with_config(
+        pool,
+        bases,
+        density_map,
+        exponents,
+        MultiexpConfig {
+            digits: Digits::Unsigned,
+            window: Some(c),
+        },
+    )
+}
+
+/// Perform multi-exponentiation with the given digit representation and
+/// window size. The caller is responsible for ensuring the query size is the
+/// same as the number of exponents.
+pub fn multiexp_with_config<Q, D, G, S>(
+    pool: &Worker,
+    bases: S,
+    density_map: D,
+    exponents: Arc<Vec<Exponent<G::Scalar>>>,
+    config: MultiexpConfig,
+) -> Waiter<Result<G, SynthesisError>>
+where
+    for<'a> &'a Q: QueryDensity,
+    D: Send + Sync + 'static + Clone + AsRef<Q>,
+    G: PrimeCurve,
+    G::Scalar: PrimeFieldBits,
+    S: SourceBuilder<<G as PrimeCurve>::Affine>,
+{
+    let c = window_for::<G::Scalar>(pool, config, exponents.len());
+
     if let Some(query_size) = density_map.as_ref().get_query_size() {
         // If the density map has a known query size, it should not be
         // inconsistent with the number of exponents.
@@ -328,27 +709,278 @@ where
         assert!(query_size == exponents.len());
     }
 
-    pool.compute(move || multiexp_inner(bases, density_map, exponents, c))
+    let worker = pool.clone();
+    pool.compute(move || {
+        multiexp_inner::<_, _, _, _, ProjectiveBuckets<G>>(
+            &worker,
+            bases,
+            density_map,
+            exponents,
+            c,
+            config.digits,
+        )
+    })
 }
 
-#[cfg(feature = "pairing")]
-#[test]
-fn test_with_bls12() {
-    fn naive_multiexp<G: PrimeCurve>(
-        bases: Arc<Vec<<G as PrimeCurve>::Affine>>,
-        exponents: Arc<Vec<G::Scalar>>,
-    ) -> G {
-        assert_eq!(bases.len(), exponents.len());
+/// Perform multi-exponentiation, accumulating buckets in affine coordinates
+/// with batched inversions instead of in projective coordinates. The caller
+/// is responsible for ensuring the query size is the same as the number of
+/// exponents.
+pub fn multiexp_batch_affine<Q, D, G, S>(
+    pool: &Worker,
+    bases: S,
+    density_map: D,
+    exponents: Arc<Vec<Exponent<G::Scalar>>>,
+    config: MultiexpConfig,
+) -> Waiter<Result<G, SynthesisError>>
+where
+    for<'a> &'a Q: QueryDensity,
+    D: Send + Sync + 'static + Clone + AsRef<Q>,
+    G: PrimeCurve,
+    G::Affine: WeierstrassAffine,
+    G::Scalar: PrimeFieldBits,
+    S: SourceBuilder<<G as PrimeCurve>::Affine>,
+{
+    let c = window_for::<G::Scalar>(pool, config, exponents.len());
 
-        let mut acc = G::identity();
+    if let Some(query_size) = density_map.as_ref().get_query_size() {
+        assert!(query_size == exponents.len());
+    }
+
+    let worker = pool.clone();
+    pool.compute(move || {
+        multiexp_inner::<_, _, _, _, AffineBuckets<G::Affine>>(
+            &worker,
+            bases,
+            density_map,
+            exponents,
+            c,
+            config.digits,
+        )
+    })
+}
 
-        for (base, exp) in bases.iter().zip(exponents.iter()) {
-            AddAssign::<&G>::add_assign(&mut acc, &(*base * *exp));
+/// Returns the window size to use for `config`.
+fn window_for<F: PrimeField>(pool: &Worker, config: MultiexpConfig, num_exponents: usize) -> u32 {
+    let c = config.window.unwrap_or_else(|| {
+        window_size(
+            config.digits,
+            F::NUM_BITS,
+            num_exponents,
+            1 << pool.log_num_threads(),
+        )
+    });
+    assert!(c > 0 && c < 63, "window size must be between 1 and 62 bits");
+    assert!(
+        c > 1 || config.digits == Digits::Unsigned,
+        "signed digits need a window of at least 2 bits"
+    );
+
+    c
+}
+
+/// Fixed bases together with precomputed multiples of each, which can be
+/// reused across many multiexps against the same bases, such as the `h` and
+/// `l` queries of Groth16 parameters.
+///
+/// For a window size of `c`, each base `P` is stored along with `2^(c*j) P`
+/// for every signed-digit window `j`. A multiexp then needs no doublings and
+/// only a single set of buckets, at the cost of storing one point per window
+/// for each base.
+pub struct PrecomputedBases<G: PrimeCurve> {
+    window: u32,
+    num_windows: usize,
+    // The multiples of base `i` are at `i * num_windows..(i + 1) * num_windows`.
+    table: Arc<Vec<G::Affine>>,
+}
+
+impl<G: PrimeCurve> Clone for PrecomputedBases<G> {
+    fn clone(&self) -> Self {
+        PrecomputedBases {
+            window: self.window,
+            num_windows: self.num_windows,
+            table: self.table.clone(),
         }
+    }
+}
 
-        acc
+impl<G> PrecomputedBases<G>
+where
+    G: PrimeCurve,
+    G::Scalar: PrimeFieldBits,
+{
+    /// Precomputes the multiples of `bases` for a window size of `window`
+    /// bits. Fails if any base is the point at infinity.
+    pub fn new(pool: &Worker, bases: &[G::Affine], window: u32) -> Result<Self, SynthesisError> {
+        assert!(
+            window > 1 && window < 63,
+            "window size must be between 2 and 62 bits"
+        );
+
+        if bases.iter().any(|base| bool::from(base.is_identity())) {
+            return Err(SynthesisError::UnexpectedIdentity);
+        }
+
+        let num_windows = Digits::Signed.num_windows(G::Scalar::NUM_BITS, window) as usize;
+        let mut table = vec![G::Affine::identity(); bases.len() * num_windows];
+
+        if !bases.is_empty() {
+            pool.scope(bases.len(), |scope, chunk| {
+                for (bases, table) in bases
+                    .chunks(chunk)
+                    .zip(table.chunks_mut(chunk * num_windows))
+                {
+                    scope.spawn(move |_scope| {
+                        let mut multiples = Vec::with_capacity(table.len());
+                        for base in bases {
+                            let mut multiple = base.to_curve();
+                            for _ in 0..num_windows {
+                                multiples.push(multiple);
+                                for _ in 0..window {
+                                    multiple = multiple.double();
+                                }
+                            }
+                        }
+
+                        G::batch_normalize(&multiples, table);
+                    });
+                }
+            });
+        }
+
+        Ok(PrecomputedBases {
+            window,
+            num_windows,
+            table: Arc::new(table),
+        })
+    }
+
+    /// Returns the number of bases.
+    pub fn len(&self) -> usize {
+        self.table.len() / self.num_windows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    /// Returns the window size that the multiples were computed for.
+    pub fn window(&self) -> u32 {
+        self.window
+    }
+}
+
+/// Perform multi-exponentiation against precomputed bases. As with
+/// [`multiexp`], the bases correspond to the dense entries of `density_map`,
+/// and the caller is responsible for ensuring the query size is the same as
+/// the number of exponents.
+pub fn multiexp_precomputed<Q, D, G>(
+    pool: &Worker,
+    bases: &PrecomputedBases<G>,
+    density_map: D,
+    exponents: Arc<Vec<Exponent<G::Scalar>>>,
+) -> Waiter<Result<G, SynthesisError>>
+where
+    for<'a> &'a Q: QueryDensity,
+    D: Send + Sync + 'static + Clone + AsRef<Q>,
+    G: PrimeCurve,
+    G::Scalar: PrimeFieldBits,
+{
+    if let Some(query_size) = density_map.as_ref().get_query_size() {
+        assert!(query_size == exponents.len());
+    }
+
+    let bases = bases.clone();
+    let num_threads = 1 << pool.log_num_threads();
+    let worker = pool.clone();
+
+    pool.compute(move || {
+        worker.check_cancelled()?;
+
+        let c = bases.window;
+        let num_windows = bases.num_windows;
+
+        // Pair each exponent with the index of its base.
+        let terms = exponents
+            .iter()
+            .zip(density_map.as_ref().iter())
+            .filter(|(_, density)| *density)
+            .map(|(exp, _)| exp)
+            .enumerate()
+            .collect::<Vec<_>>();
+        if terms.len() > bases.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "expected more bases from source",
+            )
+            .into());
+        }
+
+        let chunk_size = (terms.len() + num_threads - 1) / num_threads;
+        let parts = (0..num_threads as u32)
+            .into_par_iter()
+            .map(|part| {
+                let mut acc = G::identity();
+                if worker.is_cancelled() {
+                    return acc;
+                }
+
+                let start = (part as usize * chunk_size).min(terms.len());
+                let end = (start + chunk_size).min(terms.len());
+
+                let mut buckets = vec![G::identity(); Digits::Signed.num_buckets(c)];
+                for &(i, exp) in &terms[start..end] {
+                    let multiples = &bases.table[i * num_windows..(i + 1) * num_windows];
+                    match exp.chunks(c as usize, Digits::Signed) {
+                        ChunkedExponent::Zero => (),
+                        ChunkedExponent::One => acc += &multiples[0],
+                        ChunkedExponent::Chunks(digits) => {
+                            for (digit, multiple) in digits.into_iter().zip(multiples) {
+                                if digit > 0 {
+                                    buckets[(digit - 1) as usize] += multiple;
+                                } else if digit < 0 {
+                                    buckets[(-digit - 1) as usize] -= multiple;
+                                }
+                            }
+                        }
+                    }
+                }
+
+                // Summation by parts
+                let mut running_sum = G::identity();
+                for bucket in buckets.into_iter().rev() {
+                    running_sum.add_assign(&bucket);
+                    acc.add_assign(&running_sum);
+                }
+
+                acc
+            })
+            .collect::<Vec<_>>();
+        worker.check_cancelled()?;
+
+        Ok(parts.into_iter().sum())
+    })
+}
+
+#[cfg(all(test, feature = "pairing"))]
+fn naive_multiexp<G: PrimeCurve>(
+    bases: Arc<Vec<<G as PrimeCurve>::Affine>>,
+    exponents: Arc<Vec<G::Scalar>>,
+) -> G {
+    assert_eq!(bases.len(), exponents.len());
+
+    let mut acc = G::identity();
+
+    for (base, exp) in bases.iter().zip(exponents.iter()) {
+        AddAssign::<&G>::add_assign(&mut acc, &(*base * *exp));
     }
 
+    acc
+}
+
+#[cfg(feature = "pairing")]
+#[test]
+fn test_with_bls12() {
     use bls12_381::{Bls12, Scalar};
     use ff::Field;
     use group::{Curve, Group};
@@ -377,71 +1009,181 @@ fn test_with_bls12() {
     assert_eq!(naive, fast);
 }
 
-// #[cfg(feature = "pairing")]
-// #[test]
-// fn test_pedersen_comitment_with_bls12_381() {
-//     fn naive_multiexp<G: PrimeCurve>(
-//         bases: Arc<Vec<<G as PrimeCurve>::Affine>>,
-//         exponents: Arc<Vec<G::Scalar>>,
-//     ) -> G {
-//         assert_eq!(bases.len(), exponents.len());
-
-//         let mut acc = G::identity();
-
-//         for (base, exp) in bases.iter().zip(exponents.iter()) {
-//             AddAssign::<&G>::add_assign(&mut acc, &(*base * *exp));
-//         }
-
-//         acc
-//     }
-
-//     use bls12_381::{Bls12, G1Affine, Scalar};
-//     use ff::Field;
-//     use group::{Curve, Group};
-//     use pairing::Engine;
-
-//     const SAMPLES: usize = 1 << 14;
-
-//     let mut rng = rand::thread_rng();
-//     let v = Arc::new(
-//         (0..SAMPLES)
-//             .map(|_| Scalar::random(&mut rng))
-//             .collect::<Vec<_>>(),
-//     );
-//     let msg = Arc::new(
-//         (0..SAMPLES)
-//             .map(|_| Scalar::random(&mut rng))
-//             .collect::<Vec<_>>(),
-//     );
-//     let v_bits = Arc::new(v.iter().map(|e| e.into()).collect::<Vec<_>>());
-//     let msg_bits: Arc<Vec<Exponent<Scalar>>> = Arc::new(msg.iter().map(|e| e.into()).collect::<Vec<_>>());
-    
-//     let g = Arc::new(
-//         (0..SAMPLES)
-//             .map(|_| <Bls12 as Engine>::G1::random(&mut rng).to_affine())
-//             .collect::<Vec<_>>(),
-//     );
-
-//     let G: <Bls12 as Engine>::G1 = naive_multiexp(g.clone(), v);
-
-    
-//     let h = Arc::new(
-//         (0..SAMPLES)
-//             .map(|_| <Bls12 as Engine>::G1::random(&mut rng).to_affine())
-//             .collect::<Vec<_>>(),
-//     );
-
-//     let H: <Bls12 as Engine>::G1 = naive_multiexp(h.clone(), msg);
-
-//     let cm: <Bls12 as Engine>::G1 = G + H;
-
-//     let pool = Worker::new();
-//     let circuit_exp1 = multiexp(&pool, (g, 0), FullDensity, v_bits).wait().unwrap();
-//     let circuit_exp2 = multiexp(&pool, (h, 0), FullDensity, msg_bits).wait().unwrap();
-
-
-//     // AddAssign::<&Bls12>::add_assign(&mut circuit_exp1, &mut circuit_exp2);
-//     assert_eq!(G, circuit_exp1);
-//     assert_eq!(H, circuit_exp2);
-
-// }
+#[test]
+fn test_signed_digits() {
+    use bls12_381::Scalar;
+    use ff::Field;
+
+    let mut rng = rand::thread_rng();
+    for c in 2..10 {
+        for exp in [
+            -Scalar::one(),
+            Scalar::from(u64::MAX),
+            Scalar::random(&mut rng),
+        ] {
+            let digits = match Exponent::from(exp).chunks(c, Digits::Signed) {
+                ChunkedExponent::Chunks(digits) => digits,
+                _ => unreachable!(),
+            };
+            assert_eq!(
+                digits.len() as u32,
+                Digits::Signed.num_windows(Scalar::NUM_BITS, c as u32)
+            );
+
+            let half = 1 << (c - 1);
+            let radix = Scalar::from(1 << c);
+            let value = digits.iter().rev().fold(Scalar::zero(), |acc, &digit| {
+                assert!(-half < digit && digit <= half);
+                let magnitude = Scalar::from(digit.unsigned_abs());
+                acc * radix + if digit < 0 { -magnitude } else { magnitude }
+            });
+            assert_eq!(value, exp);
+        }
+    }
+}
+
+#[test]
+fn test_window_size() {
+    // More threads favour smaller windows, so that every thread has a window
+    // to work on.
+    let single = window_size(Digits::Signed, 255, 1 << 16, 1);
+    let many = window_size(Digits::Signed, 255, 1 << 16, 32);
+    assert!(many < single);
+
+    // Larger multiexps favour larger windows.
+    assert!(window_size(Digits::Signed, 255, 1 << 10, 1) < single);
+    assert!(window_size(Digits::Unsigned, 255, 1 << 20, 1) <= MAX_WINDOW_SIZE);
+}
+
+#[cfg(feature = "pairing")]
+#[test]
+fn test_configs_with_bls12() {
+    use bls12_381::{Bls12, Scalar};
+    use ff::Field;
+    use group::{Curve, Group};
+    use pairing::Engine;
+
+    const SAMPLES: usize = 1 << 10;
+
+    let mut rng = rand::thread_rng();
+    let mut v = (0..SAMPLES)
+        .map(|_| Scalar::random(&mut rng))
+        .collect::<Vec<_>>();
+    // Exercise the trivial exponents and the largest digits.
+    v[0] = Scalar::zero();
+    v[1] = Scalar::one();
+    v[2] = -Scalar::one();
+    let v = Arc::new(v);
+    let v_bits = Arc::new(v.iter().map(|e| e.into()).collect::<Vec<_>>());
+    let g = Arc::new(
+        (0..SAMPLES)
+            .map(|_| <Bls12 as Engine>::G1::random(&mut rng).to_affine())
+            .collect::<Vec<_>>(),
+    );
+
+    let naive: <Bls12 as Engine>::G1 = naive_multiexp(g.clone(), v.clone());
+
+    let pool = Worker::new();
+    for digits in [Digits::Unsigned, Digits::Signed] {
+        for window in [Some(2), Some(5), Some(13), None] {
+            let config = MultiexpConfig { digits, window };
+            let fast =
+                multiexp_with_config(&pool, (g.clone(), 0), FullDensity, v_bits.clone(), config)
+                    .wait()
+                    .unwrap();
+            assert_eq!(naive, fast, "{:?}", config);
+        }
+    }
+
+    for window in [2, 7, 16] {
+        let table = PrecomputedBases::<<Bls12 as Engine>::G1>::new(&pool, &g, window).unwrap();
+        assert_eq!(table.len(), SAMPLES);
+        let fast = multiexp_precomputed(&pool, &table, FullDensity, v_bits.clone())
+            .wait()
+            .unwrap();
+        assert_eq!(naive, fast);
+    }
+
+    // Only the dense exponents have bases.
+    let mut density = DensityTracker::new();
+    for i in 0..SAMPLES {
+        density.add_element();
+        if i % 3 == 0 {
+            density.inc(i);
+        }
+    }
+    let density = Arc::new(density);
+    let sparse_g = Arc::new(g.iter().step_by(3).cloned().collect::<Vec<_>>());
+    let sparse_v = Arc::new(v.iter().step_by(3).cloned().collect::<Vec<_>>());
+    let naive: <Bls12 as Engine>::G1 = naive_multiexp(sparse_g.clone(), sparse_v);
+
+    let fast = multiexp_with_config(
+        &pool,
+        (sparse_g.clone(), 0),
+        density.clone(),
+        v_bits.clone(),
+        MultiexpConfig::default(),
+    )
+    .wait()
+    .unwrap();
+    assert_eq!(naive, fast);
+
+    let table = PrecomputedBases::new(&pool, &sparse_g, 8).unwrap();
+    let fast = multiexp_precomputed(&pool, &table, density, v_bits)
+        .wait()
+        .unwrap();
+    assert_eq!(naive, fast);
+}
+
+#[test]
+fn test_batch_affine_with_bls12() {
+    fn check<G>(rng: &mut impl rand_core::RngCore)
+    where
+        G: PrimeCurve,
+        G::Affine: WeierstrassAffine,
+        G::Scalar: PrimeFieldBits,
+    {
+        const SAMPLES: usize = 600;
+
+        let p = G::random(&mut *rng).to_affine();
+        // Repeated and negated bases exercise doubling and cancellation within
+        // a bucket, and small exponents put many bases in the same bucket.
+        let g = Arc::new(
+            (0..SAMPLES)
+                .map(|i| match i % 4 {
+                    0 => p,
+                    1 => -p,
+                    _ => G::random(&mut *rng).to_affine(),
+                })
+                .collect::<Vec<_>>(),
+        );
+        let v = Arc::new(
+            (0..SAMPLES)
+                .map(|i| match i % 3 {
+                    0 => G::Scalar::from((i % 5) as u64),
+                    _ => G::Scalar::random(&mut *rng),
+                })
+                .map(|v| Exponent::from(&v))
+                .collect::<Vec<_>>(),
+        );
+
+        let pool = Worker::new();
+        let expected: G = multiexp(&pool, (g.clone(), 0), FullDensity, v.clone())
+            .wait()
+            .unwrap();
+        for digits in [Digits::Unsigned, Digits::Signed] {
+            for window in [Some(4), None] {
+                let config = MultiexpConfig { digits, window };
+                let fast: G =
+                    multiexp_batch_affine(&pool, (g.clone(), 0), FullDensity, v.clone(), config)
+                        .wait()
+                        .unwrap();
+                assert!(expected == fast, "{:?}", config);
+            }
+        }
+    }
+
+    let mut rng = rand::thread_rng();
+    check::<bls12_381::G1Projective>(&mut rng);
+    check::<bls12_381::G2Projective>(&mut rng);
+}
@@ -0,0 +1,371 @@
+//! Coordinate access for the BLS12-381 affine groups, which lets their
+//! multiexps accumulate buckets in affine coordinates.
+//!
+//! `bls12_381` does not expose its base field, so this module carries the
+//! little `Fp` and `Fp2` arithmetic that affine addition needs, and converts
+//! coordinates through the uncompressed encodings of the points.
+
+use std::ops::{Add, Mul, Neg, Sub};
+
+use bls12_381::{G1Affine, G2Affine};
+
+use super::{BaseField, WeierstrassAffine};
+
+/// The base field modulus, as little-endian words.
+const MODULUS: [u64; 6] = [
+    0xb9feffffffffaaab,
+    0x1eabfffeb153ffff,
+    0x6730d2a0f6b0f624,
+    0x64774b84f38512bf,
+    0x4b1ba7b6434bacd7,
+    0x1a0111ea397fe69a,
+];
+
+/// `-MODULUS^-1 mod 2^64`.
+const INV: u64 = 0x89f3fffcfffcfffd;
+
+/// `2^384 mod MODULUS`, which is one in Montgomery form.
+const R: Fp = Fp([
+    0x760900000002fffd,
+    0xebf4000bc40c0002,
+    0x5f48985753c758ba,
+    0x77ce585370525745,
+    0x5c071a97a256ec6d,
+    0x15f65ec3fa80e493,
+]);
+
+/// `2^768 mod MODULUS`, which converts into Montgomery form.
+const R2: Fp = Fp([
+    0xf4df1f341c341746,
+    0x0a76e6a609d104f1,
+    0x8de5476c4c95b6d5,
+    0x67eb88a9939d83c0,
+    0x9a793e85b519952d,
+    0x11988fe592cae3aa,
+]);
+
+/// An element of the base field of BLS12-381, in Montgomery form.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fp([u64; 6]);
+
+impl Fp {
+    const ZERO: Fp = Fp([0; 6]);
+
+    /// Reads a big-endian encoding, which must be less than the modulus.
+    fn from_bytes(bytes: &[u8]) -> Fp {
+        let mut limbs = [0; 6];
+        for (limb, chunk) in limbs.iter_mut().zip(bytes.rchunks(8)) {
+            *limb = u64::from_be_bytes(chunk.try_into().unwrap());
+        }
+        Fp(limbs) * R2
+    }
+
+    /// Returns the big-endian encoding of this element.
+    fn to_bytes(self) -> [u8; 48] {
+        // Multiplying by 1 leaves Montgomery form.
+        let mut one = [0; 6];
+        one[0] = 1;
+        let limbs = (self * Fp(one)).0;
+
+        let mut bytes = [0; 48];
+        for (chunk, limb) in bytes.rchunks_mut(8).zip(limbs.iter()) {
+            chunk.copy_from_slice(&limb.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// Subtracts the modulus if `limbs` is at least the modulus.
+    fn reduce(limbs: [u64; 6]) -> Fp {
+        let (difference, borrow) = sub_words(&limbs, &MODULUS);
+        Fp(if borrow { limbs } else { difference })
+    }
+}
+
+/// Returns `a - b` and whether it borrowed.
+fn sub_words(a: &[u64; 6], b: &[u64; 6]) -> ([u64; 6], bool) {
+    let mut result = [0; 6];
+    let mut borrow = false;
+    for i in 0..6 {
+        let (d, b1) = a[i].overflowing_sub(b[i]);
+        let (d, b2) = d.overflowing_sub(borrow as u64);
+        result[i] = d;
+        borrow = b1 || b2;
+    }
+    (result, borrow)
+}
+
+/// Returns `a + b`, which must not overflow.
+fn add_words(a: &[u64; 6], b: &[u64; 6]) -> [u64; 6] {
+    let mut result = [0; 6];
+    let mut carry = false;
+    for i in 0..6 {
+        let (s, c1) = a[i].overflowing_add(b[i]);
+        let (s, c2) = s.overflowing_add(carry as u64);
+        result[i] = s;
+        carry = c1 || c2;
+    }
+    result
+}
+
+impl Add for Fp {
+    type Output = Fp;
+
+    fn add(self, rhs: Fp) -> Fp {
+        // Both are less than the modulus, which is less than 2^383.
+        Fp::reduce(add_words(&self.0, &rhs.0))
+    }
+}
+
+impl Sub for Fp {
+    type Output = Fp;
+
+    fn sub(self, rhs: Fp) -> Fp {
+        let (difference, borrow) = sub_words(&self.0, &rhs.0);
+        Fp(if borrow {
+            add_words(&difference, &MODULUS)
+        } else {
+            difference
+        })
+    }
+}
+
+impl Neg for Fp {
+    type Output = Fp;
+
+    fn neg(self) -> Fp {
+        Fp::ZERO - self
+    }
+}
+
+impl Mul for Fp {
+    type Output = Fp;
+
+    /// Montgomery multiplication, interleaving the product with its reduction.
+    fn mul(self, rhs: Fp) -> Fp {
+        let mut t = [0u64; 8];
+        for i in 0..6 {
+            let mut carry = 0;
+            for (t, a) in t.iter_mut().zip(self.0.iter()) {
+                let v = *t as u128 + *a as u128 * rhs.0[i] as u128 + carry;
+                *t = v as u64;
+                carry = v >> 64;
+            }
+            let v = t[6] as u128 + carry;
+            t[6] = v as u64;
+            t[7] = (v >> 64) as u64;
+
+            let m = t[0].wrapping_mul(INV);
+            let mut carry = (t[0] as u128 + m as u128 * MODULUS[0] as u128) >> 64;
+            for j in 1..6 {
+                let v = t[j] as u128 + m as u128 * MODULUS[j] as u128 + carry;
+                t[j - 1] = v as u64;
+                carry = v >> 64;
+            }
+            let v = t[6] as u128 + carry;
+            t[5] = v as u64;
+            t[6] = t[7] + (v >> 64) as u64;
+        }
+
+        Fp::reduce([t[0], t[1], t[2], t[3], t[4], t[5]])
+    }
+}
+
+impl BaseField for Fp {
+    fn one() -> Fp {
+        R
+    }
+
+    fn invert(&self) -> Option<Fp> {
+        if *self == Fp::ZERO {
+            return None;
+        }
+
+        // self^(p - 2), by square-and-multiply from the top bit.
+        let mut exponent = MODULUS;
+        exponent[0] -= 2;
+        let mut acc = R;
+        for word in exponent.iter().rev() {
+            for i in (0..64).rev() {
+                acc = acc * acc;
+                if (word >> i) & 1 == 1 {
+                    acc = acc * *self;
+                }
+            }
+        }
+        Some(acc)
+    }
+}
+
+/// An element `c0 + c1·u` of the quadratic extension `Fp[u] / (u^2 + 1)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fp2 {
+    c0: Fp,
+    c1: Fp,
+}
+
+impl Add for Fp2 {
+    type Output = Fp2;
+
+    fn add(self, rhs: Fp2) -> Fp2 {
+        Fp2 {
+            c0: self.c0 + rhs.c0,
+            c1: self.c1 + rhs.c1,
+        }
+    }
+}
+
+impl Sub for Fp2 {
+    type Output = Fp2;
+
+    fn sub(self, rhs: Fp2) -> Fp2 {
+        Fp2 {
+            c0: self.c0 - rhs.c0,
+            c1: self.c1 - rhs.c1,
+        }
+    }
+}
+
+impl Neg for Fp2 {
+    type Output = Fp2;
+
+    fn neg(self) -> Fp2 {
+        Fp2 {
+            c0: -self.c0,
+            c1: -self.c1,
+        }
+    }
+}
+
+impl Mul for Fp2 {
+    type Output = Fp2;
+
+    fn mul(self, rhs: Fp2) -> Fp2 {
+        Fp2 {
+            c0: self.c0 * rhs.c0 - self.c1 * rhs.c1,
+            c1: self.c0 * rhs.c1 + self.c1 * rhs.c0,
+        }
+    }
+}
+
+impl BaseField for Fp2 {
+    fn one() -> Fp2 {
+        Fp2 {
+            c0: R,
+            c1: Fp::ZERO,
+        }
+    }
+
+    fn invert(&self) -> Option<Fp2> {
+        // 1 / (c0 + c1·u) = (c0 - c1·u) / (c0^2 + c1^2)
+        let norm = (self.c0 * self.c0 + self.c1 * self.c1).invert()?;
+        Some(Fp2 {
+            c0: self.c0 * norm,
+            c1: -(self.c1 * norm),
+        })
+    }
+}
+
+// The flag bits at the top of an uncompressed encoding are all clear for
+// points other than the identity.
+
+impl WeierstrassAffine for G1Affine {
+    type Base = Fp;
+
+    fn coeff_a() -> Fp {
+        Fp::ZERO
+    }
+
+    fn to_xy(&self) -> Option<(Fp, Fp)> {
+        if bool::from(self.is_identity()) {
+            return None;
+        }
+
+        let bytes = self.to_uncompressed();
+        Some((Fp::from_bytes(&bytes[..48]), Fp::from_bytes(&bytes[48..])))
+    }
+
+    fn from_xy_unchecked(x: Fp, y: Fp) -> Self {
+        let mut bytes = [0; 96];
+        bytes[..48].copy_from_slice(&x.to_bytes());
+        bytes[48..].copy_from_slice(&y.to_bytes());
+        G1Affine::from_uncompressed_unchecked(&bytes).unwrap()
+    }
+}
+
+impl WeierstrassAffine for G2Affine {
+    type Base = Fp2;
+
+    fn coeff_a() -> Fp2 {
+        Fp2 {
+            c0: Fp::ZERO,
+            c1: Fp::ZERO,
+        }
+    }
+
+    fn to_xy(&self) -> Option<(Fp2, Fp2)> {
+        if bool::from(self.is_identity()) {
+            return None;
+        }
+
+        // Each coordinate is encoded as c1 followed by c0.
+        let bytes = self.to_uncompressed();
+        let read = |offset: usize| Fp2 {
+            c0: Fp::from_bytes(&bytes[offset + 48..offset + 96]),
+            c1: Fp::from_bytes(&bytes[offset..offset + 48]),
+        };
+        Some((read(0), read(96)))
+    }
+
+    fn from_xy_unchecked(x: Fp2, y: Fp2) -> Self {
+        let mut bytes = [0; 192];
+        for (chunk, coordinate) in bytes.chunks_mut(96).zip([x, y].iter()) {
+            chunk[..48].copy_from_slice(&coordinate.c1.to_bytes());
+            chunk[48..].copy_from_slice(&coordinate.c0.to_bytes());
+        }
+        G2Affine::from_uncompressed_unchecked(&bytes).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bls12_381::{G1Affine, G1Projective, G2Affine, G2Projective};
+    use group::{Curve, Group};
+    use rand::thread_rng;
+
+    use super::{BaseField, Fp, WeierstrassAffine};
+
+    #[test]
+    fn field_arithmetic() {
+        let mut rng = thread_rng();
+        let (x, y) = G1Projective::random(&mut rng).to_affine().to_xy().unwrap();
+
+        assert_eq!(Fp::from_bytes(&x.to_bytes()), x);
+        assert_eq!(x * x.invert().unwrap(), Fp::one());
+        assert_eq!((x + y) - y, x);
+        assert_eq!(x + -x, Fp::ZERO);
+        assert_eq!((x + y) * (x - y), x * x - y * y);
+        assert_eq!(Fp::ZERO.invert(), None);
+
+        // y^2 = x^3 + 4
+        let four = Fp::one() + Fp::one() + Fp::one() + Fp::one();
+        assert_eq!(y * y, x * x * x + four);
+    }
+
+    #[test]
+    fn coordinates() {
+        let mut rng = thread_rng();
+        assert_eq!(G1Affine::identity().to_xy(), None);
+        assert_eq!(G2Affine::identity().to_xy(), None);
+
+        let p = G1Projective::random(&mut rng).to_affine();
+        let (x, y) = p.to_xy().unwrap();
+        assert_eq!(G1Affine::from_xy_unchecked(x, y), p);
+        assert_eq!(G1Affine::from_xy_unchecked(x, -y), -p);
+
+        let q = G2Projective::random(&mut rng).to_affine();
+        let (x, y) = q.to_xy().unwrap();
+        assert_eq!(G2Affine::from_xy_unchecked(x, y), q);
+        assert_eq!(G2Affine::from_xy_unchecked(x, -y), -q);
+        assert_eq!(x * x.invert().unwrap(), BaseField::one());
+    }
+}
@@ -0,0 +1,587 @@
+//! An R1CS optimization pass that eliminates linear constraints.
+//!
+//! Every call to [`ConstraintSystem::enforce`] becomes a row of the QAP, even
+//! when the constraint is linear, such as `x * 1 = y`. [`optimize`] synthesizes
+//! a circuit, then uses each linear constraint to substitute away one of its
+//! auxiliary variables, and drops the constraint. The result is an
+//! [`OptimizedCircuit`] with fewer constraints and variables.
+//!
+//! The variables that are eliminated depend only on the structure of the
+//! circuit, not on its witness, so a circuit must be optimized in the same way
+//! for parameter generation and for proving:
+//!
+//! ```
+//! use bellman::{optimizer::optimize, Circuit, ConstraintSystem, SynthesisError};
+//! # use bellman::gadgets::test::TestConstraintSystem;
+//! # use bls12_381::Scalar;
+//! # use ff::Field;
+//!
+//! struct Alias(Option<Scalar>);
+//!
+//! impl Circuit<Scalar> for Alias {
+//!     fn synthesize<CS: ConstraintSystem<Scalar>>(
+//!         self,
+//!         cs: &mut CS,
+//!     ) -> Result<(), SynthesisError> {
+//!         let value = || self.0.ok_or(SynthesisError::AssignmentMissing);
+//!         let x = cs.alloc(|| "x", value)?;
+//!         let y = cs.alloc(|| "y", value)?;
+//!         let out = cs.alloc_input(|| "out", || Ok(value()?.square()))?;
+//!         cs.enforce(|| "y = x", |lc| lc + x, |lc| lc + CS::one(), |lc| lc + y);
+//!         cs.enforce(|| "out = x * y", |lc| lc + x, |lc| lc + y, |lc| lc + out);
+//!         Ok(())
+//!     }
+//! }
+//!
+//! // The same optimization is applied with or without a witness.
+//! let shape = optimize(Alias(None))?;
+//! let circuit = optimize(Alias(Some(Scalar::from(3))))?;
+//! assert_eq!(shape.num_constraints(), 1);
+//! assert_eq!(circuit.num_aux(), 1);
+//!
+//! let mut cs = TestConstraintSystem::new();
+//! circuit.synthesize(&mut cs)?;
+//! assert!(cs.is_satisfied());
+//! # Ok::<_, SynthesisError>(())
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use ff::PrimeField;
+
+use crate::lc::{proc_lc, OrderedVariable};
+use crate::{Circuit, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
+
+pub(crate) type Terms<S> = BTreeMap<OrderedVariable, S>;
+
+/// Records a synthesized circuit, with values when they are available.
+pub(crate) struct Recorder<S: PrimeField> {
+    pub(crate) inputs: Vec<Option<S>>,
+    pub(crate) aux: Vec<Option<S>>,
+    pub(crate) constraints: Vec<[Terms<S>; 3]>,
+}
+
+impl<S: PrimeField> Recorder<S> {
+    /// Creates a recorder with the `ONE` input already allocated.
+    pub(crate) fn new() -> Self {
+        Recorder {
+            inputs: vec![Some(S::one())],
+            aux: vec![],
+            constraints: vec![],
+        }
+    }
+}
+
+fn value<S, F>(f: F) -> Result<Option<S>, SynthesisError>
+where
+    F: FnOnce() -> Result<S, SynthesisError>,
+{
+    match f() {
+        Ok(value) => Ok(Some(value)),
+        Err(SynthesisError::AssignmentMissing) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+impl<S: PrimeField> ConstraintSystem<S> for Recorder<S> {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(&mut self, _: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<S, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.aux.push(value(f)?);
+
+        Ok(Variable(Index::Aux(self.aux.len() - 1)))
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, _: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<S, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.inputs.push(value(f)?);
+
+        Ok(Variable(Index::Input(self.inputs.len() - 1)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, a: LA, b: LB, c: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<S>) -> LinearCombination<S>,
+        LB: FnOnce(LinearCombination<S>) -> LinearCombination<S>,
+        LC: FnOnce(LinearCombination<S>) -> LinearCombination<S>,
+    {
+        self.constraints.push([
+            proc_lc(a(LinearCombination::zero()).as_ref()),
+            proc_lc(b(LinearCombination::zero()).as_ref()),
+            proc_lc(c(LinearCombination::zero()).as_ref()),
+        ]);
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+
+    fn pop_namespace(&mut self) {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+/// Adds `coeff * terms` to `acc`, removing any terms that cancel.
+fn add_scaled<S: PrimeField>(acc: &mut Terms<S>, coeff: S, terms: &Terms<S>) {
+    for (var, c) in terms {
+        let entry = acc.entry(*var).or_insert_with(S::zero);
+        *entry += coeff * c;
+        if entry.is_zero_vartime() {
+            acc.remove(var);
+        }
+    }
+}
+
+/// If `terms` is a constant multiple of the `ONE` variable, returns the constant.
+fn constant<S: PrimeField>(terms: &Terms<S>) -> Option<S> {
+    match terms.iter().next() {
+        None => Some(S::zero()),
+        Some((var, coeff))
+            if terms.len() == 1 && *var == OrderedVariable(Variable(Index::Input(0))) =>
+        {
+            Some(*coeff)
+        }
+        _ => None,
+    }
+}
+
+/// Substitutions for eliminated auxiliary variables.
+///
+/// Every expression is kept in terms of variables that have not been
+/// eliminated, so that a single substitution pass fully resolves a linear
+/// combination.
+#[derive(Default)]
+struct Substitutions<S: PrimeField> {
+    exprs: HashMap<usize, Terms<S>>,
+    // For each auxiliary variable, the eliminated variables whose expressions
+    // may refer to it.
+    uses: HashMap<usize, Vec<usize>>,
+}
+
+impl<S: PrimeField> Substitutions<S> {
+    fn apply(&self, terms: &Terms<S>) -> Terms<S> {
+        let mut acc = Terms::new();
+        for (var, coeff) in terms {
+            match var.0 {
+                Variable(Index::Aux(i)) if self.exprs.contains_key(&i) => {
+                    add_scaled(&mut acc, *coeff, &self.exprs[&i])
+                }
+                _ => {
+                    let entry = acc.entry(*var).or_insert_with(S::zero);
+                    *entry += coeff;
+                    if entry.is_zero_vartime() {
+                        acc.remove(var);
+                    }
+                }
+            }
+        }
+
+        acc
+    }
+
+    fn record_uses(&mut self, eliminated: usize, expr: &Terms<S>) {
+        for var in expr.keys() {
+            if let Variable(Index::Aux(i)) = var.0 {
+                self.uses.entry(i).or_default().push(eliminated);
+            }
+        }
+    }
+
+    /// Eliminates auxiliary variable `i`, which is equal to `expr`.
+    fn eliminate(&mut self, i: usize, expr: Terms<S>) {
+        let var = OrderedVariable(Variable(Index::Aux(i)));
+        for j in self.uses.remove(&i).unwrap_or_default() {
+            let other = self.exprs.get_mut(&j).unwrap();
+            if let Some(coeff) = other.remove(&var) {
+                add_scaled(other, coeff, &expr);
+                for var in expr.keys() {
+                    if let Variable(Index::Aux(k)) = var.0 {
+                        self.uses.entry(k).or_default().push(j);
+                    }
+                }
+            }
+        }
+
+        self.record_uses(i, &expr);
+        self.exprs.insert(i, expr);
+    }
+}
+
+/// A circuit produced by [`optimize`].
+///
+/// Auxiliary variables that are eliminated, or that no longer appear in any
+/// constraint, are not allocated when this circuit is synthesized. Inputs are
+/// always preserved.
+pub struct OptimizedCircuit<S: PrimeField> {
+    inputs: Vec<Option<S>>,
+    // Values of the auxiliary variables that are kept.
+    aux: Vec<Option<S>>,
+    // Constraints in terms of the original inputs and the kept auxiliary
+    // variables, which are renumbered.
+    constraints: Vec<[Terms<S>; 3]>,
+    eliminated: usize,
+}
+
+impl<S: PrimeField> OptimizedCircuit<S> {
+    pub fn num_inputs(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn num_aux(&self) -> usize {
+        self.aux.len()
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        self.constraints.len()
+    }
+
+    /// Returns the number of linear constraints that were eliminated.
+    pub fn num_eliminated(&self) -> usize {
+        self.eliminated
+    }
+}
+
+/// Synthesizes `circuit` and eliminates its linear constraints.
+///
+/// A constraint `A * B = C` in which `A` or `B` is a constant is a linear
+/// equation. If it involves an auxiliary variable, the most recently allocated
+/// such variable is expressed in terms of the others and substituted into
+/// every other constraint. Auxiliary variables that are left unconstrained are
+/// then removed.
+///
+/// The circuit is synthesized once, and may be given with or without a
+/// witness.
+pub fn optimize<S, C>(circuit: C) -> Result<OptimizedCircuit<S>, SynthesisError>
+where
+    S: PrimeField,
+    C: Circuit<S>,
+{
+    let mut recorder = Recorder::new();
+    circuit.synthesize(&mut recorder)?;
+
+    let mut subst = Substitutions::default();
+    let mut kept = vec![];
+    let mut eliminated = 0;
+    for [a, b, c] in recorder.constraints {
+        let [a, b, c] = [&a, &b, &c].map(|lc| subst.apply(lc));
+
+        let mut linear = match (constant(&a), constant(&b)) {
+            (Some(k), _) => b.iter().map(|(var, coeff)| (*var, k * coeff)).collect(),
+            (None, Some(k)) => a.iter().map(|(var, coeff)| (*var, k * coeff)).collect(),
+            (None, None) => {
+                kept.push([a, b, c]);
+                continue;
+            }
+        };
+        add_scaled(&mut linear, -S::one(), &c);
+
+        // Inputs sort before auxiliary variables, so the last term is the most
+        // recently allocated auxiliary variable, if there is one.
+        match linear
+            .iter()
+            .next_back()
+            .map(|(var, coeff)| (var.0, *coeff))
+        {
+            Some((Variable(Index::Aux(i)), coeff)) => {
+                // coeff * x_i + rest = 0, so x_i = -rest / coeff
+                let scale = -coeff.invert().unwrap();
+                linear.remove(&OrderedVariable(Variable(Index::Aux(i))));
+                let expr = linear.iter().map(|(var, c)| (*var, scale * c)).collect();
+                subst.eliminate(i, expr);
+                eliminated += 1;
+            }
+            // 0 = 0
+            None => eliminated += 1,
+            // The constraint only involves inputs.
+            Some(_) => kept.push([a, b, c]),
+        }
+    }
+
+    // Earlier constraints may refer to variables that were eliminated later.
+    let kept = kept
+        .iter()
+        .map(|[a, b, c]| [a, b, c].map(|lc| subst.apply(lc)))
+        .collect::<Vec<_>>();
+
+    // Renumber the auxiliary variables that are still constrained.
+    let mut used = vec![false; recorder.aux.len()];
+    for lc in kept.iter().flatten() {
+        for var in lc.keys() {
+            if let Variable(Index::Aux(i)) = var.0 {
+                used[i] = true;
+            }
+        }
+    }
+    let mut aux = vec![];
+    let renumbered = used
+        .into_iter()
+        .zip(recorder.aux)
+        .map(|(used, value)| {
+            used.then(|| {
+                aux.push(value);
+                aux.len() - 1
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let constraints = kept
+        .into_iter()
+        .map(|lcs| {
+            lcs.map(|lc| {
+                lc.into_iter()
+                    .map(|(var, coeff)| match var.0 {
+                        Variable(Index::Aux(i)) => (
+                            OrderedVariable(Variable(Index::Aux(renumbered[i].unwrap()))),
+                            coeff,
+                        ),
+                        _ => (var, coeff),
+                    })
+                    .collect()
+            })
+        })
+        .collect();
+
+    Ok(OptimizedCircuit {
+        inputs: recorder.inputs,
+        aux,
+        constraints,
+        eliminated,
+    })
+}
+
+impl<S: PrimeField> Circuit<S> for OptimizedCircuit<S> {
+    fn synthesize<CS: ConstraintSystem<S>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let mut inputs = vec![CS::one()];
+        for (i, value) in self.inputs.into_iter().enumerate().skip(1) {
+            inputs.push(cs.alloc_input(
+                || format!("input {}", i),
+                || value.ok_or(SynthesisError::AssignmentMissing),
+            )?);
+        }
+
+        let aux = self
+            .aux
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| {
+                cs.alloc(
+                    || format!("aux {}", i),
+                    || value.ok_or(SynthesisError::AssignmentMissing),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let lc = |terms: &Terms<S>| {
+            terms
+                .iter()
+                .fold(LinearCombination::zero(), |lc, (var, coeff)| {
+                    let var = match var.0 {
+                        Variable(Index::Input(i)) => inputs[i],
+                        Variable(Index::Aux(i)) => aux[i],
+                    };
+                    lc + (*coeff, var)
+                })
+        };
+
+        for (i, [a, b, c]) in self.constraints.iter().enumerate() {
+            cs.enforce(
+                || format!("constraint {}", i),
+                |_| lc(a),
+                |_| lc(b),
+                |_| lc(c),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::metrics::MetricConstraintSystem;
+    use crate::gadgets::mimc7::{get_mimc_constants, mimc7, mimc7_cs};
+    use crate::gadgets::test::TestConstraintSystem;
+
+    use bls12_381::Scalar;
+    use ff::Field;
+
+    struct MiMC7 {
+        xl: Option<Scalar>,
+        xr: Option<Scalar>,
+    }
+
+    impl Circuit<Scalar> for MiMC7 {
+        fn synthesize<CS: ConstraintSystem<Scalar>>(
+            self,
+            cs: &mut CS,
+        ) -> Result<(), SynthesisError> {
+            let (out, image) = mimc7_cs(cs, self.xl, self.xr, &get_mimc_constants());
+            let image = cs.alloc_input(
+                || "image",
+                || image.ok_or(SynthesisError::AssignmentMissing),
+            )?;
+            cs.enforce(
+                || "expose image",
+                |lc| lc + out,
+                |lc| lc + CS::one(),
+                |lc| lc + image,
+            );
+
+            Ok(())
+        }
+    }
+
+    fn expected(xl: Scalar, xr: Scalar) -> Scalar {
+        mimc7(xl, xr, &get_mimc_constants())
+    }
+
+    #[test]
+    fn eliminates_linear_constraints() {
+        let (xl, xr) = (Scalar::from(7), Scalar::from(11));
+
+        // The gadget reuses names, so count it without TestConstraintSystem.
+        let mut metrics = MetricConstraintSystem::new();
+        MiMC7 { xl: None, xr: None }
+            .synthesize(&mut metrics)
+            .unwrap();
+        assert_eq!(metrics.num_constraints(), 2 + 5 * 91 + 1);
+
+        let circuit = optimize(MiMC7 {
+            xl: Some(xl),
+            xr: Some(xr),
+        })
+        .unwrap();
+        // "copy xl", every "x1 = (xl + xr) * 1", the output and the exposure
+        // of the image are all linear.
+        assert_eq!(circuit.num_eliminated(), 1 + 91 + 1 + 1);
+        assert_eq!(circuit.num_constraints(), 4 * 91);
+        // Each x1 is eliminated, as are "copy xl", the output, and the final
+        // x7 which is now expressed in terms of the image.
+        assert_eq!(circuit.num_aux(), 2 + 4 * 91 - 1);
+        assert_eq!(circuit.num_inputs(), 2);
+
+        // The same structure is produced without a witness.
+        let shape = optimize(MiMC7 { xl: None, xr: None }).unwrap();
+        assert_eq!(shape.num_aux(), circuit.num_aux());
+        assert!(shape.constraints == circuit.constraints);
+
+        let mut cs = TestConstraintSystem::new();
+        circuit.synthesize(&mut cs).unwrap();
+        assert!(cs.is_satisfied());
+        assert!(cs.verify(&[expected(xl, xr)]));
+    }
+
+    #[test]
+    fn substitutes_into_earlier_constraints() {
+        struct Chain;
+
+        impl Circuit<Scalar> for Chain {
+            fn synthesize<CS: ConstraintSystem<Scalar>>(
+                self,
+                cs: &mut CS,
+            ) -> Result<(), SynthesisError> {
+                let x = cs.alloc(|| "x", || Ok(Scalar::from(3)))?;
+                let y = cs.alloc(|| "y", || Ok(Scalar::from(4)))?;
+                let z = cs.alloc(|| "z", || Ok(Scalar::from(5)))?;
+                let out = cs.alloc_input(|| "out", || Ok(Scalar::from(60)))?;
+                // A nonlinear constraint that uses y and z before they are
+                // defined linearly.
+                cs.enforce(
+                    || "x * (y * z)",
+                    |lc| lc + x,
+                    |lc| lc + z,
+                    |lc| lc + out - y,
+                );
+                // 2z = 2x + 2y - 4
+                cs.enforce(
+                    || "z",
+                    |lc| lc + (Scalar::from(2), CS::one()),
+                    |lc| lc + z,
+                    |lc| {
+                        lc + (Scalar::from(2), x) + (Scalar::from(2), y)
+                            - (Scalar::from(4), CS::one())
+                    },
+                );
+                // y = x + 1
+                cs.enforce(
+                    || "y",
+                    |lc| lc + x + CS::one(),
+                    |lc| lc + CS::one(),
+                    |lc| lc + y,
+                );
+                // A tautology once y is substituted.
+                cs.enforce(
+                    || "y = x + 1",
+                    |lc| lc + CS::one(),
+                    |lc| lc + y,
+                    |lc| lc + x + CS::one(),
+                );
+                Ok(())
+            }
+        }
+
+        // With z = x + y - 2 = 2x - 1 and y = x + 1, only x * (2x - 1) =
+        // out - (x + 1) is left. For x = 3, out must be 19.
+        let circuit = optimize(Chain).unwrap();
+        assert_eq!(circuit.num_eliminated(), 3);
+        assert_eq!(circuit.num_constraints(), 1);
+        assert_eq!(circuit.num_aux(), 1);
+
+        let mut cs = TestConstraintSystem::new();
+        circuit.synthesize(&mut cs).unwrap();
+        assert!(!cs.is_satisfied());
+        cs.set("input 1", Scalar::from(19));
+        assert!(cs.is_satisfied());
+        cs.set("aux 0", Scalar::one().double());
+        assert!(!cs.is_satisfied());
+    }
+
+    #[cfg(feature = "groth16")]
+    #[test]
+    fn groth16_proof() {
+        use crate::groth16::{
+            create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
+        };
+        use bls12_381::Bls12;
+        use rand::thread_rng;
+
+        let mut rng = thread_rng();
+        let params = generate_random_parameters::<Bls12, _, _>(
+            optimize(MiMC7 { xl: None, xr: None }).unwrap(),
+            &mut rng,
+        )
+        .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let (xl, xr) = (Scalar::random(&mut rng), Scalar::random(&mut rng));
+        let circuit = optimize(MiMC7 {
+            xl: Some(xl),
+            xr: Some(xr),
+        })
+        .unwrap();
+        let proof = create_random_proof(circuit, &params, &mut rng).unwrap();
+
+        assert!(verify_proof(&pvk, &proof, &[expected(xl, xr)]).is_ok());
+        assert!(verify_proof(&pvk, &proof, &[expected(xr, xl)]).is_err());
+    }
+}
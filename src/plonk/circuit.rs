@@ -0,0 +1,232 @@
+//! Translation of R1CS circuits into PLONK gates.
+//!
+//! Every gate enforces
+//!
+//! ```text
+//! q_M * a * b + q_L * a + q_R * b + q_O * c + q_C = 0
+//! ```
+//!
+//! on its three wires `a`, `b` and `c`. A public input `x_i` gets a gate
+//! `a - x_i = 0` in row `i`, where `-x_i` is supplied by the public input
+//! polynomial. Each R1CS constraint `A * B = C` becomes a single gate once its
+//! linear combinations have been reduced to one scaled wire plus a constant,
+//! which takes an addition gate for each term past the first.
+
+use ff::PrimeField;
+
+use crate::optimizer::{Recorder, Terms};
+use crate::{Circuit, Index, SynthesisError};
+
+/// The variable that fills unused wires. Its value is zero.
+pub(crate) const ZERO: usize = 0;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Gate<S> {
+    /// `q_M`, `q_L`, `q_R`, `q_O` and `q_C`.
+    pub(crate) selectors: [S; 5],
+    /// The variables on the `a`, `b` and `c` wires.
+    pub(crate) wires: [usize; 3],
+}
+
+impl<S: PrimeField> Gate<S> {
+    fn new(q_m: S, q_l: S, q_r: S, q_o: S, q_c: S, wires: [usize; 3]) -> Self {
+        Gate {
+            selectors: [q_m, q_l, q_r, q_o, q_c],
+            wires,
+        }
+    }
+}
+
+/// A circuit translated into gates.
+pub(crate) struct Assembly<S: PrimeField> {
+    pub(crate) num_public: usize,
+    pub(crate) gates: Vec<Gate<S>>,
+    /// The number of variables, including [`ZERO`].
+    pub(crate) num_variables: usize,
+    /// The value of each variable, if the circuit was synthesized with a
+    /// witness.
+    pub(crate) values: Option<Vec<S>>,
+}
+
+struct Builder<S: PrimeField> {
+    num_inputs: usize,
+    gates: Vec<Gate<S>>,
+    values: Vec<Option<S>>,
+}
+
+impl<S: PrimeField> Builder<S> {
+    fn variable(&self, index: Index) -> usize {
+        match index {
+            Index::Input(i) => i,
+            Index::Aux(i) => self.num_inputs + i,
+        }
+    }
+
+    /// Reduces `terms` to a wire, its coefficient and a constant.
+    fn linearize(&mut self, terms: &Terms<S>) -> (usize, S, S) {
+        let mut constant = S::zero();
+        let mut scaled = vec![];
+        for (var, coeff) in terms {
+            match var.0.get_unchecked() {
+                Index::Input(0) => constant = *coeff,
+                index => scaled.push((self.variable(index), *coeff)),
+            }
+        }
+
+        let mut scaled = scaled.into_iter();
+        let (mut wire, mut coeff) = match scaled.next() {
+            Some(first) => first,
+            None => return (ZERO, S::zero(), constant),
+        };
+        for (next, next_coeff) in scaled {
+            // sum = coeff * wire + next_coeff * next
+            let value = self.values[wire]
+                .zip(self.values[next])
+                .map(|(x, y)| coeff * x + next_coeff * y);
+            self.values.push(value);
+            let sum = self.values.len() - 1;
+
+            self.gates.push(Gate::new(
+                S::zero(),
+                coeff,
+                next_coeff,
+                -S::one(),
+                S::zero(),
+                [wire, next, sum],
+            ));
+            wire = sum;
+            coeff = S::one();
+        }
+
+        (wire, coeff, constant)
+    }
+}
+
+impl<S: PrimeField> Assembly<S> {
+    pub(crate) fn synthesize<C: Circuit<S>>(circuit: C) -> Result<Self, SynthesisError> {
+        let mut recorder = Recorder::new();
+        circuit.synthesize(&mut recorder)?;
+
+        let num_inputs = recorder.inputs.len();
+        let mut builder = Builder {
+            num_inputs,
+            gates: vec![],
+            values: Some(Some(S::zero()))
+                .into_iter()
+                .chain(recorder.inputs.into_iter().skip(1))
+                .chain(recorder.aux)
+                .collect(),
+        };
+
+        // a - x_i = 0
+        for i in 1..num_inputs {
+            builder.gates.push(Gate::new(
+                S::zero(),
+                S::one(),
+                S::zero(),
+                S::zero(),
+                S::zero(),
+                [i, ZERO, ZERO],
+            ));
+        }
+
+        for [a, b, c] in &recorder.constraints {
+            // (alpha * a + k_a) * (beta * b + k_b) = gamma * c + k_c
+            let (a, alpha, k_a) = builder.linearize(a);
+            let (b, beta, k_b) = builder.linearize(b);
+            let (c, gamma, k_c) = builder.linearize(c);
+
+            builder.gates.push(Gate::new(
+                alpha * beta,
+                alpha * k_b,
+                beta * k_a,
+                -gamma,
+                k_a * k_b - k_c,
+                [a, b, c],
+            ));
+        }
+
+        Ok(Assembly {
+            num_public: num_inputs - 1,
+            gates: builder.gates,
+            num_variables: builder.values.len(),
+            values: builder.values.into_iter().collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ConstraintSystem;
+
+    use bls12_381::Scalar;
+
+    struct Example(Option<u64>);
+
+    impl Circuit<Scalar> for Example {
+        fn synthesize<CS: ConstraintSystem<Scalar>>(
+            self,
+            cs: &mut CS,
+        ) -> Result<(), SynthesisError> {
+            let value = |f: fn(u64) -> u64| {
+                self.0
+                    .map(|x| Scalar::from(f(x)))
+                    .ok_or(SynthesisError::AssignmentMissing)
+            };
+            let x = cs.alloc(|| "x", || value(|x| x))?;
+            let y = cs.alloc(|| "y", || value(|x| x + 3))?;
+            let out = cs.alloc_input(|| "out", || value(|x| 2 * x * (x + 3) + 1))?;
+
+            // y = x + 3
+            cs.enforce(
+                || "y",
+                |lc| lc + x + (Scalar::from(3), CS::one()),
+                |lc| lc + CS::one(),
+                |lc| lc + y,
+            );
+            // (x + x) * y = out - 1
+            cs.enforce(
+                || "out",
+                |lc| lc + x + x,
+                |lc| lc + y,
+                |lc| lc + out - CS::one(),
+            );
+            // (2x + y + out) * 0 = 0
+            cs.enforce(
+                || "sum",
+                |lc| lc + (Scalar::from(2), x) + y + out,
+                |lc| lc,
+                |lc| lc,
+            );
+            Ok(())
+        }
+    }
+
+    fn is_satisfied(assembly: &Assembly<Scalar>, public: &[Scalar]) -> bool {
+        let values = assembly.values.as_ref().unwrap();
+        assembly.gates.iter().enumerate().all(|(row, gate)| {
+            let [a, b, c] = gate.wires.map(|w| values[w]);
+            let [q_m, q_l, q_r, q_o, q_c] = gate.selectors;
+            let pi = public.get(row).map_or(Scalar::zero(), |x| -x);
+            q_m * a * b + q_l * a + q_r * b + q_o * c + q_c + pi == Scalar::zero()
+        })
+    }
+
+    #[test]
+    fn translate_r1cs() {
+        let shape = Assembly::synthesize(Example(None)).unwrap();
+        assert!(shape.values.is_none());
+        assert_eq!(shape.num_public, 1);
+        // One public input gate, one gate for each constraint, and two
+        // addition gates for the three terms of the last linear combination.
+        assert_eq!(shape.gates.len(), 1 + 3 + 2);
+        // ZERO, out, x, y and two partial sums.
+        assert_eq!(shape.num_variables, 6);
+
+        let assembly = Assembly::synthesize(Example(Some(5))).unwrap();
+        assert_eq!(assembly.gates, shape.gates);
+        assert!(is_satisfied(&assembly, &[Scalar::from(81)]));
+        assert!(!is_satisfied(&assembly, &[Scalar::from(80)]));
+    }
+}
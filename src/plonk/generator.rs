@@ -0,0 +1,131 @@
+use ff::{Field, PrimeFieldBits};
+use pairing::Engine;
+
+use super::circuit::{Assembly, ZERO};
+use super::{root_of_unity, wire_shifts, ProvingKey, VerifyingKey, QUOTIENT_DOMAIN_FACTOR};
+
+use crate::kzg::Srs;
+use crate::multicore::{Phase, Worker};
+use crate::poly::{coset_fft, ifft};
+use crate::{Circuit, SynthesisError};
+
+/// Derives the keys for a circuit from a universal SRS.
+///
+/// The SRS must support polynomials of degree at least `n + 2`, where `n` is
+/// the number of rows of the circuit: the number of public inputs and gates,
+/// rounded up to a power of two.
+pub fn generate_parameters<E, C>(circuit: C, srs: &Srs<E>) -> Result<ProvingKey<E>, SynthesisError>
+where
+    E: Engine,
+    E::Fr: PrimeFieldBits,
+    C: Circuit<E::Fr>,
+{
+    generate_parameters_with_worker(circuit, srs, &Worker::new())
+}
+
+/// Derives the keys for a circuit from a universal SRS, running the
+/// computation on `worker`.
+pub fn generate_parameters_with_worker<E, C>(
+    circuit: C,
+    srs: &Srs<E>,
+    worker: &Worker,
+) -> Result<ProvingKey<E>, SynthesisError>
+where
+    E: Engine,
+    E::Fr: PrimeFieldBits,
+    C: Circuit<E::Fr>,
+{
+    worker.checkpoint(Phase::Synthesis)?;
+    let assembly = Assembly::synthesize(circuit)?;
+    // The quotient polynomial only fits in the quotient domain from two rows.
+    let n = assembly.gates.len().next_power_of_two().max(2);
+    if srs.max_degree() < n + 2 {
+        return Err(SynthesisError::PolynomialDegreeTooLarge);
+    }
+
+    let omega = root_of_unity::<E::Fr>(n)?;
+
+    worker.checkpoint(Phase::Fft)?;
+
+    // Selector polynomials, which are zero on padding rows.
+    let mut selectors: [Vec<E::Fr>; 5] = Default::default();
+    for (i, selector) in selectors.iter_mut().enumerate() {
+        let mut evals: Vec<_> = assembly.gates.iter().map(|g| g.selectors[i]).collect();
+        evals.resize(n, E::Fr::zero());
+        *selector = ifft(worker, evals)?;
+    }
+
+    // Permutation polynomials. The copies of each variable form a cycle, and
+    // sigma_i(omega^j) is the label of the position that follows (i, j).
+    let wire = |i: usize, row: usize| assembly.gates.get(row).map_or(ZERO, |gate| gate.wires[i]);
+    let mut copies = vec![vec![]; assembly.num_variables];
+    for i in 0..3 {
+        for row in 0..n {
+            copies[wire(i, row)].push((i, row));
+        }
+    }
+
+    let mut omega_powers = Vec::with_capacity(n);
+    let mut power = E::Fr::one();
+    for _ in 0..n {
+        omega_powers.push(power);
+        power *= omega;
+    }
+    let shifts = wire_shifts::<E::Fr>();
+
+    let mut sigma_evals: [Vec<E::Fr>; 3] = Default::default();
+    for evals in sigma_evals.iter_mut() {
+        *evals = vec![E::Fr::zero(); n];
+    }
+    for cycle in copies.iter().filter(|cycle| !cycle.is_empty()) {
+        for (k, &(i, row)) in cycle.iter().enumerate() {
+            let (next_i, next_row) = cycle[(k + 1) % cycle.len()];
+            sigma_evals[i][row] = shifts[next_i] * omega_powers[next_row];
+        }
+    }
+
+    let mut sigmas: [Vec<E::Fr>; 3] = Default::default();
+    for (sigma, evals) in sigmas.iter_mut().zip(&sigma_evals) {
+        *sigma = ifft(worker, evals.clone())?;
+    }
+
+    worker.checkpoint(Phase::Evaluation)?;
+    let commit = |poly: &[E::Fr]| srs.commit(worker, poly);
+    let [q_m, q_l, q_r, q_o, q_c] = &selectors;
+    let [sigma1, sigma2, sigma3] = &sigmas;
+    let vk = VerifyingKey {
+        n,
+        num_public: assembly.num_public,
+        q_m: commit(q_m)?,
+        q_l: commit(q_l)?,
+        q_r: commit(q_r)?,
+        q_o: commit(q_o)?,
+        q_c: commit(q_c)?,
+        sigma1: commit(sigma1)?,
+        sigma2: commit(sigma2)?,
+        sigma3: commit(sigma3)?,
+        g1: srs.g1_powers()[0],
+        g2: srs.g2_powers()[0],
+        tau_g2: srs.g2_powers()[1],
+    };
+
+    worker.checkpoint(Phase::Fft)?;
+    let mut selector_cosets: [Vec<E::Fr>; 5] = Default::default();
+    for (coset, selector) in selector_cosets.iter_mut().zip(&selectors) {
+        *coset = coset_fft(worker, selector, n * QUOTIENT_DOMAIN_FACTOR)?;
+    }
+    let mut sigma_cosets: [Vec<E::Fr>; 3] = Default::default();
+    for (coset, sigma) in sigma_cosets.iter_mut().zip(&sigmas) {
+        *coset = coset_fft(worker, sigma, n * QUOTIENT_DOMAIN_FACTOR)?;
+    }
+
+    Ok(ProvingKey {
+        vk,
+        srs: srs.clone(),
+        selectors,
+        selector_cosets,
+        sigmas,
+        sigma_evals,
+        sigma_cosets,
+    })
+}
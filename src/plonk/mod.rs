@@ -0,0 +1,375 @@
+//! The [PLONK] proving system.
+//!
+//! Unlike [Groth16], PLONK does not need a setup for each circuit. Its keys are
+//! derived from a universal [`Srs`], which can be shared by all circuits up to
+//! some size and strengthened by anyone with [`Srs::update`].
+//!
+//! Circuits are written against [`ConstraintSystem`] as usual. Each R1CS
+//! constraint is translated into a PLONK gate, plus an addition gate for each
+//! term of its linear combinations past the first.
+//!
+//! ```
+//! use bellman::{kzg::Srs, multicore::Worker, plonk, Circuit, ConstraintSystem, SynthesisError};
+//! use bls12_381::{Bls12, Scalar};
+//! use rand_core::OsRng;
+//!
+//! /// Proves knowledge of `x` such that `x^3 + x + 5 = out`.
+//! struct Cube(Option<Scalar>);
+//!
+//! impl Circuit<Scalar> for Cube {
+//!     fn synthesize<CS: ConstraintSystem<Scalar>>(
+//!         self,
+//!         cs: &mut CS,
+//!     ) -> Result<(), SynthesisError> {
+//!         let x_val = self.0;
+//!         let x = cs.alloc(|| "x", || x_val.ok_or(SynthesisError::AssignmentMissing))?;
+//!         let x2_val = x_val.map(|x| x.square());
+//!         let x2 = cs.alloc(|| "x^2", || x2_val.ok_or(SynthesisError::AssignmentMissing))?;
+//!         cs.enforce(|| "x^2", |lc| lc + x, |lc| lc + x, |lc| lc + x2);
+//!         let out = cs.alloc_input(
+//!             || "out",
+//!             || Ok(x2_val.zip(x_val).ok_or(SynthesisError::AssignmentMissing)?)
+//!                 .map(|(x2, x)| x2 * x + x + Scalar::from(5)),
+//!         )?;
+//!         cs.enforce(
+//!             || "out",
+//!             |lc| lc + x2 + CS::one(),
+//!             |lc| lc + x,
+//!             |lc| lc + out - (Scalar::from(5), CS::one()),
+//!         );
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let srs = Srs::<Bls12>::generate_random(&Worker::new(), 64, 1, &mut OsRng);
+//! let pk = plonk::generate_parameters(Cube(None), &srs)?;
+//!
+//! let proof = plonk::create_random_proof(Cube(Some(Scalar::from(3))), &pk, &mut OsRng)?;
+//! assert!(plonk::verify_proof(&pk.vk, &proof, &[Scalar::from(35)]).is_ok());
+//! # Ok::<(), SynthesisError>(())
+//! ```
+//!
+//! [PLONK]: https://eprint.iacr.org/2019/953
+//! [Groth16]: crate::groth16
+//! [`ConstraintSystem`]: crate::ConstraintSystem
+
+use std::io::{self, Read, Write};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use ff::PrimeField;
+use group::{prime::PrimeCurveAffine, GroupEncoding, UncompressedEncoding};
+use pairing::Engine;
+
+use crate::kzg::Srs;
+use crate::SynthesisError;
+
+use self::transcript::Transcript;
+
+#[cfg(test)]
+mod tests;
+
+mod circuit;
+mod generator;
+mod prover;
+mod transcript;
+mod verifier;
+
+pub use self::generator::*;
+pub use self::prover::*;
+pub use self::verifier::*;
+
+/// The blow-up of the coset on which the quotient polynomial is computed.
+const QUOTIENT_DOMAIN_FACTOR: usize = 8;
+
+#[derive(Clone, Debug)]
+pub struct Proof<E: Engine> {
+    /// Commitments to the wire polynomials.
+    pub a: E::G1Affine,
+    pub b: E::G1Affine,
+    pub c: E::G1Affine,
+    /// Commitment to the permutation accumulator.
+    pub z: E::G1Affine,
+    /// Commitments to the three parts of the quotient polynomial.
+    pub t_lo: E::G1Affine,
+    pub t_mid: E::G1Affine,
+    pub t_hi: E::G1Affine,
+    /// Opening proofs at the evaluation challenge `zeta`, and at `zeta * omega`.
+    pub w_zeta: E::G1Affine,
+    pub w_zeta_omega: E::G1Affine,
+
+    /// Evaluations at `zeta` of the wire polynomials, the first two
+    /// permutation polynomials and the linearization polynomial, and of the
+    /// permutation accumulator at `zeta * omega`.
+    pub a_eval: E::Fr,
+    pub b_eval: E::Fr,
+    pub c_eval: E::Fr,
+    pub sigma1_eval: E::Fr,
+    pub sigma2_eval: E::Fr,
+    pub z_omega_eval: E::Fr,
+    pub r_eval: E::Fr,
+}
+
+impl<E: Engine> PartialEq for Proof<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.commitments() == other.commitments() && self.evaluations() == other.evaluations()
+    }
+}
+
+impl<E: Engine> Proof<E> {
+    fn commitments(&self) -> [E::G1Affine; 9] {
+        [
+            self.a,
+            self.b,
+            self.c,
+            self.z,
+            self.t_lo,
+            self.t_mid,
+            self.t_hi,
+            self.w_zeta,
+            self.w_zeta_omega,
+        ]
+    }
+
+    fn evaluations(&self) -> [E::Fr; 7] {
+        [
+            self.a_eval,
+            self.b_eval,
+            self.c_eval,
+            self.sigma1_eval,
+            self.sigma2_eval,
+            self.z_omega_eval,
+            self.r_eval,
+        ]
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for g in self.commitments() {
+            writer.write_all(g.to_bytes().as_ref())?;
+        }
+        for s in self.evaluations() {
+            writer.write_all(s.to_repr().as_ref())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut read_g1 = || -> io::Result<E::G1Affine> {
+            let mut repr = <E::G1Affine as GroupEncoding>::Repr::default();
+            reader.read_exact(repr.as_mut())?;
+
+            Option::from(E::G1Affine::from_bytes(&repr))
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid G1"))
+        };
+        let [a, b, c, z, t_lo, t_mid, t_hi, w_zeta, w_zeta_omega] = [(); 9].map(|_| read_g1());
+
+        let mut read_scalar = || -> io::Result<E::Fr> {
+            let mut repr = <E::Fr as PrimeField>::Repr::default();
+            reader.read_exact(repr.as_mut())?;
+
+            Option::from(E::Fr::from_repr(repr))
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid scalar"))
+        };
+        let [a_eval, b_eval, c_eval, sigma1_eval, sigma2_eval, z_omega_eval, r_eval] =
+            [(); 7].map(|_| read_scalar());
+
+        Ok(Proof {
+            a: a?,
+            b: b?,
+            c: c?,
+            z: z?,
+            t_lo: t_lo?,
+            t_mid: t_mid?,
+            t_hi: t_hi?,
+            w_zeta: w_zeta?,
+            w_zeta_omega: w_zeta_omega?,
+            a_eval: a_eval?,
+            b_eval: b_eval?,
+            c_eval: c_eval?,
+            sigma1_eval: sigma1_eval?,
+            sigma2_eval: sigma2_eval?,
+            z_omega_eval: z_omega_eval?,
+            r_eval: r_eval?,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct VerifyingKey<E: Engine> {
+    /// The number of rows of the circuit, which is a power of two.
+    pub n: usize,
+    /// The number of public inputs, not including the constant `ONE`.
+    pub num_public: usize,
+
+    /// Commitments to the selector polynomials `q_M`, `q_L`, `q_R`, `q_O` and
+    /// `q_C`.
+    pub q_m: E::G1Affine,
+    pub q_l: E::G1Affine,
+    pub q_r: E::G1Affine,
+    pub q_o: E::G1Affine,
+    pub q_c: E::G1Affine,
+    /// Commitments to the permutation polynomials of the three wires.
+    pub sigma1: E::G1Affine,
+    pub sigma2: E::G1Affine,
+    pub sigma3: E::G1Affine,
+
+    /// The generators and `tau` in G2, from the SRS.
+    pub g1: E::G1Affine,
+    pub g2: E::G2Affine,
+    pub tau_g2: E::G2Affine,
+}
+
+impl<E: Engine> PartialEq for VerifyingKey<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.n == other.n
+            && self.num_public == other.num_public
+            && self.commitments() == other.commitments()
+            && self.g2 == other.g2
+            && self.tau_g2 == other.tau_g2
+    }
+}
+
+impl<E: Engine> VerifyingKey<E> {
+    fn commitments(&self) -> [E::G1Affine; 9] {
+        [
+            self.q_m,
+            self.q_l,
+            self.q_r,
+            self.q_o,
+            self.q_c,
+            self.sigma1,
+            self.sigma2,
+            self.sigma3,
+            self.g1,
+        ]
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_u32::<BigEndian>(self.n as u32)?;
+        writer.write_u32::<BigEndian>(self.num_public as u32)?;
+        for g in self.commitments() {
+            writer.write_all(g.to_uncompressed().as_ref())?;
+        }
+        writer.write_all(self.g2.to_uncompressed().as_ref())?;
+        writer.write_all(self.tau_g2.to_uncompressed().as_ref())?;
+
+        Ok(())
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let n = reader.read_u32::<BigEndian>()? as usize;
+        if !n.is_power_of_two() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "number of rows is not a power of two",
+            ));
+        }
+        let num_public = reader.read_u32::<BigEndian>()? as usize;
+
+        let mut read_g1 = || -> io::Result<E::G1Affine> {
+            let mut repr = <E::G1Affine as UncompressedEncoding>::Uncompressed::default();
+            reader.read_exact(repr.as_mut())?;
+
+            Option::from(E::G1Affine::from_uncompressed(&repr))
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid G1"))
+        };
+        let [q_m, q_l, q_r, q_o, q_c, sigma1, sigma2, sigma3, g1] = [(); 9].map(|_| read_g1());
+
+        let mut read_g2 = || -> io::Result<E::G2Affine> {
+            let mut repr = <E::G2Affine as UncompressedEncoding>::Uncompressed::default();
+            reader.read_exact(repr.as_mut())?;
+
+            let affine: E::G2Affine = Option::from(E::G2Affine::from_uncompressed(&repr))
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid G2"))?;
+            if affine.is_identity().into() {
+                Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "point at infinity",
+                ))
+            } else {
+                Ok(affine)
+            }
+        };
+        let [g2, tau_g2] = [(); 2].map(|_| read_g2());
+
+        Ok(VerifyingKey {
+            n,
+            num_public,
+            q_m: q_m?,
+            q_l: q_l?,
+            q_r: q_r?,
+            q_o: q_o?,
+            q_c: q_c?,
+            sigma1: sigma1?,
+            sigma2: sigma2?,
+            sigma3: sigma3?,
+            g1: g1?,
+            g2: g2?,
+            tau_g2: tau_g2?,
+        })
+    }
+}
+
+/// The parameters for proving a particular circuit, derived from a universal
+/// [`Srs`] by [`generate_parameters`].
+#[derive(Clone)]
+pub struct ProvingKey<E: Engine> {
+    pub vk: VerifyingKey<E>,
+    srs: Srs<E>,
+    /// Coefficients of the selector polynomials.
+    selectors: [Vec<E::Fr>; 5],
+    /// Evaluations of the selector polynomials on the quotient coset.
+    selector_cosets: [Vec<E::Fr>; 5],
+    /// Coefficients of the permutation polynomials.
+    sigmas: [Vec<E::Fr>; 3],
+    /// Evaluations of the permutation polynomials on the domain.
+    sigma_evals: [Vec<E::Fr>; 3],
+    /// Evaluations of the permutation polynomials on the quotient coset.
+    sigma_cosets: [Vec<E::Fr>; 3],
+}
+
+impl<E: Engine> ProvingKey<E> {
+    /// Returns the SRS that the key was derived from.
+    pub fn srs(&self) -> &Srs<E> {
+        &self.srs
+    }
+}
+
+/// Starts the transcript of a proof, binding it to the circuit and its public
+/// inputs.
+fn transcript<E: Engine>(vk: &VerifyingKey<E>, public_inputs: &[E::Fr]) -> Transcript {
+    let mut transcript = Transcript::new();
+    transcript.absorb_u64(vk.n as u64);
+    transcript.absorb_u64(public_inputs.len() as u64);
+    for commitment in vk.commitments() {
+        transcript.absorb_point(&commitment);
+    }
+    for input in public_inputs {
+        transcript.absorb_scalar(input);
+    }
+
+    transcript
+}
+
+/// Returns the multipliers `k_i` for which `k_i * omega^j` labels row `j` of
+/// wire `i` in the permutation argument. The cosets `k_i H` of the domain `H`
+/// are disjoint.
+fn wire_shifts<S: PrimeField>() -> [S; 3] {
+    let g = S::multiplicative_generator();
+    [S::one(), g, g.square()]
+}
+
+/// Returns a primitive `n`th root of unity, for `n` a power of two.
+fn root_of_unity<S: PrimeField>(n: usize) -> Result<S, SynthesisError> {
+    let exp = n.trailing_zeros();
+    if exp > S::S {
+        return Err(SynthesisError::PolynomialDegreeTooLarge);
+    }
+
+    let mut omega = S::root_of_unity();
+    for _ in exp..S::S {
+        omega = omega.square();
+    }
+
+    Ok(omega)
+}
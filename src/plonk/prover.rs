@@ -0,0 +1,322 @@
+use ff::{Field, PrimeField, PrimeFieldBits};
+use pairing::Engine;
+use rand_core::RngCore;
+
+use super::circuit::{Assembly, ZERO};
+use super::{root_of_unity, transcript, wire_shifts, Proof, ProvingKey, QUOTIENT_DOMAIN_FACTOR};
+
+use crate::multicore::{Phase, Worker};
+use crate::poly::{batch_invert, coset_fft, evaluate, icoset_fft, ifft};
+use crate::{Circuit, SynthesisError};
+
+/// Adds `blinding(X) * (X^n - 1)` to `poly`, which leaves its values on the
+/// domain unchanged.
+fn blind<S: Field>(poly: &mut Vec<S>, n: usize, blinding: &[S]) {
+    poly.resize(n + blinding.len(), S::zero());
+    for (i, b) in blinding.iter().enumerate() {
+        poly[i] -= b;
+        poly[n + i] += b;
+    }
+}
+
+/// Adds `coeff * poly` to `acc`.
+fn add_scaled<S: Field>(acc: &mut Vec<S>, coeff: S, poly: &[S]) {
+    if acc.len() < poly.len() {
+        acc.resize(poly.len(), S::zero());
+    }
+    for (acc, p) in acc.iter_mut().zip(poly) {
+        *acc += coeff * p;
+    }
+}
+
+pub fn create_random_proof<E, C, R>(
+    circuit: C,
+    pk: &ProvingKey<E>,
+    rng: R,
+) -> Result<Proof<E>, SynthesisError>
+where
+    E: Engine,
+    E::Fr: PrimeFieldBits,
+    C: Circuit<E::Fr>,
+    R: RngCore,
+{
+    create_random_proof_with_worker(circuit, pk, &Worker::new(), rng)
+}
+
+/// Creates a proof like [`create_random_proof`], running the computation on
+/// `worker`.
+pub fn create_random_proof_with_worker<E, C, R>(
+    circuit: C,
+    pk: &ProvingKey<E>,
+    worker: &Worker,
+    mut rng: R,
+) -> Result<Proof<E>, SynthesisError>
+where
+    E: Engine,
+    E::Fr: PrimeFieldBits,
+    C: Circuit<E::Fr>,
+    R: RngCore,
+{
+    worker.checkpoint(Phase::Synthesis)?;
+    let assembly = Assembly::synthesize(circuit)?;
+    let n = pk.vk.n;
+    if assembly.gates.len() > n {
+        return Err(SynthesisError::PolynomialDegreeTooLarge);
+    }
+    let values = assembly.values.ok_or(SynthesisError::AssignmentMissing)?;
+    let public_inputs = &values[1..=assembly.num_public];
+    let blinding: Vec<E::Fr> = (0..9).map(|_| E::Fr::random(&mut rng)).collect();
+
+    let commit = |poly: &[E::Fr]| pk.srs.commit(worker, poly);
+    let one = E::Fr::one();
+    let omega = root_of_unity::<E::Fr>(n)?;
+    let shifts = wire_shifts::<E::Fr>();
+    let [_, k1, k2] = shifts;
+    let mut transcript = transcript(&pk.vk, public_inputs);
+
+    // Round 1: commit to the wire polynomials a, b and c.
+    worker.checkpoint(Phase::Fft)?;
+    let mut wire_evals: [Vec<E::Fr>; 3] = Default::default();
+    let mut wires: [Vec<E::Fr>; 3] = Default::default();
+    for (i, (evals, wire)) in wire_evals.iter_mut().zip(wires.iter_mut()).enumerate() {
+        *evals = (0..n)
+            .map(|row| values[assembly.gates.get(row).map_or(ZERO, |g| g.wires[i])])
+            .collect();
+        *wire = ifft(worker, evals.clone())?;
+        blind(wire, n, &blinding[2 * i..2 * i + 2]);
+    }
+    let [a, b, c] = &wires;
+    let a_commitment = commit(a)?;
+    let b_commitment = commit(b)?;
+    let c_commitment = commit(c)?;
+    transcript.absorb_point(&a_commitment);
+    transcript.absorb_point(&b_commitment);
+    transcript.absorb_point(&c_commitment);
+
+    worker.check_cancelled()?;
+
+    // Round 2: commit to the permutation accumulator z, where z(omega^0) = 1
+    // and z(omega^{j+1}) = z(omega^j) * prod_i (w_i + beta k_i omega^j + gamma)
+    //                                  / (w_i + beta sigma_i(omega^j) + gamma)
+    let beta: E::Fr = transcript.challenge();
+    let gamma: E::Fr = transcript.challenge();
+    let mut numerators = vec![one; n];
+    let mut denominators = vec![one; n];
+    let mut x = one;
+    for row in 0..n {
+        for i in 0..3 {
+            let w = wire_evals[i][row] + gamma;
+            numerators[row] *= w + beta * shifts[i] * x;
+            denominators[row] *= w + beta * pk.sigma_evals[i][row];
+        }
+        x *= omega;
+    }
+    batch_invert(&mut denominators).ok_or(SynthesisError::DivisionByZero)?;
+
+    let mut acc = one;
+    let z_evals = numerators
+        .iter()
+        .zip(&denominators)
+        .map(|(num, den)| {
+            let z = acc;
+            acc *= *num * den;
+            z
+        })
+        .collect();
+    let mut z = ifft(worker, z_evals)?;
+    blind(&mut z, n, &blinding[6..9]);
+    let z_commitment = commit(&z)?;
+    transcript.absorb_point(&z_commitment);
+
+    worker.check_cancelled()?;
+
+    // Round 3: commit to the quotient polynomial
+    //
+    //   t(X) = (gate(X) + alpha perm(X) + alpha^2 (z(X) - 1) L_1(X)) / Z_H(X)
+    //
+    // computed on a coset that is large enough to hold the numerator.
+    let alpha: E::Fr = transcript.challenge();
+    let mut pi_evals = vec![E::Fr::zero(); n];
+    for (pi, input) in pi_evals.iter_mut().zip(public_inputs) {
+        *pi = -*input;
+    }
+    let pi = ifft(worker, pi_evals)?;
+
+    let mut z_omega = z.clone();
+    let mut power = one;
+    for coeff in z_omega.iter_mut() {
+        *coeff *= power;
+        power *= omega;
+    }
+
+    let size = n * QUOTIENT_DOMAIN_FACTOR;
+    let omega_coset = root_of_unity::<E::Fr>(size)?;
+    let coset_shift = E::Fr::multiplicative_generator();
+    let [a_coset, b_coset, c_coset, z_coset, z_omega_coset, pi_coset] =
+        [a, b, c, &z, &z_omega, &pi].map(|poly| coset_fft(worker, poly, size));
+    let (a_coset, b_coset, c_coset) = (a_coset?, b_coset?, c_coset?);
+    let (z_coset, z_omega_coset, pi_coset) = (z_coset?, z_omega_coset?, pi_coset?);
+
+    // Z_H(x) = x^n - 1 only takes QUOTIENT_DOMAIN_FACTOR values on the coset.
+    let zh: Vec<_> = (0..QUOTIENT_DOMAIN_FACTOR)
+        .map(|k| (coset_shift * omega_coset.pow_vartime([k as u64])).pow_vartime([n as u64]) - one)
+        .collect();
+    let mut zh_inv = zh.clone();
+    batch_invert(&mut zh_inv).ok_or(SynthesisError::DivisionByZero)?;
+
+    // L_1(x) = (x^n - 1) / (n (x - 1))
+    let mut l1_coset = Vec::with_capacity(size);
+    let mut x = coset_shift;
+    for _ in 0..size {
+        l1_coset.push(E::Fr::from(n as u64) * (x - one));
+        x *= omega_coset;
+    }
+    batch_invert(&mut l1_coset).ok_or(SynthesisError::DivisionByZero)?;
+    for (i, l1) in l1_coset.iter_mut().enumerate() {
+        *l1 *= zh[i % QUOTIENT_DOMAIN_FACTOR];
+    }
+
+    let mut t = vec![E::Fr::zero(); size];
+    {
+        let [q_m, q_l, q_r, q_o, q_c] = &pk.selector_cosets;
+        let [sigma1, sigma2, sigma3] = &pk.sigma_cosets;
+        let (a, b, c) = (&a_coset, &b_coset, &c_coset);
+        let (z, z_omega, pi, l1) = (&z_coset, &z_omega_coset, &pi_coset, &l1_coset);
+        let zh_inv = &zh_inv;
+
+        worker.scope(size, |scope, chunk| {
+            for (j, t) in t.chunks_mut(chunk).enumerate() {
+                scope.spawn(move |_scope| {
+                    let start = j * chunk;
+                    let mut x = coset_shift * omega_coset.pow_vartime([start as u64]);
+                    for (i, t) in (start..).zip(t.iter_mut()) {
+                        let gate = a[i] * b[i] * q_m[i]
+                            + a[i] * q_l[i]
+                            + b[i] * q_r[i]
+                            + c[i] * q_o[i]
+                            + pi[i]
+                            + q_c[i];
+                        let perm = (a[i] + beta * x + gamma)
+                            * (b[i] + beta * k1 * x + gamma)
+                            * (c[i] + beta * k2 * x + gamma)
+                            * z[i]
+                            - (a[i] + beta * sigma1[i] + gamma)
+                                * (b[i] + beta * sigma2[i] + gamma)
+                                * (c[i] + beta * sigma3[i] + gamma)
+                                * z_omega[i];
+                        let boundary = (z[i] - one) * l1[i];
+
+                        *t = (gate + alpha * (perm + alpha * boundary))
+                            * zh_inv[i % QUOTIENT_DOMAIN_FACTOR];
+                        x *= omega_coset;
+                    }
+                });
+            }
+        });
+    }
+
+    // t has degree at most 3n + 5, and is split into three parts of n + 2
+    // coefficients each.
+    let t = icoset_fft(worker, t)?;
+    debug_assert!(t[3 * (n + 2)..].iter().all(|c| c.is_zero_vartime()));
+    let t_lo = &t[..n + 2];
+    let t_mid = &t[n + 2..2 * (n + 2)];
+    let t_hi = &t[2 * (n + 2)..3 * (n + 2)];
+    let t_lo_commitment = commit(t_lo)?;
+    let t_mid_commitment = commit(t_mid)?;
+    let t_hi_commitment = commit(t_hi)?;
+    transcript.absorb_point(&t_lo_commitment);
+    transcript.absorb_point(&t_mid_commitment);
+    transcript.absorb_point(&t_hi_commitment);
+
+    worker.check_cancelled()?;
+
+    // Round 4: evaluate at a random point zeta, and compute the linearization
+    // polynomial r, whose evaluation at zeta lets the verifier check t(zeta).
+    let zeta: E::Fr = transcript.challenge();
+    let [sigma1, sigma2, sigma3] = &pk.sigmas;
+    let a_eval = evaluate(a, &zeta);
+    let b_eval = evaluate(b, &zeta);
+    let c_eval = evaluate(c, &zeta);
+    let sigma1_eval = evaluate(sigma1, &zeta);
+    let sigma2_eval = evaluate(sigma2, &zeta);
+    let z_omega_eval = evaluate(&z, &(zeta * omega));
+
+    let zh_zeta = zeta.pow_vartime([n as u64]) - one;
+    let l1_zeta = Option::<E::Fr>::from((E::Fr::from(n as u64) * (zeta - one)).invert())
+        .ok_or(SynthesisError::DivisionByZero)?
+        * zh_zeta;
+
+    let mut r = vec![];
+    let [q_m, q_l, q_r, q_o, q_c] = &pk.selectors;
+    add_scaled(&mut r, a_eval * b_eval, q_m);
+    add_scaled(&mut r, a_eval, q_l);
+    add_scaled(&mut r, b_eval, q_r);
+    add_scaled(&mut r, c_eval, q_o);
+    add_scaled(&mut r, one, q_c);
+    add_scaled(
+        &mut r,
+        alpha
+            * (a_eval + beta * zeta + gamma)
+            * (b_eval + beta * k1 * zeta + gamma)
+            * (c_eval + beta * k2 * zeta + gamma)
+            + alpha.square() * l1_zeta,
+        &z,
+    );
+    add_scaled(
+        &mut r,
+        -(alpha
+            * beta
+            * z_omega_eval
+            * (a_eval + beta * sigma1_eval + gamma)
+            * (b_eval + beta * sigma2_eval + gamma)),
+        sigma3,
+    );
+    let r_eval = evaluate(&r, &zeta);
+
+    for eval in [
+        &a_eval,
+        &b_eval,
+        &c_eval,
+        &sigma1_eval,
+        &sigma2_eval,
+        &z_omega_eval,
+        &r_eval,
+    ] {
+        transcript.absorb_scalar(eval);
+    }
+
+    // Round 5: open a random combination of the polynomials at zeta, and z at
+    // zeta * omega.
+    worker.checkpoint(Phase::Multiexp)?;
+    let v: E::Fr = transcript.challenge();
+    let mut opened = t_lo.to_vec();
+    add_scaled(&mut opened, zeta.pow_vartime([(n + 2) as u64]), t_mid);
+    add_scaled(&mut opened, zeta.pow_vartime([(2 * n + 4) as u64]), t_hi);
+    let mut v_power = one;
+    for poly in [&r, a, b, c, sigma1, sigma2] {
+        v_power *= v;
+        add_scaled(&mut opened, v_power, poly);
+    }
+    let (_, w_zeta) = pk.srs.open(worker, &opened, zeta)?;
+    let (_, w_zeta_omega) = pk.srs.open(worker, &z, zeta * omega)?;
+
+    Ok(Proof {
+        a: a_commitment,
+        b: b_commitment,
+        c: c_commitment,
+        z: z_commitment,
+        t_lo: t_lo_commitment,
+        t_mid: t_mid_commitment,
+        t_hi: t_hi_commitment,
+        w_zeta,
+        w_zeta_omega,
+        a_eval,
+        b_eval,
+        c_eval,
+        sigma1_eval,
+        sigma2_eval,
+        z_omega_eval,
+        r_eval,
+    })
+}
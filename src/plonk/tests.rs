@@ -0,0 +1,258 @@
+use bls12_381::{Bls12, Scalar};
+use ff::Field;
+use rand_core::SeedableRng;
+use rand_xorshift::XorShiftRng;
+
+use crate::gadgets::{multieq::MultiEq, multipack, uint32::UInt32};
+use crate::kzg::Srs;
+use crate::multicore::Worker;
+use crate::{Circuit, ConstraintSystem, SynthesisError, VerificationError};
+
+use super::{
+    create_random_proof, create_random_proof_with_worker, generate_parameters,
+    generate_parameters_with_worker, verify_proof, Proof, VerifyingKey,
+};
+
+fn rng() -> XorShiftRng {
+    XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ])
+}
+
+/// Proves knowledge of `x` such that `x^3 + x + 5 = out`.
+struct Cube(Option<Scalar>);
+
+impl Circuit<Scalar> for Cube {
+    fn synthesize<CS: ConstraintSystem<Scalar>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let x_val = self.0;
+        let x = cs.alloc(|| "x", || x_val.ok_or(SynthesisError::AssignmentMissing))?;
+        let x2_val = x_val.map(|x| x.square());
+        let x2 = cs.alloc(|| "x^2", || x2_val.ok_or(SynthesisError::AssignmentMissing))?;
+        cs.enforce(|| "x^2", |lc| lc + x, |lc| lc + x, |lc| lc + x2);
+        let out = cs.alloc_input(
+            || "out",
+            || {
+                x_val
+                    .map(|x| x.cube() + x + Scalar::from(5))
+                    .ok_or(SynthesisError::AssignmentMissing)
+            },
+        )?;
+        cs.enforce(
+            || "out",
+            |lc| lc + x2 + CS::one(),
+            |lc| lc + x,
+            |lc| lc + out - (Scalar::from(5), CS::one()),
+        );
+        Ok(())
+    }
+}
+
+/// Proves knowledge of `a` and `b` such that `(a ^ b) + a = out`, where `out`
+/// is packed into public inputs.
+struct Mix(Option<(u32, u32)>);
+
+impl Circuit<Scalar> for Mix {
+    fn synthesize<CS: ConstraintSystem<Scalar>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let a = UInt32::alloc(cs.namespace(|| "a"), self.0.map(|(a, _)| a))?;
+        let b = UInt32::alloc(cs.namespace(|| "b"), self.0.map(|(_, b)| b))?;
+        let xor = a.xor(cs.namespace(|| "a ^ b"), &b)?;
+        let out = {
+            let mut cs = MultiEq::new(cs.namespace(|| "(a ^ b) + a"));
+            UInt32::addmany(cs.namespace(|| "add"), &[xor, a])?
+        };
+        multipack::pack_into_inputs(cs.namespace(|| "out"), &out.into_bits())
+    }
+}
+
+fn mix_inputs(a: u32, b: u32) -> Vec<Scalar> {
+    let out = (a ^ b).wrapping_add(a);
+    let bits: Vec<_> = (0..32).map(|i| (out >> i) & 1 == 1).collect();
+    multipack::compute_multipacking(&bits)
+}
+
+#[test]
+fn cube() {
+    let mut rng = rng();
+    let srs = Srs::<Bls12>::generate_random(&Worker::new(), 64, 1, &mut rng);
+    let pk = generate_parameters(Cube(None), &srs).unwrap();
+
+    let proof = create_random_proof(Cube(Some(Scalar::from(3))), &pk, &mut rng).unwrap();
+    assert!(verify_proof(&pk.vk, &proof, &[Scalar::from(35)]).is_ok());
+    assert!(matches!(
+        verify_proof(&pk.vk, &proof, &[Scalar::from(36)]),
+        Err(VerificationError::InvalidProof)
+    ));
+    assert!(matches!(
+        verify_proof(&pk.vk, &proof, &[]),
+        Err(VerificationError::InvalidVerifyingKey)
+    ));
+
+    // A witness that does not satisfy the circuit can't be proven.
+    let proof = create_random_proof(Cube(Some(Scalar::from(4))), &pk, &mut rng).unwrap();
+    assert!(verify_proof(&pk.vk, &proof, &[Scalar::from(35)]).is_err());
+}
+
+#[cfg(feature = "multicore")]
+#[test]
+fn setup_with_worker() {
+    let mut rng = rng();
+    let worker = Worker::with_num_threads(2).unwrap();
+    let srs = Srs::<Bls12>::generate_random(&worker, 64, 1, &mut rng);
+    let pk = generate_parameters_with_worker(Cube(None), &srs, &worker).unwrap();
+    assert!(pk.vk == generate_parameters(Cube(None), &srs).unwrap().vk);
+
+    let proof =
+        create_random_proof_with_worker(Cube(Some(Scalar::from(3))), &pk, &worker, &mut rng)
+            .unwrap();
+    assert!(verify_proof(&pk.vk, &proof, &[Scalar::from(35)]).is_ok());
+}
+
+#[test]
+fn cancellation() {
+    use crate::multicore::{CancellationToken, Phase};
+    use std::sync::{Arc, Mutex};
+
+    let mut rng = rng();
+    let srs = Srs::<Bls12>::generate_random(&Worker::new(), 64, 1, &mut rng);
+    let phases = Arc::new(Mutex::new(vec![]));
+    let worker = {
+        let phases = phases.clone();
+        Worker::new().with_progress(move |phase| phases.lock().unwrap().push(phase))
+    };
+
+    let pk = generate_parameters_with_worker(Cube(None), &srs, &worker).unwrap();
+    assert_eq!(
+        phases.lock().unwrap().drain(..).collect::<Vec<_>>(),
+        [Phase::Synthesis, Phase::Fft, Phase::Evaluation, Phase::Fft]
+    );
+    let proof =
+        create_random_proof_with_worker(Cube(Some(Scalar::from(3))), &pk, &worker, &mut rng)
+            .unwrap();
+    assert!(verify_proof(&pk.vk, &proof, &[Scalar::from(35)]).is_ok());
+    assert_eq!(
+        phases.lock().unwrap().drain(..).collect::<Vec<_>>(),
+        [Phase::Synthesis, Phase::Fft, Phase::Multiexp]
+    );
+
+    // Cancelling during a phase stops the computation.
+    for cancel_at in [
+        Phase::Synthesis,
+        Phase::Fft,
+        Phase::Evaluation,
+        Phase::Multiexp,
+    ] {
+        let token = CancellationToken::new();
+        let worker = {
+            let token = token.clone();
+            Worker::new()
+                .with_cancellation(token.clone())
+                .with_progress(move |phase| {
+                    if phase == cancel_at {
+                        token.cancel();
+                    }
+                })
+        };
+        if cancel_at == Phase::Evaluation {
+            assert!(matches!(
+                generate_parameters_with_worker(Cube(None), &srs, &worker),
+                Err(SynthesisError::Cancelled)
+            ));
+        } else {
+            assert!(matches!(
+                create_random_proof_with_worker(
+                    Cube(Some(Scalar::from(3))),
+                    &pk,
+                    &worker,
+                    &mut rng
+                ),
+                Err(SynthesisError::Cancelled)
+            ));
+        }
+        assert!(token.is_cancelled());
+    }
+}
+
+#[test]
+fn tampered_proof() {
+    let mut rng = rng();
+    let srs = Srs::<Bls12>::generate_random(&Worker::new(), 64, 1, &mut rng);
+    let pk = generate_parameters(Cube(None), &srs).unwrap();
+    let proof = create_random_proof(Cube(Some(Scalar::from(3))), &pk, &mut rng).unwrap();
+    let inputs = [Scalar::from(35)];
+
+    let mut tampered = proof.clone();
+    tampered.a_eval += Scalar::one();
+    assert!(verify_proof(&pk.vk, &tampered, &inputs).is_err());
+
+    let mut tampered = proof.clone();
+    tampered.t_hi = proof.t_lo;
+    assert!(verify_proof(&pk.vk, &tampered, &inputs).is_err());
+
+    let mut tampered = proof;
+    tampered.w_zeta_omega = tampered.w_zeta;
+    assert!(verify_proof(&pk.vk, &tampered, &inputs).is_err());
+}
+
+#[test]
+fn gadgets() {
+    let mut rng = rng();
+    let srs = Srs::<Bls12>::generate_random(&Worker::new(), 1024, 1, &mut rng);
+    let pk = generate_parameters(Mix(None), &srs).unwrap();
+
+    let (a, b) = (0xdead_beef, 0x1234_5678);
+    let proof = create_random_proof(Mix(Some((a, b))), &pk, &mut rng).unwrap();
+    assert!(verify_proof(&pk.vk, &proof, &mix_inputs(a, b)).is_ok());
+    assert!(verify_proof(&pk.vk, &proof, &mix_inputs(a, b + 1)).is_err());
+}
+
+#[test]
+fn srs_too_small() {
+    let srs = Srs::<Bls12>::generate_random(&Worker::new(), 4, 1, &mut rng());
+    assert!(matches!(
+        generate_parameters(Cube(None), &srs),
+        Err(SynthesisError::PolynomialDegreeTooLarge)
+    ));
+}
+
+#[test]
+fn updated_srs() {
+    let mut rng = rng();
+    let worker = Worker::new();
+    let mut srs = Srs::<Bls12>::generate_random(&worker, 64, 1, &mut rng);
+    let pk = generate_parameters(Cube(None), &srs).unwrap();
+
+    let previous = srs.clone();
+    let update = srs.update(&worker, &mut rng);
+    assert!(srs.verify_powers(&worker, &mut rng).is_ok());
+    assert!(srs.verify_update(&previous, &update).is_ok());
+    let updated = generate_parameters(Cube(None), &srs).unwrap();
+    assert!(pk.vk != updated.vk);
+
+    let proof = create_random_proof(Cube(Some(Scalar::from(3))), &updated, &mut rng).unwrap();
+    assert!(verify_proof(&updated.vk, &proof, &[Scalar::from(35)]).is_ok());
+    assert!(verify_proof(&pk.vk, &proof, &[Scalar::from(35)]).is_err());
+}
+
+#[test]
+fn serialization() {
+    let mut rng = rng();
+    let srs = Srs::<Bls12>::generate_random(&Worker::new(), 64, 1, &mut rng);
+    let pk = generate_parameters(Cube(None), &srs).unwrap();
+    let proof = create_random_proof(Cube(Some(Scalar::from(3))), &pk, &mut rng).unwrap();
+
+    let mut bytes = vec![];
+    proof.write(&mut bytes).unwrap();
+    assert_eq!(Proof::<Bls12>::read(&bytes[..]).unwrap(), proof);
+    assert!(Proof::<Bls12>::read(&bytes[..bytes.len() - 1]).is_err());
+
+    let mut bytes = vec![];
+    pk.vk.write(&mut bytes).unwrap();
+    let vk = VerifyingKey::<Bls12>::read(&bytes[..]).unwrap();
+    assert!(vk == pk.vk);
+    assert!(verify_proof(&vk, &proof, &[Scalar::from(35)]).is_ok());
+
+    // n must be a power of two.
+    bytes[3] = 3;
+    assert!(VerifyingKey::<Bls12>::read(&bytes[..]).is_err());
+}
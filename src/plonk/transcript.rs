@@ -0,0 +1,49 @@
+//! The Fiat-Shamir transcript for PLONK proofs.
+
+use blake2s_simd::{Params as Blake2sParams, State as Blake2sState};
+use ff::PrimeField;
+use group::GroupEncoding;
+
+pub(crate) struct Transcript {
+    state: Blake2sState,
+}
+
+impl Transcript {
+    pub(crate) fn new() -> Self {
+        Transcript {
+            state: Blake2sParams::new()
+                .hash_length(32)
+                .personal(b"BellPLNK")
+                .to_state(),
+        }
+    }
+
+    pub(crate) fn absorb_u64(&mut self, value: u64) {
+        self.state.update(&value.to_le_bytes());
+    }
+
+    pub(crate) fn absorb_point<G: GroupEncoding>(&mut self, point: &G) {
+        self.state.update(point.to_bytes().as_ref());
+    }
+
+    pub(crate) fn absorb_scalar<S: PrimeField>(&mut self, scalar: &S) {
+        self.state.update(scalar.to_repr().as_ref());
+    }
+
+    /// Derives a challenge from everything absorbed so far, and absorbs it.
+    pub(crate) fn challenge<S: PrimeField>(&mut self) -> S {
+        // Reducing 512 bits makes the bias negligible for fields of up to 384
+        // bits.
+        let mut bytes = [0; 64];
+        for (i, chunk) in bytes.chunks_mut(32).enumerate() {
+            let hash = self.state.clone().update(&[i as u8]).finalize();
+            chunk.copy_from_slice(hash.as_bytes());
+        }
+        self.state.update(&bytes);
+
+        let base = S::from(256);
+        bytes.iter().fold(S::zero(), |acc, byte| {
+            acc * base + S::from(u64::from(*byte))
+        })
+    }
+}
@@ -0,0 +1,146 @@
+use ff::Field;
+use group::{prime::PrimeCurveAffine, Curve, Group};
+use pairing::{MillerLoopResult, MultiMillerLoop};
+
+use super::{root_of_unity, transcript, wire_shifts, Proof, VerifyingKey};
+
+use crate::poly::batch_invert;
+use crate::VerificationError;
+
+pub fn verify_proof<E: MultiMillerLoop>(
+    vk: &VerifyingKey<E>,
+    proof: &Proof<E>,
+    public_inputs: &[E::Fr],
+) -> Result<(), VerificationError> {
+    if public_inputs.len() != vk.num_public {
+        return Err(VerificationError::InvalidVerifyingKey);
+    }
+
+    let n = vk.n;
+    let one = E::Fr::one();
+    let omega = root_of_unity::<E::Fr>(n).map_err(|_| VerificationError::InvalidVerifyingKey)?;
+    let [_, k1, k2] = wire_shifts::<E::Fr>();
+
+    // Replay the challenges.
+    let mut transcript = transcript(vk, public_inputs);
+    transcript.absorb_point(&proof.a);
+    transcript.absorb_point(&proof.b);
+    transcript.absorb_point(&proof.c);
+    let beta: E::Fr = transcript.challenge();
+    let gamma: E::Fr = transcript.challenge();
+    transcript.absorb_point(&proof.z);
+    let alpha: E::Fr = transcript.challenge();
+    transcript.absorb_point(&proof.t_lo);
+    transcript.absorb_point(&proof.t_mid);
+    transcript.absorb_point(&proof.t_hi);
+    let zeta: E::Fr = transcript.challenge();
+    for eval in [
+        &proof.a_eval,
+        &proof.b_eval,
+        &proof.c_eval,
+        &proof.sigma1_eval,
+        &proof.sigma2_eval,
+        &proof.z_omega_eval,
+        &proof.r_eval,
+    ] {
+        transcript.absorb_scalar(eval);
+    }
+    let v: E::Fr = transcript.challenge();
+    transcript.absorb_point(&proof.w_zeta);
+    transcript.absorb_point(&proof.w_zeta_omega);
+    let u: E::Fr = transcript.challenge();
+
+    // L_i(zeta) = omega^i (zeta^n - 1) / (n (zeta - omega^i)) for the public
+    // input rows, and for the first row.
+    let zh = zeta.pow_vartime([n as u64]) - one;
+    let mut omega_powers = Vec::with_capacity(vk.num_public.max(1));
+    let mut power = one;
+    for _ in 0..vk.num_public.max(1) {
+        omega_powers.push(power);
+        power *= omega;
+    }
+    let mut lagrange: Vec<_> = omega_powers
+        .iter()
+        .map(|w| E::Fr::from(n as u64) * (zeta - w))
+        .collect();
+    batch_invert(&mut lagrange).ok_or(VerificationError::InvalidProof)?;
+    for (l, w) in lagrange.iter_mut().zip(&omega_powers) {
+        *l *= *w * zh;
+    }
+    let l1 = lagrange[0];
+    let pi = public_inputs
+        .iter()
+        .zip(&lagrange)
+        .fold(E::Fr::zero(), |acc, (x, l)| acc - *x * l);
+
+    // t(zeta), from the evaluation of the linearization polynomial.
+    let a_term = proof.a_eval + beta * proof.sigma1_eval + gamma;
+    let b_term = proof.b_eval + beta * proof.sigma2_eval + gamma;
+    let zh_inv = Option::<E::Fr>::from(zh.invert()).ok_or(VerificationError::InvalidProof)?;
+    let t_eval = (proof.r_eval + pi
+        - alpha * a_term * b_term * (proof.c_eval + gamma) * proof.z_omega_eval
+        - alpha.square() * l1)
+        * zh_inv;
+
+    // The commitment to the combination of polynomials opened at zeta, with z
+    // opened at zeta * omega folded in with u.
+    let z_coeff = alpha
+        * (proof.a_eval + beta * zeta + gamma)
+        * (proof.b_eval + beta * k1 * zeta + gamma)
+        * (proof.c_eval + beta * k2 * zeta + gamma)
+        + alpha.square() * l1;
+    let sigma3_coeff = -(alpha * beta * proof.z_omega_eval * a_term * b_term);
+    let v2 = v.square();
+    let v3 = v2 * v;
+    let v4 = v3 * v;
+    let v5 = v4 * v;
+    let v6 = v5 * v;
+    let f = [
+        (proof.t_lo, one),
+        (proof.t_mid, zeta.pow_vartime([(n + 2) as u64])),
+        (proof.t_hi, zeta.pow_vartime([(2 * n + 4) as u64])),
+        (vk.q_m, v * proof.a_eval * proof.b_eval),
+        (vk.q_l, v * proof.a_eval),
+        (vk.q_r, v * proof.b_eval),
+        (vk.q_o, v * proof.c_eval),
+        (vk.q_c, v),
+        (proof.z, v * z_coeff + u),
+        (vk.sigma3, v * sigma3_coeff),
+        (proof.a, v2),
+        (proof.b, v3),
+        (proof.c, v4),
+        (vk.sigma1, v5),
+        (vk.sigma2, v6),
+    ]
+    .iter()
+    .fold(E::G1::identity(), |acc, (point, scalar)| {
+        acc + *point * scalar
+    });
+
+    let e = t_eval
+        + v * proof.r_eval
+        + v2 * proof.a_eval
+        + v3 * proof.b_eval
+        + v4 * proof.c_eval
+        + v5 * proof.sigma1_eval
+        + v6 * proof.sigma2_eval
+        + u * proof.z_omega_eval;
+
+    // e(W_zeta + u W_zeta_omega, [tau])
+    //     = e(zeta W_zeta + u zeta omega W_zeta_omega + F - e [1], [1])
+    let w = proof.w_zeta.to_curve() + proof.w_zeta_omega * u;
+    let rhs = proof.w_zeta * zeta + proof.w_zeta_omega * (u * zeta * omega) + f - vk.g1 * e;
+
+    if bool::from(
+        E::multi_miller_loop(&[
+            (&(-w).to_affine(), &vk.tau_g2.into()),
+            (&rhs.to_affine(), &vk.g2.into()),
+        ])
+        .final_exponentiation()
+        .is_identity(),
+    ) {
+        Ok(())
+    } else {
+        Err(VerificationError::InvalidProof)
+    }
+}
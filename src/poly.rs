@@ -0,0 +1,623 @@
+//! Dense univariate polynomials over a prime field.
+//!
+//! [`DensePolynomial`] stores coefficients lowest degree first, and multiplies
+//! large polynomials with FFTs over an [`EvaluationDomain`]. The free functions
+//! in this module evaluate polynomials given in Lagrange form, either at
+//! arbitrary points with precomputed [barycentric weights] or on the points of
+//! an [`EvaluationDomain`], and convert coefficient vectors to and from their
+//! evaluations on a radix-2 domain or one of its cosets.
+//!
+//! ```
+//! use bellman::{multicore::Worker, poly::DensePolynomial};
+//! use bls12_381::Scalar;
+//!
+//! let points = [1, 2, 3].map(Scalar::from);
+//! let values = [2, 5, 10].map(Scalar::from);
+//!
+//! // X^2 + 1
+//! let poly = DensePolynomial::interpolate(&points, &values).unwrap();
+//! assert_eq!(poly.coeffs(), &[1, 0, 1].map(Scalar::from)[..]);
+//!
+//! let square = poly.mul(&Worker::new(), &poly)?;
+//! assert_eq!(square.evaluate(&Scalar::from(3)), Scalar::from(100));
+//!
+//! let (quotient, remainder) = square.divide_by_linear(Scalar::from(3));
+//! assert_eq!(remainder, Scalar::from(100));
+//! assert_eq!(quotient.degree(), Some(3));
+//! # Ok::<(), bellman::SynthesisError>(())
+//! ```
+//!
+//! [barycentric weights]: https://people.maths.ox.ac.uk/trefethen/barycentric.pdf
+
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use ff::PrimeField;
+
+use crate::domain::{EvaluationDomain, Scalar};
+use crate::multicore::Worker;
+use crate::SynthesisError;
+
+/// Polynomials with fewer coefficients than this are multiplied directly
+/// rather than with FFTs.
+const NAIVE_MUL_THRESHOLD: usize = 32;
+
+/// A polynomial given by its coefficients, lowest degree first. Trailing zero
+/// coefficients are removed, so the zero polynomial has no coefficients.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DensePolynomial<S: PrimeField> {
+    coeffs: Vec<S>,
+}
+
+impl<S: PrimeField> DensePolynomial<S> {
+    pub fn new(coeffs: Vec<S>) -> Self {
+        let mut poly = DensePolynomial { coeffs };
+        poly.trim();
+        poly
+    }
+
+    pub fn zero() -> Self {
+        DensePolynomial { coeffs: vec![] }
+    }
+
+    /// Returns `prod_i (X - points[i])`, which vanishes exactly on `points`.
+    pub fn vanishing(points: &[S]) -> Self {
+        let mut coeffs = vec![S::one()];
+        for point in points {
+            // Multiply by (X - point).
+            coeffs.insert(0, S::zero());
+            for i in 0..coeffs.len() - 1 {
+                let term = coeffs[i + 1] * point;
+                coeffs[i] -= term;
+            }
+        }
+
+        DensePolynomial { coeffs }
+    }
+
+    /// Returns the polynomial of degree less than `points.len()` that takes
+    /// `values` at `points`, or `None` if the points are not distinct or the
+    /// lengths differ.
+    pub fn interpolate(points: &[S], values: &[S]) -> Option<Self> {
+        if points.len() != values.len() {
+            return None;
+        }
+
+        let weights = barycentric_weights(points)?;
+        let vanishing = Self::vanishing(points);
+        let mut coeffs = vec![S::zero(); points.len()];
+        for ((point, value), weight) in points.iter().zip(values).zip(weights) {
+            // L_i(X) = w_i Z(X) / (X - x_i)
+            let (basis, _) = vanishing.divide_by_linear(*point);
+            let scale = *value * weight;
+            for (acc, coeff) in coeffs.iter_mut().zip(basis.coeffs) {
+                *acc += coeff * scale;
+            }
+        }
+
+        Some(Self::new(coeffs))
+    }
+
+    pub fn coeffs(&self) -> &[S] {
+        &self.coeffs
+    }
+
+    pub fn into_coeffs(self) -> Vec<S> {
+        self.coeffs
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coeffs.is_empty()
+    }
+
+    /// Returns the degree of the polynomial, or `None` for the zero
+    /// polynomial.
+    pub fn degree(&self) -> Option<usize> {
+        self.coeffs.len().checked_sub(1)
+    }
+
+    pub fn evaluate(&self, point: &S) -> S {
+        evaluate(&self.coeffs, point)
+    }
+
+    /// Multiplies two polynomials, using FFTs unless either is small.
+    pub fn mul(&self, worker: &Worker, other: &Self) -> Result<Self, SynthesisError> {
+        if self.is_zero() || other.is_zero() {
+            return Ok(Self::zero());
+        }
+
+        let len = self.coeffs.len() + other.coeffs.len() - 1;
+        if self.coeffs.len().min(other.coeffs.len()) < NAIVE_MUL_THRESHOLD {
+            let mut coeffs = vec![S::zero(); len];
+            for (i, a) in self.coeffs.iter().enumerate() {
+                for (b, c) in other.coeffs.iter().zip(&mut coeffs[i..]) {
+                    *c += *a * b;
+                }
+            }
+            return Ok(Self::new(coeffs));
+        }
+
+        let to_domain = |coeffs: &[S]| {
+            let mut coeffs: Vec<_> = coeffs.iter().copied().map(Scalar).collect();
+            coeffs.resize(len, Scalar(S::zero()));
+            EvaluationDomain::from_coeffs(coeffs)
+        };
+        let mut a = to_domain(&self.coeffs)?;
+        let mut b = to_domain(&other.coeffs)?;
+        a.fft(worker);
+        b.fft(worker);
+        a.mul_assign(worker, &b);
+        a.ifft(worker);
+
+        Ok(Self::new(
+            a.into_coeffs().into_iter().take(len).map(|s| s.0).collect(),
+        ))
+    }
+
+    /// Divides by `X - point`, returning the quotient and the remainder, which
+    /// is the value of the polynomial at `point`.
+    pub fn divide_by_linear(&self, point: S) -> (Self, S) {
+        let mut quotient = vec![S::zero(); self.coeffs.len().saturating_sub(1)];
+        let mut acc = S::zero();
+        for (i, coeff) in self.coeffs.iter().enumerate().rev() {
+            acc = acc * point + coeff;
+            if i > 0 {
+                quotient[i - 1] = acc;
+            }
+        }
+
+        (DensePolynomial { coeffs: quotient }, acc)
+    }
+
+    /// Divides by `divisor`, returning the quotient and the remainder, or
+    /// `None` if `divisor` is zero.
+    pub fn divide(&self, divisor: &Self) -> Option<(Self, Self)> {
+        let d = divisor.degree()?;
+        if self.coeffs.len() <= d {
+            return Some((Self::zero(), self.clone()));
+        }
+
+        let lead_inv = Option::<S>::from(divisor.coeffs[d].invert())?;
+        let mut remainder = self.coeffs.clone();
+        let mut quotient = vec![S::zero(); self.coeffs.len() - d];
+        for i in (0..quotient.len()).rev() {
+            let coeff = remainder[i + d] * lead_inv;
+            quotient[i] = coeff;
+            for (r, c) in remainder[i..=i + d].iter_mut().zip(&divisor.coeffs) {
+                *r -= coeff * c;
+            }
+        }
+        remainder.truncate(d);
+
+        Some((Self::new(quotient), Self::new(remainder)))
+    }
+
+    /// Divides by `X^n - 1`, the vanishing polynomial of a domain of size `n`,
+    /// returning the quotient and the remainder.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is zero.
+    pub fn divide_by_vanishing(&self, n: usize) -> (Self, Self) {
+        assert!(n > 0);
+        if self.coeffs.len() <= n {
+            return (Self::zero(), self.clone());
+        }
+
+        // c X^i = c X^(i - n) (X^n - 1) + c X^(i - n)
+        let mut remainder = self.coeffs.clone();
+        let mut quotient = vec![S::zero(); self.coeffs.len() - n];
+        for i in (n..self.coeffs.len()).rev() {
+            let coeff = remainder[i];
+            quotient[i - n] += coeff;
+            remainder[i - n] += coeff;
+        }
+        remainder.truncate(n);
+
+        (Self::new(quotient), Self::new(remainder))
+    }
+
+    fn trim(&mut self) {
+        while self.coeffs.last().map_or(false, |c| c.is_zero_vartime()) {
+            self.coeffs.pop();
+        }
+    }
+}
+
+impl<'a, S: PrimeField> AddAssign<&'a DensePolynomial<S>> for DensePolynomial<S> {
+    fn add_assign(&mut self, other: &'a DensePolynomial<S>) {
+        if self.coeffs.len() < other.coeffs.len() {
+            self.coeffs.resize(other.coeffs.len(), S::zero());
+        }
+        for (a, b) in self.coeffs.iter_mut().zip(&other.coeffs) {
+            *a += b;
+        }
+        self.trim();
+    }
+}
+
+impl<'a, S: PrimeField> SubAssign<&'a DensePolynomial<S>> for DensePolynomial<S> {
+    fn sub_assign(&mut self, other: &'a DensePolynomial<S>) {
+        if self.coeffs.len() < other.coeffs.len() {
+            self.coeffs.resize(other.coeffs.len(), S::zero());
+        }
+        for (a, b) in self.coeffs.iter_mut().zip(&other.coeffs) {
+            *a -= b;
+        }
+        self.trim();
+    }
+}
+
+impl<'a, S: PrimeField> Add<&'a DensePolynomial<S>> for &'a DensePolynomial<S> {
+    type Output = DensePolynomial<S>;
+
+    fn add(self, other: &'a DensePolynomial<S>) -> DensePolynomial<S> {
+        let mut result = self.clone();
+        result += other;
+        result
+    }
+}
+
+impl<'a, S: PrimeField> Sub<&'a DensePolynomial<S>> for &'a DensePolynomial<S> {
+    type Output = DensePolynomial<S>;
+
+    fn sub(self, other: &'a DensePolynomial<S>) -> DensePolynomial<S> {
+        let mut result = self.clone();
+        result -= other;
+        result
+    }
+}
+
+impl<S: PrimeField> Neg for DensePolynomial<S> {
+    type Output = DensePolynomial<S>;
+
+    fn neg(mut self) -> DensePolynomial<S> {
+        for coeff in self.coeffs.iter_mut() {
+            *coeff = -*coeff;
+        }
+        self
+    }
+}
+
+impl<S: PrimeField> Mul<S> for &DensePolynomial<S> {
+    type Output = DensePolynomial<S>;
+
+    fn mul(self, scalar: S) -> DensePolynomial<S> {
+        DensePolynomial::new(self.coeffs.iter().map(|c| *c * scalar).collect())
+    }
+}
+
+/// Evaluates the polynomial with coefficients `coeffs`, lowest degree first,
+/// at `point`.
+pub(crate) fn evaluate<S: PrimeField>(coeffs: &[S], point: &S) -> S {
+    coeffs
+        .iter()
+        .rev()
+        .fold(S::zero(), |acc, coeff| acc * point + coeff)
+}
+
+/// Returns the barycentric weights `1 / prod_{j != i} (points[i] - points[j])`
+/// of `points`, or `None` if the points are not distinct.
+pub fn barycentric_weights<S: PrimeField>(points: &[S]) -> Option<Vec<S>> {
+    let mut weights: Vec<_> = points
+        .iter()
+        .enumerate()
+        .map(|(i, point)| {
+            points
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .fold(S::one(), |acc, (_, other)| acc * (*point - other))
+        })
+        .collect();
+    batch_invert(&mut weights)?;
+
+    Some(weights)
+}
+
+/// Evaluates at `x` the polynomial of degree less than `points.len()` that
+/// takes `values` at `points`, where `weights` are the
+/// [`barycentric_weights`] of `points`. This takes `O(n)` operations.
+///
+/// # Panics
+///
+/// Panics if `points`, `weights` and `values` have different lengths.
+pub fn evaluate_barycentric<S: PrimeField>(points: &[S], weights: &[S], values: &[S], x: S) -> S {
+    assert_eq!(points.len(), weights.len());
+    assert_eq!(points.len(), values.len());
+    if let Some(i) = points.iter().position(|point| *point == x) {
+        return values[i];
+    }
+
+    // p(x) = Z(x) sum_i w_i v_i / (x - x_i)
+    let mut diffs: Vec<_> = points.iter().map(|point| x - point).collect();
+    let vanishing = diffs.iter().fold(S::one(), |acc, diff| acc * diff);
+    batch_invert(&mut diffs).expect("x is not one of the points");
+
+    vanishing
+        * diffs
+            .iter()
+            .zip(weights)
+            .zip(values)
+            .fold(S::zero(), |acc, ((diff, w), v)| acc + *diff * w * v)
+}
+
+/// Evaluates at `x` the polynomial whose evaluations on the points of `domain`
+/// are held by `domain`, without interpolating it.
+pub fn evaluate_from_domain<S: PrimeField>(domain: &EvaluationDomain<S, Scalar<S>>, x: S) -> S {
+    let values = domain.as_ref();
+    let n = values.len();
+    let omega = domain.omega();
+
+    let mut omega_powers = Vec::with_capacity(n);
+    let mut power = S::one();
+    for _ in 0..n {
+        omega_powers.push(power);
+        power *= omega;
+    }
+
+    // The barycentric weights of the domain are omega^i / n, and its
+    // vanishing polynomial is X^n - 1.
+    let zh = domain.z(&x);
+    if zh.is_zero_vartime() {
+        let i = omega_powers.iter().position(|w| *w == x).unwrap();
+        return values[i].0;
+    }
+
+    let mut diffs: Vec<_> = omega_powers.iter().map(|w| x - w).collect();
+    batch_invert(&mut diffs).expect("x is not in the domain");
+
+    zh * S::from(n as u64).invert().unwrap()
+        * diffs
+            .iter()
+            .zip(&omega_powers)
+            .zip(values)
+            .fold(S::zero(), |acc, ((diff, w), v)| acc + *diff * w * v.0)
+}
+
+/// Returns the coefficients of the polynomial that takes the values `evals`
+/// on the radix-2 [`EvaluationDomain`] with at least `evals.len()` elements,
+/// where `evals` is padded with zeros to the size of the domain.
+pub fn ifft<S: PrimeField>(worker: &Worker, evals: Vec<S>) -> Result<Vec<S>, SynthesisError> {
+    let mut domain = EvaluationDomain::from_coeffs(evals.into_iter().map(Scalar).collect())?;
+    domain.ifft(worker);
+
+    Ok(domain.into_coeffs().into_iter().map(|s| s.0).collect())
+}
+
+/// Evaluates the polynomial with coefficients `coeffs` on the coset used by
+/// [`EvaluationDomain::coset_fft`] of the radix-2 domain with at least `size`
+/// elements.
+pub fn coset_fft<S: PrimeField>(
+    worker: &Worker,
+    coeffs: &[S],
+    size: usize,
+) -> Result<Vec<S>, SynthesisError> {
+    let mut coeffs: Vec<_> = coeffs.iter().copied().map(Scalar).collect();
+    coeffs.resize(size.max(coeffs.len()), Scalar(S::zero()));
+    let mut domain = EvaluationDomain::from_coeffs(coeffs)?;
+    domain.coset_fft(worker);
+
+    Ok(domain.into_coeffs().into_iter().map(|s| s.0).collect())
+}
+
+/// Returns the coefficients of the polynomial that takes the values `evals`
+/// on the coset of a radix-2 domain, as evaluated by [`coset_fft`].
+pub fn icoset_fft<S: PrimeField>(worker: &Worker, evals: Vec<S>) -> Result<Vec<S>, SynthesisError> {
+    let mut domain = EvaluationDomain::from_coeffs(evals.into_iter().map(Scalar).collect())?;
+    domain.icoset_fft(worker);
+
+    Ok(domain.into_coeffs().into_iter().map(|s| s.0).collect())
+}
+
+/// Inverts every element of `values` with a single field inversion, or
+/// returns `None` if any of them is zero.
+pub fn batch_invert<S: PrimeField>(values: &mut [S]) -> Option<()> {
+    let mut products = Vec::with_capacity(values.len());
+    let mut acc = S::one();
+    for value in values.iter() {
+        products.push(acc);
+        acc *= value;
+    }
+
+    let mut inv = Option::<S>::from(acc.invert())?;
+    for (value, product) in values.iter_mut().zip(products).rev() {
+        let tmp = inv * *value;
+        *value = inv * product;
+        inv = tmp;
+    }
+
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bls12_381::Scalar as Fr;
+    use ff::Field;
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    use crate::domain::{DomainConfig, SmallSubgroup};
+
+    fn rng() -> XorShiftRng {
+        XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ])
+    }
+
+    fn random(rng: &mut XorShiftRng, len: usize) -> Vec<Fr> {
+        (0..len).map(|_| Fr::random(&mut *rng)).collect()
+    }
+
+    #[test]
+    fn arithmetic() {
+        let mut rng = rng();
+        let worker = Worker::new();
+        let x = Fr::random(&mut rng);
+
+        let mut trailing = random(&mut rng, 5);
+        trailing.extend([Fr::zero(); 3]);
+        let poly = DensePolynomial::new(trailing);
+        assert_eq!(poly.degree(), Some(4));
+        assert_eq!((&poly - &poly).degree(), None);
+        assert!(DensePolynomial::<Fr>::zero().is_zero());
+
+        for (len_a, len_b) in [(0, 5), (1, 1), (5, 40), (40, 70), (100, 100)] {
+            let a = DensePolynomial::new(random(&mut rng, len_a));
+            let b = DensePolynomial::new(random(&mut rng, len_b));
+            let (ea, eb) = (a.evaluate(&x), b.evaluate(&x));
+
+            assert_eq!((&a + &b).evaluate(&x), ea + eb);
+            assert_eq!((&a - &b).evaluate(&x), ea - eb);
+            assert_eq!((-a.clone()).evaluate(&x), -ea);
+            assert_eq!((&a * x).evaluate(&x), ea * x);
+
+            let product = a.mul(&worker, &b).unwrap();
+            assert_eq!(product.evaluate(&x), ea * eb);
+            if !a.is_zero() {
+                assert_eq!(product.degree(), Some(len_a + len_b - 2));
+            }
+        }
+    }
+
+    #[test]
+    fn division() {
+        let mut rng = rng();
+        let x = Fr::random(&mut rng);
+        let poly = DensePolynomial::new(random(&mut rng, 20));
+
+        let point = Fr::random(&mut rng);
+        let (quotient, value) = poly.divide_by_linear(point);
+        assert_eq!(value, poly.evaluate(&point));
+        assert_eq!(
+            poly.evaluate(&x),
+            quotient.evaluate(&x) * (x - point) + value
+        );
+
+        let divisor = DensePolynomial::new(random(&mut rng, 7));
+        let (quotient, remainder) = poly.divide(&divisor).unwrap();
+        assert!(remainder.degree() < divisor.degree());
+        assert_eq!(
+            poly.evaluate(&x),
+            quotient.evaluate(&x) * divisor.evaluate(&x) + remainder.evaluate(&x)
+        );
+        assert!(poly.divide(&DensePolynomial::zero()).is_none());
+
+        for n in [1, 4, 8, 32] {
+            let (quotient, remainder) = poly.divide_by_vanishing(n);
+            assert!(remainder.coeffs().len() <= n);
+            assert_eq!(
+                poly.evaluate(&x),
+                quotient.evaluate(&x) * (Field::pow_vartime(&x, [n as u64]) - Fr::one())
+                    + remainder.evaluate(&x)
+            );
+        }
+    }
+
+    #[test]
+    fn domain_conversions() {
+        let mut rng = rng();
+        let worker = Worker::new();
+        let coeffs = random(&mut rng, 13);
+        let poly = DensePolynomial::new(coeffs.clone());
+
+        // The evaluations are padded to a domain of 16 elements.
+        let domain = EvaluationDomain::from_coeffs(vec![Scalar(Fr::zero()); 16]).unwrap();
+        let omega = domain.omega();
+        let evals: Vec<_> = (0..13)
+            .map(|i| poly.evaluate(&Field::pow_vartime(&omega, [i as u64])))
+            .collect();
+        let interpolated = ifft(&worker, evals).unwrap();
+        assert_eq!(interpolated.len(), 16);
+        for i in 0..16 {
+            let point = Field::pow_vartime(&omega, [i as u64]);
+            let expected = if i < 13 {
+                poly.evaluate(&point)
+            } else {
+                Fr::zero()
+            };
+            assert_eq!(evaluate(&interpolated, &point), expected);
+        }
+
+        let coset = coset_fft(&worker, &coeffs, 64).unwrap();
+        assert_eq!(coset.len(), 64);
+        let mut back = icoset_fft(&worker, coset).unwrap();
+        back.truncate(coeffs.len());
+        assert_eq!(back, coeffs);
+
+        let mut values = random(&mut rng, 10);
+        let inverses = {
+            let mut v = values.clone();
+            batch_invert(&mut v).unwrap();
+            v
+        };
+        for (v, inv) in values.iter().zip(&inverses) {
+            assert_eq!(*v * inv, Fr::one());
+        }
+        values[3] = Fr::zero();
+        assert!(batch_invert(&mut values).is_none());
+    }
+    #[test]
+    fn interpolation() {
+        let mut rng = rng();
+        let points = random(&mut rng, 6);
+        let values = random(&mut rng, 6);
+
+        let vanishing = DensePolynomial::vanishing(&points);
+        assert_eq!(vanishing.degree(), Some(6));
+        for point in &points {
+            assert!(vanishing.evaluate(point).is_zero_vartime());
+        }
+
+        let poly = DensePolynomial::interpolate(&points, &values).unwrap();
+        assert!(poly.degree() < Some(6));
+        for (point, value) in points.iter().zip(&values) {
+            assert_eq!(poly.evaluate(point), *value);
+        }
+        assert!(DensePolynomial::interpolate(&[points[0], points[0]], &values[..2]).is_none());
+        assert!(DensePolynomial::interpolate(&points, &values[..5]).is_none());
+
+        let weights = barycentric_weights(&points).unwrap();
+        let x = Fr::random(&mut rng);
+        assert_eq!(
+            evaluate_barycentric(&points, &weights, &values, x),
+            poly.evaluate(&x)
+        );
+        assert_eq!(
+            evaluate_barycentric(&points, &weights, &values, points[2]),
+            values[2]
+        );
+    }
+
+    #[test]
+    fn domain_evaluation() {
+        let mut rng = rng();
+        let worker = Worker::new();
+        let x = Fr::random(&mut rng);
+
+        // A mixed-radix domain of size 48, using a primitive cube root of unity.
+        let cube_root =
+            ((-Fr::from(3)).sqrt().unwrap() - Fr::one()) * Fr::from(2).invert().unwrap();
+        let mixed = DomainConfig {
+            subgroup: SmallSubgroup::new(3, 1, cube_root),
+            ..Default::default()
+        };
+
+        for (config, len) in [(DomainConfig::default(), 16), (mixed, 40)] {
+            let coeffs = random(&mut rng, len);
+            let poly = DensePolynomial::new(coeffs.clone());
+            let mut domain = EvaluationDomain::from_coeffs_with_config(
+                coeffs.into_iter().map(Scalar).collect(),
+                config,
+            )
+            .unwrap();
+            domain.fft(&worker);
+
+            assert_eq!(evaluate_from_domain(&domain, x), poly.evaluate(&x));
+            let point = Field::pow_vartime(&domain.omega(), [3]);
+            assert_eq!(evaluate_from_domain(&domain, point), domain.as_ref()[3].0);
+        }
+    }
+}